@@ -3,16 +3,37 @@ use std::fs::{self, File};
 use std::io::{self, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 use syn;
 use syn::visit::Visit; // Import for the Visit trait
 use walkdir::WalkDir;
 use rayon::prelude::*;
 
-use crate::visitors::{FunctionVisitor, CallVisitor};
-use crate::models::{FileAnalysisResult, PathNodeInfo};
+use crate::visitors::{FunctionVisitor, CallVisitor, MacroExpander};
+use crate::models::{CfgRegion, Confidence, CrateTarget, FileAnalysisResult, ModuleFile, TargetKind, FileExtraction, MacroDefinition, MacroScope, PathNodeInfo, SourceSpan, TypeDefinition, TypeHint, UnsafeDeclaration, UnsafeOperation};
 use crate::analysis::CallGraph;
+use crate::analysis::cache::ExtractionCache;
+use crate::analysis::candidates;
+use crate::analysis::module_tree;
+use crate::analysis::public_api::PublicApi;
+use crate::analysis::source_map::SourceMap;
+use crate::analysis::type_resolver::TypeResolver;
+use crate::analysis::workspace::{Package, Workspace};
+use crate::config::{AnalysisConfig, PathPatterns};
+use crate::report::{self, OutputFormat};
+use crate::report::baseline::{Baseline, BaselineDiff};
 use crate::utils;
+use crate::visitors::{cfg, macros, scope};
+use crate::visitors::cfg::{CfgMode, CfgSet};
+
+/// How much progress information the analyzer prints; errors are always reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,   // 只输出错误
+    Normal,  // 输出进度和结果摘要
+    Verbose, // 额外输出每个文件和调用图的详细信息
+}
 
 /// Static analyzer for Rust code
 pub struct StaticAnalyzer {
@@ -20,6 +41,16 @@ pub struct StaticAnalyzer {
     max_search_depth: usize,
     file_size_limit: u64,
     timeout: Duration,
+    verbosity: Verbosity,
+    include_globs: PathPatterns, // 只分析匹配的文件（相对于分析目录）
+    exclude_globs: PathPatterns, // 跳过匹配的文件
+    entry_point_includes: PathPatterns, // 额外作为入口点的函数
+    entry_point_excludes: PathPatterns, // 不作为入口点的函数
+    include_binaries: bool, // 是否也分析二进制目标
+    cfg_set: CfgSet, // 求值 #[cfg] 的目标平台和选项，特性按crate加入
+    cache: Option<ExtractionCache>, // 文件提取结果的磁盘缓存
+    failed_files: AtomicUsize, // 无法读取、解析或分析的文件数
+    config: AnalysisConfig,
 }
 
 impl StaticAnalyzer {
@@ -29,9 +60,83 @@ impl StaticAnalyzer {
             max_search_depth: max_depth,
             file_size_limit: file_size_limit_mb * 1024 * 1024,
             timeout: Duration::from_secs(timeout_seconds),
+            verbosity: Verbosity::Normal,
+            include_globs: PathPatterns::default(),
+            exclude_globs: PathPatterns::default(),
+            entry_point_includes: PathPatterns::default(),
+            entry_point_excludes: PathPatterns::default(),
+            include_binaries: false,
+            cfg_set: CfgSet::host(),
+            cache: None,
+            failed_files: AtomicUsize::new(0),
+            config: AnalysisConfig::default(),
         }
     }
-
+    
+    /// Set how much progress information is printed
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+    
+    /// Restrict directory analysis with include/exclude globs, matched against
+    /// paths relative to the analyzed directory (e.g. `src/generated/**`)
+    pub fn with_file_filters(mut self, include: &[String], exclude: &[String]) -> io::Result<Self> {
+        self.include_globs = PathPatterns::new(include)?;
+        self.exclude_globs = PathPatterns::new(exclude)?;
+        Ok(self)
+    }
+    
+    /// Also analyze binary targets of Cargo packages; only library targets are analyzed by default
+    pub fn with_binaries(mut self, include_binaries: bool) -> Self {
+        self.include_binaries = include_binaries;
+        self
+    }
+    
+    /// Apply project settings: file filters, entry point rules, ignored modules,
+    /// extra unsafe functions/keywords and the `#[cfg]` configuration. Depth, size and
+    /// timeout limits are passed to `new` so that command-line values can take precedence.
+    pub fn with_config(mut self, config: AnalysisConfig) -> io::Result<Self> {
+        self = self.with_file_filters(&config.include, &config.exclude)?;
+        self.entry_point_includes = PathPatterns::new(&config.entry_points.include)?;
+        self.entry_point_excludes = PathPatterns::new(&config.entry_points.exclude)?;
+        self.cfg_set = config.cfg.cfg_set()?;
+        self.config = config;
+        Ok(self)
+    }
+    
+    /// Reuse the file extractions cached under `dir` by earlier runs, so that only changed
+    /// files are parsed again. Call after `with_config`: cached entries are only used with
+    /// the configuration and the size and time limits they were extracted with.
+    pub fn with_cache(mut self, dir: &Path) -> Self {
+        match ExtractionCache::open(dir, &self.config, self.file_size_limit, self.timeout) {
+            Ok(cache) => self.cache = Some(cache),
+            Err(e) => eprintln!("Error opening cache {}: {}, analyzing without cache", dir.display(), e),
+        }
+        self
+    }
+    
+    /// How `#[cfg]` is evaluated for a crate: against the configured target and options with
+    /// the features enabled for its package, or only the requested features without a manifest
+    fn crate_cfg(&self, package: Option<&Package>) -> CfgMode {
+        let cfg = &self.config.cfg;
+        if cfg.all_configurations {
+            return CfgMode::All;
+        }
+        let features = match package {
+            Some(package) => package.enabled_features(&cfg.features, !cfg.no_default_features),
+            None => cfg.features.iter().filter(|feature| !feature.contains('/')).cloned().collect(),
+        };
+        CfgMode::Active(self.cfg_set.clone().with_features(features))
+    }
+    
+    /// Print a progress message if the verbosity allows it
+    fn log(&self, level: Verbosity, message: &str) {
+        if self.verbosity >= level {
+            println!("{}", message);
+        }
+    }
+    
     /// Quick check if file might contain code that needs analysis
     pub fn should_analyze_file(&self, file_path: &Path) -> io::Result<bool> {
         // Check file size
@@ -43,14 +148,18 @@ impl StaticAnalyzer {
         // Read file content
         let content = fs::read_to_string(file_path)?;
         
-        // If file doesn't contain unsafe or pub fn, can skip
-        if !content.contains("unsafe") || !content.contains("pub fn") {
+        // If file doesn't contain unsafe, or neither an entry point nor unsafe impl/trait/global_asm!, can skip
+        if !content.contains("unsafe") && !content.contains("global_asm") {
+            return Ok(false);
+        }
+        let markers = ["pub fn", "no_mangle", "export_name", "unsafe impl", "unsafe trait", "global_asm"];
+        if !markers.iter().any(|marker| content.contains(marker)) {
             return Ok(false);
         }
         
         Ok(true)
     }
-
+    
     /// Analyze a single file
     pub fn analyze_file(&self, file_path: &Path) -> io::Result<Option<FileAnalysisResult>> {
         // Quick check if file needs analysis
@@ -58,6 +167,57 @@ impl StaticAnalyzer {
             return Ok(None);
         }
         
+        // 单文件模式不知道crate根，函数路径只包含文件内的内联模块
+        let module = ModuleFile {
+            file_path: file_path.to_path_buf(),
+            module_path: Vec::new(),
+            owns_directory: true,
+            cfg: Vec::new(),
+            macros: Vec::new(),
+            macro_use: false,
+        };
+        let extraction = match self.extract_file(&module, &[], &self.crate_cfg(None))? {
+            Some(extraction) => extraction,
+            None => return Ok(None),
+        };
+        
+        // Create call graph and analyze
+        let call_graph = self.build_call_graph(std::slice::from_ref(&extraction));
+        
+        // Find paths, now returns paths with detailed function info
+        let paths = call_graph.find_paths_to_unsafe();
+        let unsafe_declarations = self.collect_unsafe_declarations(&call_graph, std::slice::from_ref(&extraction));
+        
+        if paths.is_empty() && unsafe_declarations.is_empty() {
+            return Ok(None);
+        }
+        
+        let type_definitions = Self::collect_path_type_definitions(&paths, &extraction.type_definitions);
+        
+        Ok(Some(FileAnalysisResult {
+            target: None,
+            file_path: extraction.file_path,
+            paths,
+            type_definitions,
+            unsafe_declarations,
+        }))
+    }
+    
+    /// Parse a file and collect its functions, calls, type definitions and
+    /// submodule files under the file's crate-rooted module path. Invocations of the
+    /// file's own `macro_rules!` macros and of `macros` defined elsewhere in the crate
+    /// are expanded before the visitors run; code that is not compiled in the `cfg_mode`
+    /// configuration is removed before and after expansion.
+    pub fn extract_file(&self, module: &ModuleFile, macros: &[MacroDefinition], cfg_mode: &CfgMode) -> io::Result<Option<FileExtraction>> {
+        let file_path = module.file_path.as_path();
+        let module_path = module.module_path.clone();
+        
+        // Check file size
+        let metadata = fs::metadata(file_path)?;
+        if metadata.len() > self.file_size_limit {
+            return Ok(None);
+        }
+        
         let start_time = Instant::now();
         
         // Read file content, add more error handling
@@ -65,25 +225,55 @@ impl StaticAnalyzer {
             Ok(content) => content,
             Err(e) => {
                 eprintln!("Error reading file {}: {}", file_path.display(), e);
+                self.failed_files.fetch_add(1, Ordering::Relaxed);
                 return Ok(None); // Return None instead of error for reading errors
             }
         };
         
         // Parse source code
-        let syntax = match syn::parse_file(&source) {
+        let mut syntax = match syn::parse_file(&source) {
             Ok(parsed) => parsed,
             Err(e) => {
                 eprintln!("Error parsing file {}: {}", file_path.display(), e);
+                self.failed_files.fetch_add(1, Ordering::Relaxed);
                 return Ok(None); // Return parsing errors as None, not error
             }
         };
         
+        // 不编译的代码中的宏定义同样无效，先删除再收集
+        if let CfgMode::Active(cfg_set) = cfg_mode {
+            cfg::strip_inactive(&mut syntax, cfg_set);
+        }
+        
+        // 展开本crate的声明宏，文件自身的定义覆盖同名的外部定义
+        let file_macros = macros::collect_definitions(&syntax);
+        let mut expander = MacroExpander::new(macros.iter().chain(&file_macros));
+        expander.expand_file(&mut syntax);
+        
+        // 展开生成的代码也可能带有 #[cfg]；全部配置模式下记录各段代码存在的条件
+        let (file_conditions, cfg_regions) = match cfg_mode {
+            CfgMode::Active(cfg_set) => {
+                if !expander.expansions.is_empty() {
+                    cfg::strip_inactive(&mut syntax, cfg_set);
+                }
+                (Vec::new(), Vec::new())
+            },
+            CfgMode::All => {
+                let mut conditions = module.cfg.clone();
+                conditions.extend(cfg::conditions(&syntax.attrs));
+                (conditions, cfg::collect_regions(&syntax))
+            },
+        };
+        
         // Collect function information
         let file_path_str = file_path.to_string_lossy().to_string();
         
         // Use defensive programming to catch possible panics
         let fn_visitor_result = std::panic::catch_unwind(|| {
-            let mut fn_visitor = FunctionVisitor::new(file_path_str.clone(), source.clone());
+            let mut fn_visitor = FunctionVisitor::new(file_path_str.clone(), source.clone())
+                .with_module_path(module_path.clone())
+                .with_known_unsafe_functions(&self.config.known_unsafe_functions)
+                .with_unsafe_keywords(&self.config.unsafe_keywords);
             fn_visitor.visit_file(&syntax);
             fn_visitor
         });
@@ -92,13 +282,14 @@ impl StaticAnalyzer {
             Ok(visitor) => visitor,
             Err(_) => {
                 eprintln!("Function visitor crashed while processing file {}", file_path.display());
+                self.failed_files.fetch_add(1, Ordering::Relaxed);
                 return Ok(None);
             }
         };
         
         // Same defensive handling for call visitor
         let call_visitor_result = std::panic::catch_unwind(|| {
-            let mut call_visitor = CallVisitor::new();
+            let mut call_visitor = CallVisitor::new().with_module_path(module_path.clone());
             call_visitor.visit_file(&syntax);
             call_visitor
         });
@@ -107,6 +298,7 @@ impl StaticAnalyzer {
             Ok(visitor) => visitor,
             Err(_) => {
                 eprintln!("Call visitor crashed while processing file {}", file_path.display());
+                self.failed_files.fetch_add(1, Ordering::Relaxed);
                 return Ok(None);
             }
         };
@@ -114,31 +306,173 @@ impl StaticAnalyzer {
         // Check timeout
         if start_time.elapsed() >= self.timeout {
             eprintln!("Analysis timeout: {}", file_path.display());
+            self.failed_files.fetch_add(1, Ordering::Relaxed);
             return Ok(None);
         }
-
-        // Create call graph and analyze
+        
+        let declared = module_tree::declared_modules(module, &syntax);
+        
+        Ok(Some(FileExtraction {
+            file_path: file_path_str,
+            module_path,
+            functions: fn_visitor.functions,
+            calls: call_visitor.calls,
+            type_definitions: fn_visitor.type_definitions,
+            traits: fn_visitor.traits,
+            imports: call_visitor.imports,
+            modules: fn_visitor.modules,
+            submodules: declared.files,
+            missing_modules: declared.missing,
+            unsafe_declarations: fn_visitor.unsafe_declarations,
+            mutable_statics: fn_visitor.mutable_statics,
+            candidate_operations: call_visitor.candidate_operations,
+            foreign_items: fn_visitor.foreign_items,
+            macros: declared.macros,
+            macro_expansions: expander.expansions,
+            cfg: file_conditions,
+            cfg_regions,
+        }))
+    }
+    
+    /// Merge the extractions of one or more files into a single call graph
+    pub fn build_call_graph(&self, extractions: &[FileExtraction]) -> CallGraph {
         let mut call_graph = CallGraph::new(self.max_search_depth);
         
-        // Add functions and call relationships
-        for (path, info) in fn_visitor.functions {
-            call_graph.add_function(path, info);
+        // Add functions first so that calls across files connect to known nodes
+        for extraction in extractions {
+            for (path, info) in &extraction.functions {
+                if !self.config.is_ignored_module(path) {
+                    call_graph.add_function(path.clone(), info.clone());
+                }
+            }
+            for (type_path, def) in &extraction.type_definitions {
+                call_graph.add_type_definition(type_path.clone(), def.clone());
+            }
+            for (trait_path, def) in &extraction.traits {
+                call_graph.add_trait(trait_path.clone(), def.clone());
+            }
+            for (module_path, imports) in &extraction.imports {
+                call_graph.add_imports(module_path.clone(), imports);
+            }
+            for (module_path, visibility) in &extraction.modules {
+                call_graph.add_module(module_path.clone(), visibility.clone());
+            }
+            call_graph.mutable_statics.extend(extraction.mutable_statics.iter().cloned());
+            call_graph.foreign_items.extend(extraction.foreign_items.iter().map(|(path, item)| (path.clone(), item.clone())));
         }
         
-        for call in call_visitor.calls {
-            call_graph.add_call(call.caller, call.callee);
+        // 方法调用按接收者类型解析，需要整个crate的类型定义和函数签名
+        let mut edges = Vec::new();
+        let mut unresolved = Vec::new();
+        {
+            let resolver = TypeResolver::new(&call_graph);
+            for extraction in extractions {
+                for call in &extraction.calls {
+                    // 忽略模块中的函数既不作为调用者也不作为被调用者
+                    if self.config.is_ignored_module(&call.caller) {
+                        continue;
+                    }
+                    // trait方法调用保守地连接到所有实现，并标记为动态分发
+                    let dispatch = match &call.receiver {
+                        None => resolver.resolve_trait_call(&call.callee),
+                        Some(TypeHint::Bounds(traits)) => resolver.resolve_dispatch(traits, &call.callee),
+                        Some(_) => None,
+                    };
+                    let (callees, via_trait) = match (dispatch, &call.receiver) {
+                        (Some((trait_name, methods)), _) => (methods, Some(trait_name)),
+                        (None, None) => (call_graph.resolve_callee(&call.callee), None),
+                        (None, Some(receiver)) => match resolver.resolve_method_call(receiver, &call.callee) {
+                            Some(methods) => (methods, None),
+                            None => {
+                                // 只标记可能指向本crate方法的调用，标准库等外部方法不关心
+                                if call_graph.method_names.contains(&call.callee) {
+                                    unresolved.push((call.caller.clone(), call.callee.clone()));
+                                }
+                                continue;
+                            }
+                        },
+                    };
+                    for callee in callees {
+                        if !self.config.is_ignored_module(&callee) {
+                            edges.push((call.caller.clone(), callee, via_trait.clone()));
+                        }
+                    }
+                }
+            
+            }
         }
         
-        // Find paths, now returns paths with detailed function info
-        let paths = call_graph.find_paths_to_unsafe();
+        for (caller, callee, via_trait) in edges {
+            match via_trait {
+                Some(trait_name) => call_graph.add_dispatch_call(caller, callee, trait_name),
+                None => call_graph.add_call(caller, callee),
+            }
+        }
+        // unsafe fn、联合体、static mut 和 extern 块可以定义在其他文件中，按整个crate确认
+        candidates::apply_candidate_operations(&mut call_graph, extractions);
         
-        if paths.is_empty() {
-            return Ok(None);
+        // 展开生成的代码都带有宏调用的位置，据此标注操作来自哪个宏
+        let invocations: HashMap<(String, SourceSpan), String> = extractions.iter()
+            .flat_map(|extraction| extraction.macro_expansions.iter()
+                .filter_map(|expansion| Some(((extraction.file_path.clone(), expansion.span?), expansion.name.clone()))))
+            .collect();
+        call_graph.set_macro_origins(&invocations);
+        
+        // 全部配置模式下标注函数和操作存在的 #[cfg] 条件
+        let conditional_files: HashMap<&str, (&[String], &[CfgRegion])> = extractions.iter()
+            .filter(|extraction| !extraction.cfg.is_empty() || !extraction.cfg_regions.is_empty())
+            .map(|extraction| (extraction.file_path.as_str(), (extraction.cfg.as_slice(), extraction.cfg_regions.as_slice())))
+            .collect();
+        call_graph.set_cfg_conditions(&conditional_files);
+        
+        let unresolved_count = unresolved.len();
+        for (caller, method) in unresolved {
+            call_graph.add_unresolved_call(caller, method);
+        }
+        if unresolved_count > 0 {
+            self.log(Verbosity::Verbose, &format!("{} method calls with unknown receiver type", unresolved_count));
         }
         
-        // Find relevant type definitions - FIXED VERSION
-        let mut path_type_defs = std::collections::HashMap::new();
-        for path in &paths {
+        // pub函数只有在从crate根可以访问时才是公共API
+        let public_api = PublicApi::compute(&call_graph).public_functions(&call_graph);
+        let hidden = call_graph.public_functions.len().saturating_sub(public_api.len());
+        call_graph.restrict_to_public_api(&public_api);
+        if hidden > 0 {
+            self.log(Verbosity::Verbose, &format!("{} pub functions are not reachable from the crate root", hidden));
+        }
+        
+        call_graph.apply_entry_point_rules(&self.entry_point_includes, &self.entry_point_excludes);
+        call_graph
+    }
+    
+    /// The `unsafe impl` and `unsafe trait` declarations of the extracted files, outside ignored
+    /// modules, with the definition of the implementing type attached when it is a crate type
+    pub fn collect_unsafe_declarations(&self, call_graph: &CallGraph, extractions: &[FileExtraction]) -> Vec<UnsafeDeclaration> {
+        let resolver = TypeResolver::new(call_graph);
+        let mut declarations: Vec<UnsafeDeclaration> = extractions.iter()
+            .flat_map(|extraction| extraction.unsafe_declarations.iter())
+            .filter(|declaration| !self.config.is_ignored_module(&declaration.module_path))
+            .cloned()
+            .map(|mut declaration| {
+                if let Some(type_name) = &declaration.self_type_name {
+                    declaration.type_definition = resolver.resolve_type(type_name, &declaration.module_path)
+                        .and_then(|type_path| call_graph.type_definitions.get(&type_path))
+                        .cloned();
+                }
+                declaration
+            })
+            .collect();
+        declarations.sort_by_key(|declaration| (declaration.file_path.clone(), declaration.span.map(|span| (span.start_line, span.start_column))));
+        declarations
+    }
+    
+    /// Find type definitions related to the entry points of the given paths
+    pub fn collect_path_type_definitions(
+        paths: &[Vec<PathNodeInfo>],
+        type_definitions: &HashMap<String, TypeDefinition>,
+    ) -> HashMap<String, TypeDefinition> {
+        let mut path_type_defs = HashMap::new();
+        for path in paths {
             if path.is_empty() {
                 continue;
             }
@@ -147,10 +481,8 @@ impl StaticAnalyzer {
             let first_node = &path[0];
             let param_types = &first_node.param_custom_types;
             
-            // We don't need this variable, so removed it to fix the warning
-            
             for type_name in param_types {
-                for (type_path, def) in &fn_visitor.type_definitions {
+                for (type_path, def) in type_definitions {
                     let def_name = type_path.split("::").last().unwrap_or(type_path);
                     if def_name == *type_name {
                         // Get or create type definition for this path
@@ -240,82 +572,366 @@ impl StaticAnalyzer {
             }
         }
         
-        Ok(Some(FileAnalysisResult {
-            file_path: file_path_str,
-            paths,
-            type_definitions: path_type_defs,
-        }))
+        path_type_defs
     }
-
-    /// Parallel analyze directory
+    
+    /// Parallel analyze a directory. A Cargo package or workspace is analyzed per
+    /// library target (and binary target if enabled), anything else as one crate.
     pub fn analyze_directory_parallel(&self, dir_path: &Path) -> io::Result<()> {
         let start_time = Instant::now();
         
-        // Collect all Rust file paths
+        let workspace = match Workspace::load(dir_path) {
+            Ok(workspace) => workspace.filter(|w| !w.packages.is_empty()),
+            Err(e) => {
+                eprintln!("{}, analyzing directory without Cargo metadata", e);
+                None
+            }
+        };
+        
+        let (total_files, error_count) = match workspace {
+            Some(workspace) => self.analyze_workspace(dir_path, &workspace),
+            None => {
+                let roots = self.loose_crate_roots(dir_path)?;
+                self.analyze_crate(dir_path, roots, None, &self.crate_cfg(None))
+            }
+        };
+        
+        if let Some(cache) = &self.cache {
+            self.log(Verbosity::Normal, &format!("Cache: {} files reused, {} files parsed", cache.hits(), cache.misses()));
+        }
+        self.log(Verbosity::Normal, &format!("Analysis complete! Processed {} files, {} files had errors, Time: {:?}", 
+                 total_files, error_count, start_time.elapsed()));
+        
+        Ok(())
+    }
+    
+    /// Analyze a crate that was expanded into a single file, e.g. by `cargo expand` or
+    /// `rustc -Zunpretty=expanded`, so that code generated by derives and procedural macros is
+    /// analyzed as well. When the original sources are given, findings are mapped back to
+    /// the files they were written in.
+    pub fn analyze_expanded(&self, expanded_path: &Path, source_dir: Option<&Path>) -> io::Result<()> {
+        let start_time = Instant::now();
+        
+        // 展开后的文件就是整个crate，其中的模块都是内联模块
+        let Some(extraction) = self.extract_file(&ModuleFile::crate_root(expanded_path), &[], &self.crate_cfg(None))? else {
+            return Ok(());
+        };
+        let extractions = [extraction];
+        let call_graph = self.build_call_graph(&extractions);
+        self.log(Verbosity::Verbose, &format!("Call graph: {} functions, {} with internal unsafe, {} entry points", 
+                 call_graph.functions.len(), call_graph.unsafe_functions.len(), call_graph.entry_points.len()));
+        let mut paths = call_graph.find_paths_to_unsafe();
+        let mut unsafe_declarations = self.collect_unsafe_declarations(&call_graph, &extractions);
+        let mut type_definitions = call_graph.type_definitions.clone();
+        
+        if let Some(source_dir) = source_dir {
+            // 展开结果只对应一种配置，保留原始代码的所有配置以便都能对应上
+            let roots = self.loose_crate_roots(source_dir)?;
+            let (originals, total_files, _) = self.extract_crate(source_dir, roots, &CfgMode::All);
+            self.log(Verbosity::Normal, &format!("Mapping findings to {} source files in {}", total_files, source_dir.display()));
+            
+            let source_map = SourceMap::new(originals);
+            for node in paths.iter_mut().flatten() {
+                source_map.map_node(node);
+            }
+            for declaration in &mut unsafe_declarations {
+                source_map.map_declaration(declaration);
+            }
+            for (path, definition) in &mut type_definitions {
+                source_map.map_type_definition(path, definition);
+            }
+        }
+        
+        self.add_crate_results(paths, unsafe_declarations, &type_definitions, None);
+        self.log(Verbosity::Normal, &format!("Analysis complete! Time: {:?}", start_time.elapsed()));
+        
+        Ok(())
+    }
+    
+    /// Analyze every selected target of a workspace as a separate crate
+    fn analyze_workspace(&self, dir_path: &Path, workspace: &Workspace) -> (usize, usize) {
+        let mut total_files = 0;
+        let mut error_count = 0;
+        
+        for package in &workspace.packages {
+            let cfg_mode = self.crate_cfg(Some(package));
+            for package_target in &package.targets {
+                if package_target.target.kind == TargetKind::Bin && !self.include_binaries {
+                    self.log(Verbosity::Verbose, &format!("Skipping binary target {}", package_target.target));
+                    continue;
+                }
+                
+                self.log(Verbosity::Normal, &format!("Analyzing {}", package_target.target));
+                let root = ModuleFile::crate_root(&package_target.root_file);
+                let (files, errors) = self.analyze_crate(dir_path, vec![root], Some(&package_target.target), &cfg_mode);
+                total_files += files;
+                error_count += errors;
+            }
+        }
+        
+        (total_files, error_count)
+    }
+    
+    /// Starting files for a directory without Cargo metadata: its `lib.rs` or `main.rs`
+    /// if there is one, otherwise every Rust file with a module path derived from its location
+    fn loose_crate_roots(&self, dir_path: &Path) -> io::Result<Vec<ModuleFile>> {
+        let source_root = Self::find_source_root(dir_path);
+        for root_name in ["lib.rs", "main.rs"] {
+            let root_file = source_root.join(root_name);
+            if root_file.is_file() {
+                return Ok(vec![ModuleFile::crate_root(&root_file)]);
+            }
+        }
+        
         let rust_files = self.collect_rust_files(dir_path)?;
-        let total_files = rust_files.len();
+        Ok(rust_files.iter()
+            .map(|path| ModuleFile {
+                file_path: module_tree::normalize_path(path),
+                module_path: Self::module_path_for_file(&source_root, dir_path, path),
+                owns_directory: path.file_stem().is_some_and(|stem| stem == "mod"),
+                cfg: Vec::new(),
+                macros: Vec::new(),
+                macro_use: false,
+            })
+            .collect())
+    }
+    
+    /// Extract every file of one crate first, following `mod` declarations from the roots,
+    /// then search paths on the merged call graph.
+    /// Returns the number of files processed and the number that had errors.
+    fn analyze_crate(&self, dir_path: &Path, roots: Vec<ModuleFile>, target: Option<&CrateTarget>, cfg_mode: &CfgMode) -> (usize, usize) {
+        let start_time = Instant::now();
+        
+        self.log(Verbosity::Normal, &format!("Found {} root files, starting parallel analysis...", roots.len()));
+        
+        // Phase 1: extract the files along the module tree
+        let (extractions, total_files, error_count) = self.extract_crate(dir_path, roots, cfg_mode);
+        
+        self.log(Verbosity::Normal, &format!("Processed: {} files Time: {:?}", total_files, start_time.elapsed()));
+        
+        // Phase 2: merge all files into one call graph and search paths on it
+        let call_graph = self.build_call_graph(&extractions);
+        self.log(Verbosity::Verbose, &format!("Call graph: {} functions, {} with internal unsafe, {} entry points", 
+                 call_graph.functions.len(), call_graph.unsafe_functions.len(), call_graph.entry_points.len()));
+        let paths = call_graph.find_paths_to_unsafe();
+        let unsafe_declarations = self.collect_unsafe_declarations(&call_graph, &extractions);
+        
+        self.add_crate_results(paths, unsafe_declarations, &call_graph.type_definitions, target);
+        
+        (total_files, error_count)
+    }
+    
+    /// Extract the files of one crate along the module tree in parallel rounds. `macro_rules!`
+    /// macros are scoped by text order: a file sees the macros defined before the `mod`
+    /// declarations that lead to it and those of `#[macro_use]` modules declared before them, so
+    /// it waits for such modules to be extracted first. Returns the extractions, the number of
+    /// files processed and the number that had errors.
+    fn extract_crate(&self, dir_path: &Path, roots: Vec<ModuleFile>, cfg_mode: &CfgMode) -> (Vec<FileExtraction>, usize, usize) {
+        let mut visited = HashSet::new();
+        let mut extracted = Vec::new(); // 提取结果、文件和文件可见的宏
+        let mut exported: HashMap<PathBuf, Vec<MacroScope>> = HashMap::new(); // 已处理的文件末尾可见的宏
+        let mut total_files = 0;
+        let mut error_count = 0;
+        
+        // 待提取的文件和上层文件传入的宏
+        let mut pending: Vec<(ModuleFile, Vec<MacroDefinition>)> = roots.into_iter()
+            .filter(|module| visited.insert(module.file_path.clone()))
+            .map(|module| (module, Vec::new()))
+            .collect();
         
-        println!("Found {} Rust files, starting parallel analysis...", total_files);
+        while !pending.is_empty() {
+            let mut ready = Vec::new();
+            let mut waiting = Vec::new();
+            for (module, inherited) in pending {
+                match resolve_macro_scope(&module.macros, &exported, Some(&visited)) {
+                    Some(macros) => ready.push((module, [inherited, macros].concat())),
+                    None => waiting.push((module, inherited)),
+                }
+            }
+            // #[macro_use] 模块互相依赖时不再等待
+            if ready.is_empty() {
+                ready = waiting.drain(..)
+                    .map(|(module, inherited)| {
+                        let macros = resolve_macro_scope(&module.macros, &exported, None).unwrap_or_default();
+                        (module, [inherited, macros].concat())
+                    })
+                    .collect();
+            }
+            // 按文件排序，合并调用图的顺序不受并行的影响
+            ready.sort_by(|a, b| a.0.file_path.cmp(&b.0.file_path));
+            
+            let (extractions, errors) = self.extract_modules(&ready, cfg_mode);
+            total_files += ready.len();
+            error_count += errors;
+            
+            let mut extractions = extractions.into_iter().peekable();
+            for (index, (module, macros)) in ready.into_iter().enumerate() {
+                let Some((_, extraction)) = extractions.next_if(|(extracted_index, _)| *extracted_index == index) else {
+                    exported.insert(module.file_path, Vec::new());
+                    continue;
+                };
+                exported.insert(module.file_path.clone(), extraction.macros.clone());
+                // 子模块可见的宏是本文件可见的宏加上声明之前的宏，后者在子模块提取前解析
+                waiting.extend(extraction.submodules.iter()
+                    .filter(|submodule| self.is_file_selected(dir_path, &submodule.file_path))
+                    .filter(|submodule| visited.insert(submodule.file_path.clone()))
+                    .map(|submodule| (submodule.clone(), macros.clone())));
+                extracted.push((extraction, module, macros));
+            }
+            pending = waiting;
+        }
         
-        // Create progress counter
-        let processed_count = Arc::new(Mutex::new(0));
-        let results = self.results.clone();
-        let error_count = Arc::new(Mutex::new(0));
+        // 文件自身在 #[macro_use] mod 声明之后同样可以使用这些模块的宏，模块提取之后重新提取
+        // 声明它们的文件；文件内的宏不区分先后，子模块已经按第一次提取的结果查找过
+        let (indices, modules): (Vec<usize>, Vec<_>) = extracted.iter()
+            .enumerate()
+            .filter_map(|(index, (extraction, module, macros))| {
+                let modules: Vec<MacroScope> = extraction.macros.iter()
+                    .filter(|scope| matches!(scope, MacroScope::Module(_)))
+                    .cloned()
+                    .collect();
+                let imported = resolve_macro_scope(&modules, &exported, None).unwrap_or_default();
+                (!imported.is_empty()).then(|| (index, (module.clone(), [macros.clone(), imported].concat())))
+            })
+            .unzip();
+        if !modules.is_empty() {
+            // 这些文件在第一轮已经计数，重新提取失败时保留第一次的结果，不再计为错误
+            let failed_files = self.failed_files.load(Ordering::Relaxed);
+            let (extractions, _) = self.extract_modules(&modules, cfg_mode);
+            self.failed_files.store(failed_files, Ordering::Relaxed);
+            for (index, extraction) in extractions {
+                extracted[indices[index]].0 = extraction;
+            }
+        }
+        
+        (extracted.into_iter().map(|(extraction, _, _)| extraction).collect(), total_files, error_count)
+    }
+    
+    /// Add the results of one crate: each path under the file of its public entry point, each
+    /// unsafe declaration under the file it is written in
+    fn add_crate_results(
+        &self,
+        paths: Vec<Vec<PathNodeInfo>>,
+        unsafe_declarations: Vec<UnsafeDeclaration>,
+        type_definitions: &HashMap<String, TypeDefinition>,
+        target: Option<&CrateTarget>,
+    ) {
+        let mut paths_by_file: HashMap<String, Vec<Vec<PathNodeInfo>>> = HashMap::new();
+        for path in paths {
+            if let Some(entry) = path.first() {
+                paths_by_file.entry(entry.file_path.clone()).or_default().push(path);
+            }
+        }
         
-        // Process files in parallel, using rayon's try_for_each to catch possible errors
-        let process_result = rust_files.par_iter().try_for_each(|path| -> Result<(), io::Error> {
+        // unsafe impl/trait 声明报告在其所在的文件下
+        let mut declarations_by_file: HashMap<String, Vec<UnsafeDeclaration>> = HashMap::new();
+        for declaration in unsafe_declarations {
+            declarations_by_file.entry(declaration.file_path.clone()).or_default().push(declaration);
+        }
+        
+        let mut file_paths: Vec<_> = paths_by_file.keys().chain(declarations_by_file.keys()).cloned().collect();
+        file_paths.sort();
+        file_paths.dedup();
+        for file_path in file_paths {
+            let paths = paths_by_file.remove(&file_path).unwrap_or_default();
+            let path_type_defs = Self::collect_path_type_definitions(&paths, type_definitions);
+            self.add_result(FileAnalysisResult {
+                target: target.cloned(),
+                unsafe_declarations: declarations_by_file.remove(&file_path).unwrap_or_default(),
+                file_path,
+                paths,
+                type_definitions: path_type_defs,
+            });
+        }
+    }
+    
+    /// Extract a set of module files in parallel, each with the crate macros visible to it. Returns
+    /// the extractions with the index of their module, in the order of `modules`, and the number
+    /// of errors.
+    fn extract_modules(&self, modules: &[(ModuleFile, Vec<MacroDefinition>)], cfg_mode: &CfgMode) -> (Vec<(usize, FileExtraction)>, usize) {
+        let extractions = Mutex::new(Vec::new());
+        let error_count = Mutex::new(0);
+        
+        modules.par_iter().enumerate().for_each(|(index, (module, macros))| {
+            let path = &module.file_path;
+            
             // Use catch_unwind to capture serious errors, prevent a single file from stopping all processing
-            let file_result = std::panic::catch_unwind(|| {
-                self.analyze_file(path)
+            let file_result = std::panic::catch_unwind(|| match &self.cache {
+                Some(cache) => cache.get_or_extract(module, macros, cfg_mode, || self.extract_file(module, macros, cfg_mode)),
+                None => self.extract_file(module, macros, cfg_mode),
             });
             
             match file_result {
-                Ok(Ok(Some(file_result))) => {
-                    // Normal case: file analysis successful with results
-                    let mut results_guard = results.lock().unwrap();
-                    results_guard.push(file_result);
+                Ok(Ok(Some(extraction))) => {
+                    // Normal case: file extraction successful
+                    self.log(Verbosity::Verbose, &format!("Extracted {} functions and {} calls from {} ({})", 
+                             extraction.functions.len(), extraction.calls.len(), path.display(), module.module_path.join("::")));
+                    extractions.lock().unwrap().push((index, extraction));
                 },
                 Ok(Ok(None)) => {
-                    // Normal case: file analysis successful but no results
+                    // Normal case: file skipped (too large, unparsable or timed out)
                 },
                 Ok(Err(e)) => {
                     // File IO error
                     eprintln!("File IO error analyzing {}: {}", path.display(), e);
-                    let mut count = error_count.lock().unwrap();
-                    *count += 1;
+                    self.failed_files.fetch_add(1, Ordering::Relaxed);
+                    *error_count.lock().unwrap() += 1;
                 },
                 Err(_) => {
                     // Parsing error or other serious error
                     eprintln!("Serious error occurred while parsing {}", path.display());
-                    let mut count = error_count.lock().unwrap();
-                    *count += 1;
+                    self.failed_files.fetch_add(1, Ordering::Relaxed);
+                    *error_count.lock().unwrap() += 1;
                 }
             }
-            
-            // Update progress
-            let mut count = processed_count.lock().unwrap();
-            *count += 1;
-            if *count % 100 == 0 || *count == total_files {
-                println!("Processed: {}/{} files ({:.1}%) Time: {:?}", 
-                         *count, total_files, 
-                         (*count as f64 / total_files as f64) * 100.0,
-                         start_time.elapsed());
-            }
-            
-            // Continue to next file
-            Ok(())
         });
         
-        // Handle overall error
-        if let Err(e) = process_result {
-            eprintln!("Error occurred during parallel file processing: {}", e);
+        let mut extractions = extractions.into_inner().unwrap();
+        extractions.sort_by_key(|(index, _)| *index);
+        (extractions, error_count.into_inner().unwrap())
+    }
+    
+    /// Directory that module paths are derived from: `src/` if present, otherwise the directory itself
+    pub fn find_source_root(dir_path: &Path) -> PathBuf {
+        let src_dir = dir_path.join("src");
+        if src_dir.is_dir() {
+            src_dir
+        } else {
+            dir_path.to_path_buf()
         }
+    }
+    
+    /// Derive the crate-rooted module path of a file from its location,
+    /// e.g. `src/foo/bar.rs` -> `crate::foo::bar`, `src/foo/mod.rs` -> `crate::foo`
+    pub fn module_path_for_file(source_root: &Path, dir_path: &Path, file_path: &Path) -> Vec<String> {
+        let relative = file_path.strip_prefix(source_root)
+            .or_else(|_| file_path.strip_prefix(dir_path))
+            .unwrap_or(file_path);
         
-        let error_count = *error_count.lock().unwrap();
-        println!("Analysis complete! Processed {} files, {} files had errors, Time: {:?}", 
-                 total_files, error_count, start_time.elapsed());
+        let components: Vec<String> = relative.components()
+            .filter_map(|c| c.as_os_str().to_str().map(String::from))
+            .collect();
         
-        Ok(())
+        let mut module_path = vec!["crate".to_string()];
+        for (i, component) in components.iter().enumerate() {
+            if i + 1 < components.len() {
+                module_path.push(component.clone());
+                continue;
+            }
+            
+            let stem = Path::new(component)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(component);
+            
+            // lib.rs/main.rs at the root and mod.rs anywhere stand for their directory module
+            let is_root_file = i == 0 && (stem == "lib" || stem == "main");
+            if stem != "mod" && !is_root_file {
+                module_path.push(stem.to_string());
+            }
+        }
+        
+        module_path
     }
     
     /// Collect all Rust files in directory
@@ -325,11 +941,12 @@ impl StaticAnalyzer {
         let walk_dir = WalkDir::new(dir_path)
             .follow_links(false)
             .into_iter()
+            .filter_entry(|e| !Self::is_skipped_dir(e))
             .filter_map(|e| e.ok());
         
         for entry in walk_dir {
             let path = entry.path();
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "rs") {
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "rs") && self.is_file_selected(dir_path, path) {
                 rust_files.push(path.to_owned());
             }
         }
@@ -337,6 +954,24 @@ impl StaticAnalyzer {
         Ok(rust_files)
     }
     
+    /// Build output and hidden directories (`target/`, `.git/`) never contain crate sources
+    fn is_skipped_dir(entry: &walkdir::DirEntry) -> bool {
+        entry.depth() > 0
+            && entry.file_type().is_dir()
+            && entry.file_name().to_str().is_some_and(|name| name == "target" || name.starts_with('.'))
+    }
+    
+    /// Check a file against the include/exclude globs
+    pub fn is_file_selected(&self, dir_path: &Path, file_path: &Path) -> bool {
+        let relative = file_path.strip_prefix(dir_path).unwrap_or(file_path);
+        
+        if !self.include_globs.is_empty() && !self.include_globs.is_match(relative) {
+            return false;
+        }
+        
+        !self.exclude_globs.is_match(relative)
+    }
+    
     /// Add a result to the results collection
     pub fn add_result(&self, result: FileAnalysisResult) {
         let mut guard = self.results.lock().unwrap();
@@ -348,10 +983,60 @@ impl StaticAnalyzer {
         let guard = self.results.lock().unwrap();
         guard.clone()
     }
-
+    
+    /// Write results in the requested format
+    pub fn write_results(&self, output_path: &Path, format: OutputFormat) -> io::Result<()> {
+        match format {
+            OutputFormat::Rust => self.write_results_to_file(output_path),
+            OutputFormat::Json => self.write_results_to_json(output_path),
+            OutputFormat::JsonLines => self.write_results_to_json_lines(output_path),
+            OutputFormat::Sarif => self.write_results_to_sarif(output_path),
+        }
+    }
+    
+    /// Write results as a single JSON document
+    pub fn write_results_to_json(&self, output_path: &Path) -> io::Result<()> {
+        self.log(Verbosity::Normal, &format!("Writing JSON results to: {}", output_path.display()));
+        let writer = BufWriter::new(File::create(output_path)?);
+        report::json::write_json(writer, &self.get_results())
+    }
+    
+    /// Write results as JSON Lines, one object per analyzed file
+    pub fn write_results_to_json_lines(&self, output_path: &Path) -> io::Result<()> {
+        self.log(Verbosity::Normal, &format!("Writing JSON Lines results to: {}", output_path.display()));
+        let writer = BufWriter::new(File::create(output_path)?);
+        report::json::write_json_lines(writer, &self.get_results())
+    }
+    
+    /// Write results as a SARIF 2.1.0 log
+    pub fn write_results_to_sarif(&self, output_path: &Path) -> io::Result<()> {
+        self.log(Verbosity::Normal, &format!("Writing SARIF results to: {}", output_path.display()));
+        let writer = BufWriter::new(File::create(output_path)?);
+        report::sarif::write_sarif(writer, &self.get_results())
+    }
+    
+    /// Number of files that could not be read, parsed or analyzed; their findings are missing
+    /// from the results
+    pub fn failed_files(&self) -> usize {
+        self.failed_files.load(Ordering::Relaxed)
+    }
+    
+    /// Save the findings as a baseline for later runs to compare with
+    pub fn save_baseline(&self, baseline_path: &Path) -> io::Result<()> {
+        self.log(Verbosity::Normal, &format!("Writing baseline to: {}", baseline_path.display()));
+        let writer = BufWriter::new(File::create(baseline_path)?);
+        Baseline::new(&self.get_results()).write(writer)
+    }
+    
+    /// Compare the findings with a saved baseline
+    pub fn compare_with_baseline(&self, baseline_path: &Path) -> io::Result<BaselineDiff> {
+        let baseline = Baseline::load(baseline_path)?;
+        Ok(baseline.compare(&Baseline::new(&self.get_results())))
+    }
+    
     /// Write results to file - new implementation that groups paths by destination unsafe function
     pub fn write_results_to_file(&self, output_path: &Path) -> io::Result<()> {
-        println!("Writing results to: {}", output_path.display());
+        self.log(Verbosity::Normal, &format!("Writing results to: {}", output_path.display()));
         
         let file = File::create(output_path)?;
         let mut writer = BufWriter::new(file);
@@ -369,19 +1054,23 @@ impl StaticAnalyzer {
         
         let results = self.get_results();
         
-        // 用于跟踪已处理的文件，避免重复输出
+        // 用于跟踪已处理的文件，避免重复输出（同一文件可能属于多个目标）
         let mut processed_files = HashSet::new();
         
         for result in &results {
-            if result.paths.is_empty() || processed_files.contains(&result.file_path) {
+            let file_key = (result.target.clone(), result.file_path.clone());
+            if (result.paths.is_empty() && result.unsafe_declarations.is_empty()) || processed_files.contains(&file_key) {
                 continue;
             }
             
             // 标记文件已处理
-            processed_files.insert(result.file_path.clone());
+            processed_files.insert(file_key);
             
             // 文件标题作为模块注释
             writeln!(writer, "// ============================================================")?;
+            if let Some(target) = &result.target {
+                writeln!(writer, "// 目标: {}", target)?;
+            }
             writeln!(writer, "// 文件: {}", result.file_path)?;
             writeln!(writer, "// ============================================================\n")?;
             
@@ -411,9 +1100,14 @@ impl StaticAnalyzer {
                     .push(path.clone());
             }
             
-            writeln!(writer, "    // 发现 {} 组通向不安全函数的路径", paths_by_destination.len())?;
+            if !paths_by_destination.is_empty() {
+                writeln!(writer, "    // 发现 {} 组通向不安全函数的路径", paths_by_destination.len())?;
+            }
+            
+            // Process each group of paths leading to the same unsafe function, in a stable order
+            let mut paths_by_destination: Vec<_> = paths_by_destination.into_iter().collect();
+            paths_by_destination.sort_by(|a, b| a.0.cmp(&b.0));
             
-            // Process each group of paths leading to the same unsafe function
             for (group_idx, (unsafe_fn, paths)) in paths_by_destination.into_iter().enumerate() {
                 // 为每个组创建一个子模块
                 let unsafe_fn_name = unsafe_fn.split("::").last().unwrap_or(&unsafe_fn);
@@ -429,7 +1123,7 @@ impl StaticAnalyzer {
                     writeln!(writer, "        // {}.{} {}", group_idx + 1, i + 1, Self::format_path_with_visibility(path))?;
                 }
                 
-                writeln!(writer, "")?;
+                writeln!(writer)?;
                 
                 // 收集所有需要输出的函数
                 let mut all_methods = Vec::new();
@@ -464,8 +1158,7 @@ impl StaticAnalyzer {
                 let mut intermediate_functions = HashMap::new();
                 for path in &paths {
                     if path.len() > 2 { // Only paths with intermediates
-                        for i in 1..path.len()-1 {
-                            let node = &path[i];
+                        for node in &path[1..path.len() - 1] {
                             intermediate_functions.insert(node.full_path.clone(), node);
                         }
                     }
@@ -489,11 +1182,9 @@ impl StaticAnalyzer {
                     // 收集入口函数参数中的自定义类型
                     let param_types = &path[0].param_custom_types;
                     for type_name in param_types {
-                        for (type_path, _) in &result.type_definitions {
-                            if let Some(def_name) = type_path.split("::").last() {
-                                if def_name == type_name {
-                                    all_types.insert(type_path.clone());
-                                }
+                        for type_path in result.type_definitions.keys() {
+                            if type_path.split("::").last() == Some(type_name.as_str()) {
+                                all_types.insert(type_path.clone());
                             }
                         }
                     }
@@ -544,7 +1235,7 @@ impl StaticAnalyzer {
                             // 输出处理后的类型定义
                             let type_text = processed_type.join("\n");
                             writeln!(writer, "{}", type_text)?;
-                            writeln!(writer, "")?;
+                            writeln!(writer)?;
                             
                             // 直接在类型定义后输出其impl块和实例方法
                             let type_name = type_path.split("::").last().unwrap_or(type_path);
@@ -553,7 +1244,7 @@ impl StaticAnalyzer {
                             let instance_methods: Vec<_> = all_methods.iter()
                                 .filter(|method| {
                                     method.has_self_param && 
-                                    method.owner_type.as_ref().map_or(false, |t| t == type_name)
+                                    method.owner_type.as_ref().is_some_and(|t| t == type_name)
                                 })
                                 .collect();
                             
@@ -586,13 +1277,15 @@ impl StaticAnalyzer {
                                     };
                                     
                                     writeln!(writer, "\n            // {}: {}", method_type, method.full_path)?;
+                                    Self::write_node_locations(&mut writer, method, "            ")?;
                                     
                                     // 输出函数的unsafe操作信息
                                     if !method.unsafe_operations.is_empty() {
                                         writeln!(writer, "            // 不安全操作：")?;
                                         for (j, op) in method.unsafe_operations.iter().enumerate() {
-                                            writeln!(writer, "            //            {}. 代码: {}", 
+                                            writeln!(writer, "            //            {}. {}代码: {}", 
                                                 j + 1, 
+                                                Self::format_operation_location(method, op),
                                                 op.code_snippet)?;
                                         }
                                     }
@@ -635,26 +1328,29 @@ impl StaticAnalyzer {
                         };
                         
                         writeln!(writer, "        // {}: {}", method_type, method.full_path)?;
+                        Self::write_node_locations(&mut writer, method, "        ")?;
                         
                         // 输出函数的unsafe操作信息
                         if !method.unsafe_operations.is_empty() {
                             writeln!(writer, "        // 不安全操作：")?;
                             for (j, op) in method.unsafe_operations.iter().enumerate() {
-                                writeln!(writer, "        //            {}. 代码: {}", 
+                                writeln!(writer, "        //            {}. {}代码: {}", 
                                     j + 1, 
+                                    Self::format_operation_location(method, op),
                                     op.code_snippet)?;
                             }
                         }
                         
-                        // 输出方法代码
+                        // 输出方法代码，闭包不是合法的项，作为注释输出
+                        let prefix = if scope::is_closure_key(&method.full_path) { "        // " } else { "        " };
                         let source_code = filter_doc_comments(&utils::beautify_source_code(&method.source_code))
                             .lines()
-                            .map(|line| format!("        {}", line))
+                            .map(|line| format!("{}{}", prefix, line))
                             .collect::<Vec<_>>()
                             .join("\n");
                         
                         writeln!(writer, "{}", source_code)?;
-                        writeln!(writer, "")?;
+                        writeln!(writer)?;
                     }
                 }
                 
@@ -662,14 +1358,117 @@ impl StaticAnalyzer {
                 writeln!(writer, "    }} // end of module {}", group_module_name)?;
             }
             
+            Self::write_unsafe_declarations(&mut writer, &result.unsafe_declarations)?;
+            
             // 关闭文件模块
             writeln!(writer, "}} // end of module {}\n", module_name)?;
         }
         
-        println!("成功写入 {} 个文件的分析结果", results.len());
+        self.log(Verbosity::Normal, &format!("成功写入 {} 个文件的分析结果", results.len()));
         Ok(())
     }
     
+    /// Write the `unsafe impl` and `unsafe trait` declarations of a file as a module of their own,
+    /// each with its generic bounds, missing `Send`/`Sync` bounds and the implementing type
+    fn write_unsafe_declarations<W: Write>(writer: &mut W, declarations: &[UnsafeDeclaration]) -> io::Result<()> {
+        if declarations.is_empty() {
+            return Ok(());
+        }
+        
+        writeln!(writer, "\n    // 发现 {} 个unsafe impl/unsafe trait/global_asm!声明", declarations.len())?;
+        writeln!(writer, "    pub mod unsafe_declarations {{")?;
+        
+        // 同一类型的多个unsafe impl只输出一次类型定义
+        let mut written_types = HashSet::new();
+        for (i, declaration) in declarations.iter().enumerate() {
+            writeln!(writer, "        // {}. {}", i + 1, declaration)?;
+            match &declaration.span {
+                Some(span) => writeln!(writer, "        // 位置: {}:{}:{}", declaration.file_path, span.start_line, span.start_column)?,
+                None => writeln!(writer, "        // 位置: {}", declaration.file_path)?,
+            }
+            if !declaration.generic_bounds.is_empty() {
+                writeln!(writer, "        // 泛型参数: {}", declaration.generic_bounds.join(", "))?;
+            }
+            if !declaration.missing_bounds.is_empty() {
+                writeln!(writer, "        // 缺少的约束: {}", declaration.missing_bounds.join(", "))?;
+            }
+            
+            if let Some(type_def) = &declaration.type_definition
+                && written_types.insert(scope::qualify(&type_def.module_path, &type_def.name)) {
+                writeln!(writer, "        // 类型定义:")?;
+                for line in filter_doc_comments(&utils::beautify_source_code(&type_def.source_code)).lines() {
+                    writeln!(writer, "        {}", line)?;
+                }
+            }
+            for line in filter_doc_comments(&utils::beautify_source_code(&declaration.source_code)).lines() {
+                writeln!(writer, "        {}", line)?;
+            }
+            writeln!(writer)?;
+        }
+        
+        writeln!(writer, "    }} // end of module unsafe_declarations")?;
+        Ok(())
+    }
+    
+    /// Write the definition location and unsafe block locations of a function as comments
+    fn write_node_locations<W: Write>(writer: &mut W, node: &PathNodeInfo, indent: &str) -> io::Result<()> {
+        match &node.span {
+            Some(span) => writeln!(writer, "{}// 位置: {}:{}:{}", indent, node.file_path, span.start_line, span.start_column)?,
+            None => writeln!(writer, "{}// 位置: {}", indent, node.file_path)?,
+        }
+        
+        if !node.unsafe_blocks.is_empty() {
+            let blocks: Vec<String> = node.unsafe_blocks.iter().map(|span| span.to_string()).collect();
+            writeln!(writer, "{}// unsafe块: {}", indent, blocks.join(", "))?;
+        }
+        
+        if let Some(parent) = &node.parent {
+            writeln!(writer, "{}// 所在节点: {}", indent, parent)?;
+        }
+        
+        if let Some(condition) = &node.cfg {
+            writeln!(writer, "{}// 条件编译: cfg({})", indent, condition)?;
+        }
+        
+        if let Some(symbol) = &node.exported_symbol {
+            writeln!(writer, "{}// 导出符号: {}（外部代码可以直接调用）", indent, symbol)?;
+        }
+        
+        if let Some(trait_name) = &node.dispatched_via {
+            writeln!(writer, "{}// 动态分发: 经由trait {} 调用（可能的实现之一）", indent, trait_name)?;
+        }
+        
+        if !node.unresolved_calls.is_empty() {
+            writeln!(writer, "{}// 未解析的方法调用: {}", indent, node.unresolved_calls.join(", "))?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Format the prefix of an unsafe operation line: operations guessed from names are marked,
+    /// followed by the location, the macro the operation was expanded from, the `#[cfg]`
+    /// condition when it differs from the function's and the category of a standard library API
+    fn format_operation_location(node: &PathNodeInfo, op: &UnsafeOperation) -> String {
+        let mut prefix = match op.confidence {
+            Confidence::High => String::new(),
+            Confidence::Low => "[启发式] ".to_string(),
+        };
+        if let Some(span) = &op.span {
+            prefix.push_str(&format!("位置: {}:{}, ", span.start_line, span.start_column));
+        }
+        if let Some(macro_name) = &op.macro_origin {
+            prefix.push_str(&format!("宏: {}, ", macro_name));
+        }
+        if let Some(condition) = &op.cfg
+            && op.cfg != node.cfg {
+            prefix.push_str(&format!("条件: cfg({}), ", condition));
+        }
+        if let Some(category) = op.category {
+            prefix.push_str(&format!("类别: {}, ", category));
+        }
+        prefix
+    }
+    
     /// Format a call path with visibility information
     fn format_path_with_visibility(path: &[PathNodeInfo]) -> String {
         let mut result = String::new();
@@ -678,7 +1477,7 @@ impl StaticAnalyzer {
         for (i, node) in path.iter().enumerate() {
             // 添加函数名和可见性
             let func_name = node.full_path.split("::").last().unwrap_or(&node.full_path);
-            result.push_str(&format!("{}{}", node.visibility.to_string(), func_name));
+            result.push_str(&format!("{}{}", node.visibility, func_name));
             
             // 添加箭头，除非是最后一个节点
             if i < path.len() - 1 {
@@ -690,6 +1489,41 @@ impl StaticAnalyzer {
     }
 }
 
+/// Macros of a textual scope in definition order, following `#[macro_use]` modules to the
+/// macros at their end. None while such a module is in `queued` but not extracted yet.
+fn resolve_macro_scope(scope: &[MacroScope], exported: &HashMap<PathBuf, Vec<MacroScope>>, queued: Option<&HashSet<PathBuf>>) -> Option<Vec<MacroDefinition>> {
+    fn collect<'a>(
+        scope: &'a [MacroScope],
+        exported: &'a HashMap<PathBuf, Vec<MacroScope>>,
+        queued: Option<&HashSet<PathBuf>>,
+        stack: &mut Vec<&'a PathBuf>,
+        macros: &mut Vec<MacroDefinition>,
+    ) -> Option<()> {
+        for entry in scope {
+            match entry {
+                MacroScope::Defined(definition) => macros.push(definition.clone()),
+                MacroScope::Module(path) => match exported.get(path) {
+                    // #[path] 可以让模块文件互相声明，正在展开的模块不再重复
+                    Some(module_scope) if !stack.contains(&path) => {
+                        stack.push(path);
+                        collect(module_scope, exported, queued, stack, macros)?;
+                        stack.pop();
+                    },
+                    Some(_) => {},
+                    None if queued.is_some_and(|queued| queued.contains(path)) => return None,
+                    // 未被选中分析的文件
+                    None => {},
+                },
+            }
+        }
+        Some(())
+    }
+    
+    let mut macros = Vec::new();
+    collect(scope, exported, queued, &mut Vec::new(), &mut macros)?;
+    Some(macros)
+}
+
 /// 从impl块中提取方法定义，并移除文档注释
 fn extract_method_from_impl(source_code: &str) -> String {
     // 检查是否包含impl
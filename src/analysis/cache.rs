@@ -0,0 +1,144 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, UNIX_EPOCH};
+use sha2::{Digest, Sha256};
+
+use crate::config::AnalysisConfig;
+use crate::models::{FileExtraction, MacroDefinition, ModuleFile};
+use crate::visitors::cfg::CfgMode;
+
+/// On-disk cache of file extractions, so that analyzing a crate again only parses the files
+/// that changed. An entry is keyed by a hash of the file content and of everything else its
+/// extraction depends on: the module it is loaded as, the macros it inherits and the `#[cfg]`
+/// configuration. The files its `mod` declarations were looked up in are checked again when an
+/// entry is loaded. Entries are kept in one subdirectory per analyzer build, configuration and
+/// file size and time limits, since an entry only exists for files extracted within the limits;
+/// the subdirectories of other builds and settings are removed when the cache is opened.
+pub struct ExtractionCache {
+    dir: PathBuf, // 当前分析器版本和配置的条目目录
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl ExtractionCache {
+    /// Length of the subdirectory names, a prefix of the hash of the analyzer build and settings
+    const SETTINGS_KEY_LEN: usize = 16;
+    
+    /// Open the cache under `root` for entries extracted with `config` and the analyzer's
+    /// file size limit in bytes and per-file timeout
+    pub fn open(root: &Path, config: &AnalysisConfig, file_size_limit: u64, timeout: Duration) -> io::Result<Self> {
+        let mut hasher = Sha256::new();
+        hasher.update(analyzer_version());
+        hasher.update(serde_json::to_vec(config)?);
+        hasher.update(format!("{} {:?}", file_size_limit, timeout));
+        let settings_key = &to_hex(&hasher.finalize())[..Self::SETTINGS_KEY_LEN];
+        
+        let dir = root.join(settings_key);
+        fs::create_dir_all(&dir)?;
+        
+        // 分析器或配置变化后旧条目不会再命中，直接删除；只删除像是条目目录的子目录
+        for entry in fs::read_dir(root)?.flatten() {
+            let is_stale = entry.file_name().to_str().is_some_and(|name| {
+                name != settings_key
+                    && name.len() == Self::SETTINGS_KEY_LEN
+                    && name.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+            });
+            if is_stale && entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+        
+        Ok(ExtractionCache {
+            dir,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        })
+    }
+    
+    /// Return the cached extraction of a module file, or extract it with `extract` and cache
+    /// the result. Files that are skipped or fail to parse are not cached, so their errors
+    /// are reported on every run.
+    pub fn get_or_extract<F>(&self, module: &ModuleFile, macros: &[MacroDefinition], cfg_mode: &CfgMode, extract: F) -> io::Result<Option<FileExtraction>>
+    where
+        F: FnOnce() -> io::Result<Option<FileExtraction>>,
+    {
+        // 读取失败时交给提取函数报告错误
+        let Ok(source) = fs::read(&module.file_path) else {
+            return extract();
+        };
+        let entry_path = self.dir.join(format!("{}.json", Self::entry_key(module, macros, cfg_mode, &source)?));
+        
+        if let Some(extraction) = Self::load(&entry_path) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Some(extraction));
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        
+        let extraction = extract()?;
+        if let Some(extraction) = &extraction
+            && let Err(e) = Self::store(&entry_path, extraction) {
+            eprintln!("Error writing cache entry {}: {}", entry_path.display(), e);
+        }
+        Ok(extraction)
+    }
+    
+    /// Number of files whose cached extraction was reused
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+    
+    /// Number of files that had to be extracted
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+    
+    /// Hash of the file content and of the context the file is extracted in
+    fn entry_key(module: &ModuleFile, macros: &[MacroDefinition], cfg_mode: &CfgMode, source: &[u8]) -> io::Result<String> {
+        let mut hasher = Sha256::new();
+        hasher.update(serde_json::to_vec(module)?);
+        hasher.update(serde_json::to_vec(macros)?);
+        hasher.update(format!("{:?}", cfg_mode));
+        hasher.update(source);
+        Ok(to_hex(&hasher.finalize()))
+    }
+    
+    /// Read an entry; missing, unreadable and outdated entries count as misses
+    fn load(entry_path: &Path) -> Option<FileExtraction> {
+        let content = fs::read(entry_path).ok()?;
+        let extraction: FileExtraction = serde_json::from_slice(&content).ok()?;
+        
+        // 子模块文件是按 `mod` 声明在磁盘上查找的，找到的文件被移动或删除、或者查找过的
+        // 文件出现（例如构建脚本生成的代码）后需要重新查找
+        let unchanged = extraction.submodules.iter().all(|submodule| submodule.file_path.is_file())
+            && !extraction.missing_modules.iter().any(|path| path.is_file());
+        unchanged.then_some(extraction)
+    }
+    
+    /// Write an entry through a temporary file, so that concurrent runs never read a partial entry
+    fn store(entry_path: &Path, extraction: &FileExtraction) -> io::Result<()> {
+        let temp_path = entry_path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temp_path, serde_json::to_vec(extraction)?)?;
+        fs::rename(&temp_path, entry_path).inspect_err(|_| {
+            let _ = fs::remove_file(&temp_path);
+        })
+    }
+}
+
+/// Version of the running analyzer. The size and modification time of the executable are
+/// included so that rebuilding the analyzer invalidates the cache during development too.
+fn analyzer_version() -> String {
+    let build = std::env::current_exe()
+        .and_then(fs::metadata)
+        .ok()
+        .and_then(|metadata| {
+            let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+            Some((metadata.len(), modified.as_nanos()))
+        });
+    format!("{} {:?}", env!("CARGO_PKG_VERSION"), build)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -1,5 +1,10 @@
-use std::collections::{HashMap, HashSet, VecDeque};
-use crate::models::{FunctionInfo, PathNodeInfo, VisibilityKind};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use crate::config::PathPatterns;
+use crate::visitors::{cfg, scope};
+use crate::models::{CfgRegion, ForeignItem, FunctionInfo, ModuleImports, PathNodeInfo, SourceSpan, TraitDefinition, TypeDefinition, UnsafeOperation, VisibilityKind};
+
+/// Upper bound on the paths tried when following imports for a single path
+const MAX_IMPORT_STEPS: usize = 256;
 
 /// Function call graph representation
 pub struct CallGraph {
@@ -17,12 +22,40 @@ pub struct CallGraph {
     pub public_unsafe_functions: HashSet<String>,
     /// Public and non-unsafe-declared functions
     pub public_non_unsafe_functions: HashSet<String>,
+    /// Functions used as path starting points, public non-unsafe functions by default
+    pub entry_points: HashSet<String>,
     /// Maximum search depth
     pub max_search_depth: usize,
     /// Mapping from function path to custom types used in its parameters
     pub param_custom_types: HashMap<String, HashSet<String>>,
     /// Mapping from function path to custom types used in its return value
     pub return_custom_types: HashMap<String, HashSet<String>>,
+    /// Methods by (module path, method name), for `Type::method` calls
+    pub methods_by_name: HashMap<(String, String), Vec<String>>,
+    /// Methods by (owner type name, method name), for method calls on a receiver of known type
+    pub methods_by_owner: HashMap<(String, String), Vec<String>>,
+    /// Names of all methods defined in the crate
+    pub method_names: HashSet<String>,
+    /// Type definitions of the crate, by type path
+    pub type_definitions: HashMap<String, TypeDefinition>,
+    /// Method calls on crate methods whose receiver type could not be inferred, by caller
+    pub unresolved_calls: HashMap<String, BTreeSet<String>>,
+    /// Trait definitions of the crate, by trait path
+    pub traits: HashMap<String, TraitDefinition>,
+    /// Trait impl methods by (trait name, method name)
+    pub trait_impls: HashMap<(String, String), Vec<String>>,
+    /// Edges (caller, callee) that only exist through trait dispatch, with the trait name
+    pub dispatch_edges: HashMap<(String, String), String>,
+    /// `use` declarations by module path, for glob imports and re-exports
+    pub imports: HashMap<String, ModuleImports>,
+    /// Paths of the modules that contain functions, including their parent modules
+    pub modules: HashSet<String>,
+    /// Visibility of the declared modules, by module path
+    pub module_visibility: HashMap<String, VisibilityKind>,
+    /// Keys of the `static mut` items of the crate
+    pub mutable_statics: HashSet<String>,
+    /// Functions and statics declared in `extern` blocks, by item path
+    pub foreign_items: HashMap<String, ForeignItem>,
 }
 
 impl CallGraph {
@@ -35,20 +68,40 @@ impl CallGraph {
             public_functions: HashSet::new(),
             public_unsafe_functions: HashSet::new(),
             public_non_unsafe_functions: HashSet::new(),
+            entry_points: HashSet::new(),
             max_search_depth: max_depth,
             param_custom_types: HashMap::new(),
             return_custom_types: HashMap::new(),
+            methods_by_name: HashMap::new(),
+            methods_by_owner: HashMap::new(),
+            method_names: HashSet::new(),
+            type_definitions: HashMap::new(),
+            unresolved_calls: HashMap::new(),
+            traits: HashMap::new(),
+            trait_impls: HashMap::new(),
+            dispatch_edges: HashMap::new(),
+            imports: HashMap::new(),
+            modules: HashSet::new(),
+            module_visibility: HashMap::new(),
+            mutable_statics: HashSet::new(),
+            foreign_items: HashMap::new(),
         }
     }
-
+    
     /// Add function to graph
     pub fn add_function(&mut self, path: String, info: FunctionInfo) {
+        let mut module_path = info.module_path.as_str();
+        while !module_path.is_empty() && self.modules.insert(module_path.to_string()) {
+            module_path = module_path.rsplit_once("::").map(|(parent, _)| parent).unwrap_or("");
+        }
+        
         if info.visibility == VisibilityKind::Public {
             self.public_functions.insert(path.clone());
             
             // If function is public and not unsafe-declared, add to public_non_unsafe_functions set
             if !info.is_unsafe_fn {
                 self.public_non_unsafe_functions.insert(path.clone());
+                self.entry_points.insert(path.clone());
             }
             
             // If function is both public and contains unsafe code, add to new set
@@ -61,6 +114,11 @@ impl CallGraph {
             self.unsafe_functions.insert(path.clone());
         }
         
+        // 导出给外部代码的extern函数可以被直接调用，与可见性无关
+        if info.exported_symbol.is_some() && !info.is_unsafe_fn {
+            self.entry_points.insert(path.clone());
+        }
+        
         // Save custom types used in function parameters and return value
         if !info.param_custom_types.is_empty() {
             self.param_custom_types.insert(path.clone(), info.param_custom_types.clone());
@@ -70,51 +128,309 @@ impl CallGraph {
             self.return_custom_types.insert(path.clone(), info.return_custom_types.clone());
         }
         
+        if let Some(owner) = &info.owner_type {
+            self.methods_by_name.entry((info.module_path.clone(), info.name.clone()))
+                .or_default()
+                .push(path.clone());
+            self.methods_by_owner.entry((owner.clone(), info.name.clone()))
+                .or_default()
+                .push(path.clone());
+            self.method_names.insert(info.name.clone());
+            
+            if let Some(trait_name) = &info.trait_name {
+                self.trait_impls.entry((trait_name.clone(), info.name.clone()))
+                    .or_default()
+                    .push(path.clone());
+            }
+        }
+        
         self.functions.insert(path, info);
     }
-
+    
+    /// Add type definition to graph
+    pub fn add_type_definition(&mut self, path: String, definition: TypeDefinition) {
+        self.type_definitions.insert(path, definition);
+    }
+    
+    /// Add trait definition to graph
+    pub fn add_trait(&mut self, path: String, definition: TraitDefinition) {
+        self.traits.insert(path, definition);
+    }
+    
+    /// Add the `use` declarations of a module
+    pub fn add_imports(&mut self, module_path: String, imports: &ModuleImports) {
+        let entry = self.imports.entry(module_path).or_default();
+        entry.names.extend(imports.names.clone());
+        entry.globs.extend(imports.globs.iter().cloned());
+        entry.exported.extend(imports.exported.iter().cloned());
+        entry.exported_globs.extend(imports.exported_globs.iter().cloned());
+    }
+    
+    /// Add a module declaration
+    pub fn add_module(&mut self, module_path: String, visibility: VisibilityKind) {
+        self.module_visibility.insert(module_path, visibility);
+    }
+    
+    /// Add an unsafe operation that is only known once the whole crate has been read, e.g. a
+    /// read of a union defined in another file; the function then contains unsafe code
+    pub fn add_unsafe_operation(&mut self, path: &str, operation: UnsafeOperation) {
+        let Some(info) = self.functions.get_mut(path) else {
+            return;
+        };
+        if info.unsafe_operations.iter().any(|op| op.code_snippet == operation.code_snippet && op.span == operation.span) {
+            return;
+        }
+        
+        info.unsafe_operations.push(operation);
+        info.unsafe_operations.sort_by_key(|op| op.span.map(|span| (span.start_line, span.start_column)));
+        info.has_internal_unsafe = true;
+        self.unsafe_functions.insert(path.to_string());
+        if self.public_functions.contains(path) {
+            self.public_unsafe_functions.insert(path.to_string());
+        }
+    }
+    
+    /// Drop the operations recorded for an expression of a function, used when the call there has
+    /// been resolved and the operations guessed from its name no longer apply. The code is
+    /// compared too: code expanded from a macro shares the span of the invocation.
+    pub fn remove_operations_at(&mut self, path: &str, span: SourceSpan, code_snippet: &str) {
+        if let Some(info) = self.functions.get_mut(path) {
+            info.unsafe_operations.retain(|op| op.span != Some(span) || op.code_snippet != code_snippet);
+        }
+    }
+    
+    /// Mark the operations generated by macro expansion with the macro they came from, given
+    /// the invocations by file and span
+    pub fn set_macro_origins(&mut self, invocations: &HashMap<(String, SourceSpan), String>) {
+        if invocations.is_empty() {
+            return;
+        }
+        for info in self.functions.values_mut() {
+            for op in &mut info.unsafe_operations {
+                if let Some(span) = op.span
+                    && let Some(name) = invocations.get(&(info.file_path.clone(), span)) {
+                    op.macro_origin = Some(name.clone());
+                }
+            }
+        }
+    }
+    
+    /// Annotate functions and operations with the `#[cfg]` conditions they exist under, given
+    /// the conditions of whole files and their conditional regions by file path. A function
+    /// merged from variants under different conditions (see `FunctionVisitor`) only carries
+    /// the conditions of its file; each of its operations carries its own.
+    pub fn set_cfg_conditions(&mut self, files: &HashMap<&str, (&[String], &[CfgRegion])>) {
+        if files.is_empty() {
+            return;
+        }
+        for info in self.functions.values_mut() {
+            let Some((file_conditions, regions)) = files.get(info.file_path.as_str()) else {
+                continue;
+            };
+            let condition_at = |span: Option<SourceSpan>| {
+                let mut conditions = file_conditions.to_vec();
+                if let Some(span) = span {
+                    conditions.extend(regions.iter()
+                        .filter(|region| region.span.contains(&span))
+                        .map(|region| region.condition.clone()));
+                }
+                cfg::combine(&conditions)
+            };
+            
+            // 操作不在函数的位置范围内时，它来自同名函数的另一个cfg变体
+            let has_variants = info.span.is_some_and(|span| info.unsafe_operations.iter()
+                .any(|op| op.span.is_some_and(|op_span| !span.contains(&op_span))));
+            info.cfg = if has_variants { cfg::combine(file_conditions) } else { condition_at(info.span) };
+            for op in &mut info.unsafe_operations {
+                op.cfg = condition_at(op.span);
+            }
+        }
+    }
+    
+    /// Replace the public functions, decided so far by each function's own `pub`, with the
+    /// functions reachable from outside the crate; entry points follow, except functions
+    /// exported to foreign code. Call before `apply_entry_point_rules`.
+    pub fn restrict_to_public_api(&mut self, public_api: &HashSet<String>) {
+        self.public_functions.retain(|path| public_api.contains(path));
+        self.public_unsafe_functions.retain(|path| public_api.contains(path));
+        self.public_non_unsafe_functions.retain(|path| public_api.contains(path));
+        let functions = &self.functions;
+        self.entry_points.retain(|path| public_api.contains(path)
+            || functions.get(path).is_some_and(|info| info.exported_symbol.is_some()));
+    }
+    
+    /// Map a callee path recorded by `CallVisitor` to known functions; call after all
+    /// functions have been added. `module::Type::method` also matches trait methods of
+    /// `Type` (`module::<Type as Trait>::method`), and paths through glob imports and
+    /// re-exports lead to the defining function. Unknown callees, e.g. from other
+    /// crates, are kept as they are.
+    pub fn resolve_callee(&self, callee: &str) -> Vec<String> {
+        self.follow_imports(callee, |path| !self.lookup_callee(path).is_empty())
+            .map(|path| self.lookup_callee(&path))
+            .unwrap_or_else(|| vec![callee.to_string()])
+    }
+    
+    /// Known functions for a path, without following imports
+    fn lookup_callee(&self, callee: &str) -> Vec<String> {
+        if self.functions.contains_key(callee) {
+            return vec![callee.to_string()];
+        }
+        
+        let Some((parent, name)) = callee.rsplit_once("::") else {
+            return Vec::new();
+        };
+        
+        // module::Type::method
+        if let Some((module, owner)) = parent.rsplit_once("::")
+            && let Some(methods) = self.methods_by_name.get(&(module.to_string(), name.to_string())) {
+            let mut candidates: Vec<String> = methods.iter()
+                .filter(|path| self.functions[*path].owner_type.as_deref() == Some(owner))
+                .cloned()
+                .collect();
+            candidates.sort();
+            return candidates;
+        }
+        
+        Vec::new()
+    }
+    
+    /// First path accepted by `is_known`, starting with the path itself and then following
+    /// `use` declarations breadth-first: `module::name::rest` becomes `target::rest` for an
+    /// import `name -> target` of the module, and `glob::name::rest` for each glob import
+    /// unless `name` is a submodule of the module (glob imports never shadow local items)
+    pub fn follow_imports(&self, path: &str, is_known: impl Fn(&str) -> bool) -> Option<String> {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(path.to_string());
+        queue.push_back(path.to_string());
+        
+        while let Some(current) = queue.pop_front() {
+            if is_known(&current) {
+                return Some(current);
+            }
+            // 互相glob导入的模块可能产生无限多的路径
+            if visited.len() > MAX_IMPORT_STEPS {
+                break;
+            }
+            
+            let segments: Vec<&str> = current.split("::").collect();
+            // 从最长的模块前缀开始尝试，越具体的导入越优先
+            for split in (0..segments.len()).rev() {
+                let module_path = segments[..split].join("::");
+                let Some(imports) = self.imports.get(&module_path) else {
+                    continue;
+                };
+                let name = segments[split];
+                let rest = &segments[split + 1..];
+                
+                let mut alternatives = Vec::new();
+                if let Some(target) = imports.names.get(name) {
+                    alternatives.push(target.clone());
+                }
+                if !self.modules.contains(&scope::qualify(&module_path, name)) {
+                    alternatives.extend(imports.globs.iter().map(|glob| scope::qualify(glob, name)));
+                }
+                
+                for alternative in alternatives {
+                    let alternative = std::iter::once(alternative.as_str())
+                        .chain(rest.iter().copied())
+                        .collect::<Vec<_>>()
+                        .join("::");
+                    if visited.insert(alternative.clone()) {
+                        queue.push_back(alternative);
+                    }
+                }
+            }
+        }
+        
+        None
+    }
+    
     /// Add function call relationship
     pub fn add_call(&mut self, caller: String, callee: String) {
-        self.calls.entry(caller.clone()).or_insert_with(HashSet::new).insert(callee.clone());
-        self.reverse_calls.entry(callee).or_insert_with(HashSet::new).insert(caller);
+        // 同时存在直接调用时不再视为动态分发
+        self.dispatch_edges.remove(&(caller.clone(), callee.clone()));
+        self.calls.entry(caller.clone()).or_default().insert(callee.clone());
+        self.reverse_calls.entry(callee).or_default().insert(caller);
     }
-
-    /// Check if path is valid, using public_non_unsafe_functions instead of public_functions
-    /// for checking the first node
+    
+    /// Add a call that may reach the callee through trait dispatch
+    pub fn add_dispatch_call(&mut self, caller: String, callee: String, trait_name: String) {
+        let is_direct = self.calls.get(&caller).is_some_and(|callees| callees.contains(&callee));
+        if !is_direct {
+            self.dispatch_edges.insert((caller.clone(), callee.clone()), trait_name);
+            self.calls.entry(caller.clone()).or_default().insert(callee.clone());
+            self.reverse_calls.entry(callee).or_default().insert(caller);
+        }
+    }
+    
+    /// Record a method call that may target a crate method but whose receiver type is unknown
+    pub fn add_unresolved_call(&mut self, caller: String, method: String) {
+        self.unresolved_calls.entry(caller).or_default().insert(method);
+    }
+    
+    /// Adjust the entry points with configured function path patterns;
+    /// call after all functions have been added
+    pub fn apply_entry_point_rules(&mut self, include: &PathPatterns, exclude: &PathPatterns) {
+        // unsafe声明的函数要求调用者保证安全，不能作为入口点
+        for (path, info) in &self.functions {
+            if !info.is_unsafe_fn && include.is_match(path) {
+                self.entry_points.insert(path.clone());
+            }
+        }
+        
+        self.entry_points.retain(|path| !exclude.is_match(path));
+    }
+    
+    /// Check if path is valid: it must start at an entry point
+    /// and end at a function containing internal unsafe code
     pub fn is_valid_path(&self, path: &[String]) -> bool {
-        // 修改：检查路径长度必须等于1（而不是大于1）
-        if path.len() != 1 {
+        let (Some(first), Some(last)) = (path.first(), path.last()) else {
             return false;
-        }
+        };
         
-        // 检查节点必须是公共非unsafe声明函数
-        if !self.public_non_unsafe_functions.contains(&path[0]) {
+        // 起点必须是入口点（默认为公共非unsafe声明函数）
+        if !self.entry_points.contains(first) {
             return false;
         }
         
-        // 检查节点必须是内部unsafe函数
-        if !self.unsafe_functions.contains(&path[0]) {
+        // 终点必须是内部unsafe函数
+        if !self.unsafe_functions.contains(last) {
             return false;
         }
         
-        true
+        // 中间节点不能包含unsafe代码，否则应以该节点作为终点
+        path.iter()
+            .skip(1)
+            .take(path.len().saturating_sub(2))
+            .all(|node| !self.unsafe_functions.contains(node))
     }
     
     /// Check if path is minimal (no public functions except starting node)
     pub fn is_minimal_path(&self, path: &[String]) -> bool {
         // Skip first node, check if subsequent nodes have public functions
-        for i in 1..path.len() {
-            if self.public_functions.contains(&path[i]) {
+        for node in path.iter().skip(1) {
+            if self.public_functions.contains(node) {
                 return false;  // Found public function in the middle, not minimal path
             }
         }
         true
     }
-
+    
     /// Convert path to node info format with function details
     pub fn convert_path_to_node_info(&self, path: Vec<String>) -> Vec<PathNodeInfo> {
+        // 每个节点记录进入它的边是否为trait动态分发
+        let dispatched: Vec<Option<String>> = path.iter()
+            .enumerate()
+            .map(|(i, callee)| {
+                let caller = path.get(i.checked_sub(1)?)?;
+                self.dispatch_edges.get(&(caller.clone(), callee.clone())).cloned()
+            })
+            .collect();
+        
         path.into_iter()
-            .map(|full_path| {
+            .zip(dispatched)
+            .map(|(full_path, dispatched_via)| {
                 if let Some(info) = self.functions.get(&full_path) {
                     let param_types = self.param_custom_types.get(&full_path)
                         .cloned()
@@ -124,11 +440,16 @@ impl CallGraph {
                         .cloned()
                         .unwrap_or_else(HashSet::new);
                     
+                    let unresolved_calls = self.unresolved_calls.get(&full_path)
+                        .map(|calls| calls.iter().cloned().collect())
+                        .unwrap_or_default();
+                    
                     //println!("Function: {}, has_self_param: {}, owner_type: {:?}", 
                         //full_path, info.has_self_param, info.owner_type);
                     
                     PathNodeInfo {
                         full_path,
+                        file_path: info.file_path.clone(),
                         visibility: info.visibility.clone(),
                         source_code: info.source_code.clone(),
                         param_custom_types: param_types,
@@ -136,11 +457,19 @@ impl CallGraph {
                         has_self_param: info.has_self_param,
                         owner_type: info.owner_type.clone(),
                         unsafe_operations: info.unsafe_operations.clone(),
+                        span: info.span,
+                        unsafe_blocks: info.unsafe_blocks.clone(),
+                        unresolved_calls,
+                        dispatched_via,
+                        parent: info.parent.clone(),
+                        exported_symbol: info.exported_symbol.clone(),
+                        cfg: info.cfg.clone(),
                     }
                 } else {
                     // Default value, normally shouldn't reach here
                     PathNodeInfo {
                         full_path,
+                        file_path: String::new(),
                         visibility: VisibilityKind::Module,
                         source_code: String::new(),
                         param_custom_types: HashSet::new(),
@@ -148,29 +477,43 @@ impl CallGraph {
                         has_self_param: false,
                         owner_type: None,
                         unsafe_operations: Vec::new(),
+                        span: None,
+                        unsafe_blocks: Vec::new(),
+                        unresolved_calls: Vec::new(),
+                        dispatched_via,
+                        parent: None,
+                        exported_symbol: None,
+                        cfg: None,
                     }
                 }
             })
             .collect()
     }
-
+    
     /// Find all valid paths from public functions to internal unsafe functions,
     /// return paths with detailed function information
     pub fn find_paths_to_unsafe(&self) -> Vec<Vec<PathNodeInfo>> {
         let mut all_paths = Vec::new();
         
-        // 只添加直接的公共不安全函数
-        for pub_unsafe_fn in &self.public_unsafe_functions {
-            // 只添加那些非unsafe声明的公共函数
-            if self.public_non_unsafe_functions.contains(pub_unsafe_fn) {
-                let mut path = Vec::new();
-                path.push(pub_unsafe_fn.clone());
-                
-                // 修改：使用is_valid_path验证，这里会检查路径长度是否为1
-                if self.is_valid_path(&path) {
-                    all_paths.push(path);
-                }
+        // 按路径排序入口点，保证输出顺序稳定
+        let mut entry_points: Vec<&String> = self.entry_points.iter().collect();
+        entry_points.sort();
+        
+        // 只有能经由私有函数到达unsafe函数的节点才值得搜索
+        let reaching = self.precompute_nodes_reaching_unsafe();
+        
+        for entry in entry_points {
+            // 预先计算入口可达的unsafe函数，没有可达目标的入口直接跳过
+            let reachable = self.precompute_reachable_targets(entry, &self.unsafe_functions);
+            if reachable.is_empty() {
+                continue;
             }
+            
+            // 经由私有函数搜索到可达unsafe函数的路径，只保留最短（最小）路径
+            let mut paths = self.find_valid_paths(entry, &reachable, &reaching);
+            paths.retain(|path| self.is_valid_path(path) && self.is_minimal_path(path));
+            paths.sort();
+            all_paths.extend(paths);
         }
         
         // 将路径转换为详细格式
@@ -178,15 +521,15 @@ impl CallGraph {
             .map(|path| self.convert_path_to_node_info(path))
             .collect()
     }
-
+    
     /// Pre-compute reachable target functions, reducing search space
-    pub fn precompute_reachable_targets(&self, start: &String, targets: &HashSet<String>) -> HashSet<String> {
+    pub fn precompute_reachable_targets(&self, start: &str, targets: &HashSet<String>) -> HashSet<String> {
         let mut reachable = HashSet::new();
         let mut visited = HashSet::new();
         let mut queue = VecDeque::new();
         
-        queue.push_back(start.clone());
-        visited.insert(start.clone());
+        queue.push_back(start.to_string());
+        visited.insert(start.to_string());
         
         while let Some(current) = queue.pop_front() {
             if targets.contains(&current) {
@@ -205,30 +548,54 @@ impl CallGraph {
         
         reachable
     }
-
-    /// Find all valid paths from start function to any function in target set
-    pub fn find_valid_paths(&self, start: &String, targets: &HashSet<String>) -> Vec<Vec<String>> {
+    
+    /// Functions that can reach an unsafe function through valid intermediate nodes (private
+    /// functions without unsafe code), found by a reverse BFS from the unsafe functions
+    pub fn precompute_nodes_reaching_unsafe(&self) -> HashSet<String> {
+        let mut reaching = self.unsafe_functions.clone();
+        let mut queue: VecDeque<&String> = self.unsafe_functions.iter().collect();
+        
+        while let Some(current) = queue.pop_front() {
+            if let Some(callers) = self.reverse_calls.get(current) {
+                for caller in callers {
+                    // 公共函数和unsafe函数不能作为路径的中间节点，不再向上扩展
+                    let is_intermediate = !self.public_functions.contains(caller)
+                        && !self.unsafe_functions.contains(caller)
+                        && !self.public_unsafe_functions.contains(caller);
+                    if is_intermediate && reaching.insert(caller.clone()) {
+                        queue.push_back(caller);
+                    }
+                }
+            }
+        }
+        
+        reaching
+    }
+    
+    /// Find all valid paths from start function to any function in target set, only
+    /// descending into callees in `reaching` (see `precompute_nodes_reaching_unsafe`)
+    pub fn find_valid_paths(&self, start: &String, targets: &HashSet<String>, reaching: &HashSet<String>) -> Vec<Vec<String>> {
         let mut all_paths = Vec::new();
         let mut visited = HashSet::new();
         let mut path = Vec::new();
         
-        self.dfs_find_valid_paths(start, targets, &mut visited, &mut path, &mut all_paths, 0);
+        self.dfs_find_valid_paths(start, targets, reaching, &mut visited, &mut path, &mut all_paths);
         
         all_paths
     }
-
+    
     /// Depth-first search to find valid paths, with depth limit
     pub fn dfs_find_valid_paths(
         &self,
         current: &String,
         targets: &HashSet<String>,
+        reaching: &HashSet<String>,
         visited: &mut HashSet<String>,
         path: &mut Vec<String>,
         all_paths: &mut Vec<Vec<String>>,
-        depth: usize,
     ) {
-        // Exceed depth limit, terminate search
-        if depth > self.max_search_depth {
+        // Exceed depth limit, terminate search; the depth is the number of nodes before this one
+        if path.len() > self.max_search_depth {
             return;
         }
         
@@ -244,14 +611,20 @@ impl CallGraph {
             }
         }
         
+        // Paths through another public function are not minimal, that function is an entry point itself
+        if !path.is_empty() && self.public_functions.contains(current) {
+            return;
+        }
+        
         visited.insert(current.clone());
         path.push(current.clone());
         
         if targets.contains(current) {
             all_paths.push(path.clone()); // Found a valid path
         } else if let Some(callees) = self.calls.get(current) {
-            for callee in callees {
-                self.dfs_find_valid_paths(callee, targets, visited, path, all_paths, depth + 1);
+            // 到达不了unsafe函数的子图不需要枚举其中的路径
+            for callee in callees.iter().filter(|callee| reaching.contains(*callee)) {
+                self.dfs_find_valid_paths(callee, targets, reaching, visited, path, all_paths);
             }
         }
         
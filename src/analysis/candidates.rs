@@ -0,0 +1,147 @@
+use crate::analysis::call_graph::CallGraph;
+use crate::analysis::type_resolver::TypeResolver;
+use crate::models::{CandidateTarget, Confidence, FileExtraction, SourceSpan, TypeHint, UnsafeApiCategory, UnsafeOperation, UnsafeOperationType};
+use crate::unsafe_apis;
+use crate::visitors::scope;
+
+/// What a candidate operation turned out to be once the whole crate is known
+enum Outcome {
+    /// An unsafe operation that is not a call: a union field read or a `static mut` access
+    Operation(Found),
+    /// A call resolved to crate, foreign or standard library functions; the operation when the callee is unsafe
+    Call(Option<Found>),
+}
+
+/// A confirmed unsafe operation, before it is attached to its node
+struct Found {
+    operation_type: UnsafeOperationType,
+    description: String,
+    category: Option<UnsafeApiCategory>,
+}
+
+/// Confirm the candidate operations of the extracted files against the whole crate and add
+/// the unsafe ones to their nodes. Calls that resolve to functions of the crate or of an
+/// `extern` block are classified by the callee's declaration, and the operations the name
+/// heuristics of `FunctionVisitor` recorded for the same call are dropped. Calls of the
+/// standard library whose full path or receiver type is known are looked up in the unsafe
+/// API database.
+pub fn apply_candidate_operations(call_graph: &mut CallGraph, extractions: &[FileExtraction]) {
+    let mut operations = Vec::new();
+    let mut resolved_calls: Vec<(String, SourceSpan, &str)> = Vec::new();
+    {
+        let resolver = TypeResolver::new(call_graph);
+        for candidate in extractions.iter().flat_map(|extraction| &extraction.candidate_operations) {
+            let operation = match classify(call_graph, &resolver, &candidate.target) {
+                Some(Outcome::Operation(found)) => Some(found),
+                Some(Outcome::Call(operation)) => {
+                    if let Some(span) = candidate.span {
+                        resolved_calls.push((candidate.function.clone(), span, &candidate.code_snippet));
+                    }
+                    operation
+                },
+                None => None,
+            };
+            
+            if let Some(found) = operation {
+                operations.push((candidate.function.clone(), UnsafeOperation {
+                    operation_type: found.operation_type,
+                    description: found.description,
+                    code_snippet: candidate.code_snippet.clone(),
+                    line_number: candidate.span.map(|span| span.start_line),
+                    span: candidate.span,
+                    confidence: Confidence::High,
+                    category: found.category,
+                    macro_origin: None,
+                    cfg: None,
+                }));
+            }
+        }
+    }
+    
+    // 先去掉已解析调用上按名称推测的操作，再加入按声明确定的操作
+    for (function, span, code_snippet) in resolved_calls {
+        call_graph.remove_operations_at(&function, span, code_snippet);
+    }
+    for (function, operation) in operations {
+        call_graph.add_unsafe_operation(&function, operation);
+    }
+}
+
+fn classify(call_graph: &CallGraph, resolver: &TypeResolver, target: &CandidateTarget) -> Option<Outcome> {
+    let is_foreign = |path: &str, is_static: bool| {
+        call_graph.foreign_items.get(path).is_some_and(|item| item.is_static == is_static)
+    };
+    let ffi_call = |path: String| {
+        let item = &call_graph.foreign_items[&path];
+        let description = if item.is_static { "访问外部静态变量" } else { "调用外部函数" };
+        let operation_type = UnsafeOperationType::FfiCall { abi: item.abi.clone(), link_name: item.link_name.clone() };
+        Found { operation_type, description: format!("{}: {}", description, item.link_name), category: None }
+    };
+    let plain = |operation_type: UnsafeOperationType, category: Option<UnsafeApiCategory>| {
+        let description = operation_type.to_string();
+        Found { operation_type, description, category }
+    };
+    
+    match target {
+        CandidateTarget::FieldOf(base) => resolver.resolve_hint(base)
+            .filter(|type_path| call_graph.type_definitions.get(type_path).is_some_and(|definition| definition.is_union))
+            .map(|_| Outcome::Operation(plain(UnsafeOperationType::UnionFieldAccess, None))),
+        CandidateTarget::Path(path) => call_graph.follow_imports(path, |path| call_graph.mutable_statics.contains(path) || is_foreign(path, true))
+            .map(|path| {
+                let found = if call_graph.mutable_statics.contains(&path) {
+                    plain(UnsafeOperationType::MutStaticAccess, None)
+                } else {
+                    ffi_call(path)
+                };
+                Outcome::Operation(found)
+            }),
+        CandidateTarget::Call(path) => {
+            if let Some(foreign) = call_graph.follow_imports(path, |path| is_foreign(path, false)) {
+                return Some(Outcome::Call(Some(ffi_call(foreign))));
+            }
+            let callees = resolver.resolve_call(path, None);
+            if resolved(call_graph, &callees) {
+                return Some(Outcome::Call(is_unsafe(call_graph, &callees).then(|| plain(UnsafeOperationType::UnsafeFunctionCall, None))));
+            }
+            // 经导入解析为标准库路径的调用，例如 use std::mem::transmute 之后的 transmute(..)
+            let segments: Vec<String> = path.split("::").map(String::from).collect();
+            let is_std = matches!(segments.first().map(String::as_str), Some("std" | "core" | "alloc"));
+            is_std.then(|| {
+                let category = unsafe_apis::database().function(&segments);
+                Outcome::Call(category.map(|category| plain(UnsafeOperationType::UnsafeFunctionCall, Some(category))))
+            })
+        },
+        CandidateTarget::MethodCall(receiver, method) => {
+            let callees = resolver.resolve_call(method, Some(receiver));
+            if resolved(call_graph, &callees) {
+                return Some(Outcome::Call(is_unsafe(call_graph, &callees).then(|| plain(UnsafeOperationType::UnsafeMethodCall, None))));
+            }
+            // 接收者是已知的非本crate类型时，按类型在数据库中查找方法；数据库中没有的方法是安全的，
+            // 按名称推测的操作随之删除
+            let TypeHint::Type(receiver_type) = receiver else {
+                return None;
+            };
+            if resolver.resolve_hint(receiver).is_some() {
+                return None;
+            }
+            let database = unsafe_apis::database();
+            // 接收者类型中的 Option、Pin 等包装类型已被去掉，包装类型自身的unsafe方法
+            // （例如 unwrap_unchecked）无法确定，保留推测
+            if database.method_receivers(method).any(scope::is_transparent_wrapper) {
+                return None;
+            }
+            let category = database.method(receiver_type, method);
+            Some(Outcome::Call(category.map(|category| plain(UnsafeOperationType::UnsafeMethodCall, Some(category)))))
+        },
+    }
+}
+
+/// Whether a call reaches crate functions only, so that their declarations decide whether it is unsafe
+fn resolved(call_graph: &CallGraph, callees: &[String]) -> bool {
+    !callees.is_empty() && callees.iter().all(|callee| call_graph.functions.contains_key(callee))
+}
+
+/// Whether any of the possible callees is declared `unsafe fn`
+fn is_unsafe(call_graph: &CallGraph, callees: &[String]) -> bool {
+    callees.iter().any(|callee| call_graph.functions.get(callee).is_some_and(|info| info.is_unsafe_fn))
+}
@@ -1,5 +1,13 @@
 pub mod call_graph;
 pub mod analyzer;
+pub mod workspace;
+pub mod module_tree;
+pub mod type_resolver;
+pub mod public_api;
+pub mod candidates;
+pub mod source_map;
+pub mod cache;
 
 pub use call_graph::CallGraph;
-pub use analyzer::StaticAnalyzer;
\ No newline at end of file
+pub use analyzer::{StaticAnalyzer, Verbosity};
+pub use workspace::Workspace;
\ No newline at end of file
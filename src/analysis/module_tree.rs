@@ -0,0 +1,165 @@
+use std::path::{Component, Path, PathBuf};
+use syn::{Attribute, Expr, Item, Lit, Meta};
+
+use crate::models::{MacroScope, ModuleFile};
+use crate::visitors::{cfg, macros};
+
+impl ModuleFile {
+    /// The root file of a crate (`src/lib.rs`, `src/main.rs`, `src/bin/tool.rs`, ...)
+    pub fn crate_root(file_path: &Path) -> Self {
+        ModuleFile {
+            file_path: normalize_path(file_path),
+            module_path: vec!["crate".to_string()],
+            owns_directory: true,
+            cfg: Vec::new(),
+            macros: Vec::new(),
+            macro_use: false,
+        }
+    }
+    
+    /// Directory in which the file's `mod foo;` declarations are looked up
+    pub fn module_dir(&self) -> PathBuf {
+        let parent = self.file_path.parent().unwrap_or(Path::new("")).to_path_buf();
+        if self.owns_directory {
+            return parent;
+        }
+        match self.file_path.file_stem() {
+            Some(stem) => parent.join(stem),
+            None => parent,
+        }
+    }
+}
+
+/// The out-of-line `mod foo;` declarations of a file
+#[derive(Debug, Default)]
+pub struct DeclaredModules {
+    pub files: Vec<ModuleFile>,  // 找到的子模块文件
+    pub missing: Vec<PathBuf>,   // 查找过但不存在的文件：foo.rs、foo/mod.rs 或 #[path] 指定的文件
+    pub macros: Vec<MacroScope>, // 文件末尾可见的宏
+}
+
+/// Resolve the out-of-line `mod foo;` declarations of a parsed file, including those
+/// nested in inline `mod bar { ... }` blocks, to the files that define them. Each module
+/// records the `macro_rules!` scope at its declaration.
+pub fn declared_modules(file: &ModuleFile, syntax: &syn::File) -> DeclaredModules {
+    let mut declared = DeclaredModules::default();
+    // 文件顶层的#[path]相对于文件所在目录，而不是子模块目录
+    let path_base = file.file_path.parent().unwrap_or(Path::new("")).to_path_buf();
+    collect_modules(&syntax.items, &file.module_dir(), &path_base, &file.module_path, &file.cfg, &mut declared);
+    declared
+}
+
+fn collect_modules(
+    items: &[Item],
+    module_dir: &Path,
+    path_base: &Path,
+    module_path: &[String],
+    conditions: &[String],
+    declared: &mut DeclaredModules,
+) {
+    for item in items {
+        // 声明宏按文本顺序可见：只有之前定义的宏在 mod 声明加载的文件中可用
+        if let Item::Macro(item_macro) = item
+            && let Some(definition) = macros::definition(item_macro) {
+            declared.macros.push(MacroScope::Defined(definition));
+            continue;
+        }
+        let Item::Mod(item_mod) = item else {
+            continue;
+        };
+        
+        let name = item_mod.ident.to_string();
+        let mut child_path = module_path.to_vec();
+        child_path.push(name.clone());
+        let path_attr = path_attribute(&item_mod.attrs);
+        let macro_use = item_mod.attrs.iter().any(|attr| attr.path().is_ident("macro_use"));
+        // 子模块文件只在各级 mod 声明的 #[cfg] 条件都成立时编译
+        let mut child_conditions = conditions.to_vec();
+        child_conditions.extend(cfg::conditions(&item_mod.attrs));
+        
+        match &item_mod.content {
+            // 内联模块：其中的 mod 声明位于以模块名命名的子目录；其中定义的宏只在带有
+            // #[macro_use] 时在模块之后可用
+            Some((_, child_items)) => {
+                let child_dir = match &path_attr {
+                    Some(path) => path_base.join(path),
+                    None => module_dir.join(&name),
+                };
+                let outer_macros = declared.macros.clone();
+                collect_modules(child_items, &child_dir, &child_dir, &child_path, &child_conditions, declared);
+                if !macro_use {
+                    declared.macros = outer_macros;
+                }
+            }
+            None => {
+                let candidates = match &path_attr {
+                    // #[path] 加载的文件与 mod.rs 一样拥有所在目录
+                    Some(path) => vec![(path_base.join(path), true)],
+                    None => vec![
+                        (module_dir.join(format!("{}.rs", name)), false),
+                        (module_dir.join(&name).join("mod.rs"), true),
+                    ],
+                };
+                
+                // 找不到的模块文件（例如构建脚本生成的代码）直接跳过；记录查找过但不存在的文件，
+                // 它们出现后声明会加载另一个文件
+                let found = candidates.iter().position(|(path, _)| path.is_file());
+                declared.missing.extend(candidates[..found.unwrap_or(candidates.len())].iter().map(|(path, _)| normalize_path(path)));
+                let Some((file_path, owns_directory)) = found.map(|index| &candidates[index]) else {
+                    continue;
+                };
+                let file_path = normalize_path(file_path);
+                declared.files.push(ModuleFile {
+                    file_path: file_path.clone(),
+                    module_path: child_path,
+                    owns_directory: *owns_directory,
+                    cfg: child_conditions,
+                    macros: declared.macros.clone(),
+                    macro_use,
+                });
+                if macro_use {
+                    declared.macros.push(MacroScope::Module(file_path));
+                }
+            }
+        }
+    }
+}
+
+/// Value of a `#[path = "..."]` attribute
+fn path_attribute(attrs: &[Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| {
+        let Meta::NameValue(name_value) = &attr.meta else {
+            return None;
+        };
+        if !name_value.path.is_ident("path") {
+            return None;
+        }
+        match &name_value.value {
+            Expr::Lit(expr_lit) => match &expr_lit.lit {
+                Lit::Str(lit) => Some(lit.value()),
+                _ => None,
+            },
+            _ => None,
+        }
+    })
+}
+
+/// Remove `.` and `..` components without touching the file system, so that a file
+/// reached through `#[path = "../x.rs"]` is recognized as already visited
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(normalized.components().next_back(), Some(Component::Normal(_))) {
+                    normalized.pop();
+                } else {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
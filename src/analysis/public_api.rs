@@ -0,0 +1,134 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::analysis::call_graph::CallGraph;
+use crate::analysis::type_resolver::TypeResolver;
+use crate::models::{FunctionInfo, VisibilityKind};
+use crate::visitors::scope;
+
+/// Modules and items that other crates can name: a module is reachable when it is the crate
+/// root, a `pub mod` of a reachable module or re-exported by a `pub use` of one, and an item
+/// when its module is reachable or it is re-exported itself
+pub struct PublicApi {
+    /// Reachable module paths
+    pub modules: HashSet<String>,
+    /// Functions, types and traits re-exported by name from reachable modules
+    pub exported_items: HashSet<String>,
+}
+
+impl PublicApi {
+    pub fn compute(graph: &CallGraph) -> Self {
+        let mut known_modules: HashSet<&str> = HashSet::new();
+        let defining_modules = graph.functions.values().map(|info| info.module_path.as_str())
+            .chain(graph.type_definitions.values().map(|definition| definition.module_path.as_str()))
+            .chain(graph.traits.values().map(|definition| definition.module_path.as_str()))
+            .chain(graph.module_visibility.keys().map(String::as_str))
+            .chain(graph.imports.keys().map(String::as_str));
+        for module in defining_modules {
+            // 包括所有上级模块，单文件模式下的根模块路径为空
+            let mut current = module;
+            while known_modules.insert(current) {
+                match current.rsplit_once("::") {
+                    Some((parent, _)) => current = parent,
+                    None if !current.is_empty() => current = "",
+                    None => break,
+                }
+            }
+        }
+        
+        // 没有mod声明的模块是crate根（按文件位置推导模块路径时也是如此）
+        let mut queue: VecDeque<String> = known_modules.iter()
+            .filter(|module| !graph.module_visibility.contains_key(**module))
+            .map(|module| module.to_string())
+            .collect();
+        let mut modules: HashSet<String> = queue.iter().cloned().collect();
+        let mut exported_items = HashSet::new();
+        
+        let is_item = |path: &str| {
+            graph.functions.contains_key(path)
+                || graph.type_definitions.contains_key(path)
+                || graph.traits.contains_key(path)
+                || known_modules.contains(path)
+        };
+        
+        while let Some(module) = queue.pop_front() {
+            let mut reached = Vec::new();
+            
+            // pub mod 子模块
+            for (child, visibility) in &graph.module_visibility {
+                if *visibility == VisibilityKind::Public
+                    && child.rsplit_once("::").map(|(parent, _)| parent).unwrap_or("") == module {
+                    reached.push(child.clone());
+                }
+            }
+            
+            // pub use 重导出的模块和项
+            if let Some(imports) = graph.imports.get(&module) {
+                for name in &imports.exported {
+                    let Some(target) = graph.follow_imports(&scope::qualify(&module, name), is_item) else {
+                        continue;
+                    };
+                    if known_modules.contains(target.as_str()) {
+                        reached.push(target);
+                    } else {
+                        exported_items.insert(target);
+                    }
+                }
+                for glob in &imports.exported_globs {
+                    let target = graph.follow_imports(glob, |path| known_modules.contains(path))
+                        .unwrap_or_else(|| glob.clone());
+                    reached.push(target);
+                }
+            }
+            
+            for module in reached {
+                if modules.insert(module.clone()) {
+                    queue.push_back(module);
+                }
+            }
+        }
+        
+        PublicApi {
+            modules,
+            exported_items,
+        }
+    }
+    
+    /// Whether an item defined as `module_path::name` can be named from outside the crate
+    pub fn is_reachable(&self, module_path: &str, name: &str) -> bool {
+        self.modules.contains(module_path) || self.exported_items.contains(&scope::qualify(module_path, name))
+    }
+    
+    /// Functions of the graph that are `pub` and reachable from outside the crate. Inherent
+    /// methods and trait default methods also need their type or trait to be reachable, and
+    /// items nested in a function body never are.
+    pub fn public_functions(&self, graph: &CallGraph) -> HashSet<String> {
+        let resolver = TypeResolver::new(graph);
+        graph.functions.iter()
+            .filter(|(_, info)| info.visibility == VisibilityKind::Public)
+            // 函数体内定义的项无法从外部访问
+            .filter(|(_, info)| info.parent.is_none())
+            .filter(|(_, info)| self.is_function_reachable(graph, &resolver, info))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+    
+    fn is_function_reachable(&self, graph: &CallGraph, resolver: &TypeResolver, info: &FunctionInfo) -> bool {
+        match (&info.owner_type, &info.trait_name) {
+            // 自由函数
+            (None, None) => self.is_reachable(&info.module_path, &info.name),
+            // trait默认方法：trait本身需要可访问
+            (None, Some(trait_name)) => {
+                let trait_path = scope::qualify(&info.module_path, trait_name);
+                graph.traits.get(&trait_path).is_none_or(|definition| definition.visibility == VisibilityKind::Public)
+                    && self.is_reachable(&info.module_path, trait_name)
+            },
+            // 方法：impl的类型需要可访问，impl可以位于类型所在模块之外
+            (Some(owner), _) => match resolver.resolve_type(owner, &info.module_path)
+                .and_then(|type_path| graph.type_definitions.get(&type_path)) {
+                Some(definition) => definition.visibility == VisibilityKind::Public
+                    && self.is_reachable(&definition.module_path, &definition.name),
+                None => self.modules.contains(&info.module_path),
+            },
+        }
+    }
+}
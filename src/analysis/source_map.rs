@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use crate::models::{FileExtraction, FunctionInfo, PathNodeInfo, TypeDefinition, UnsafeDeclaration};
+use crate::visitors::scope;
+
+/// Locations of the items in the original source files of a crate, used to map findings in
+/// a pre-expanded copy of the crate (`cargo expand`, `rustc -Zunpretty=expanded`) back to the
+/// files they were written in. Expanded output carries no line markers, so items are matched
+/// by their crate-rooted paths; code generated by macros is attributed to the type or module
+/// it was generated for.
+pub struct SourceMap {
+    functions: HashMap<String, FunctionInfo>,
+    type_definitions: HashMap<String, TypeDefinition>,
+    module_files: HashMap<String, String>, // 模块路径 -> 定义该模块的文件
+    declarations: Vec<UnsafeDeclaration>,
+}
+
+impl SourceMap {
+    /// Build the map from the extractions of the original files
+    pub fn new(extractions: Vec<FileExtraction>) -> Self {
+        let mut functions = HashMap::new();
+        let mut type_definitions = HashMap::new();
+        let mut module_files = HashMap::new();
+        let mut file_modules = Vec::new();
+        let mut declarations = Vec::new();
+        
+        for extraction in extractions {
+            // 文件中的内联模块属于该文件；`mod foo;` 声明也会出现在这里，由foo自己的文件覆盖
+            for module_path in extraction.modules.keys() {
+                module_files.insert(module_path.clone(), extraction.file_path.clone());
+            }
+            file_modules.push((extraction.module_path.join("::"), extraction.file_path.clone()));
+            functions.extend(extraction.functions);
+            type_definitions.extend(extraction.type_definitions);
+            declarations.extend(extraction.unsafe_declarations);
+        }
+        module_files.extend(file_modules);
+        
+        SourceMap {
+            functions,
+            type_definitions,
+            module_files,
+            declarations,
+        }
+    }
+    
+    /// Point a path node at the original source. A function written in the source takes its
+    /// location, unsafe blocks and the locations of operations with the same code; a function
+    /// generated by a macro moves to the file of its type or module, without locations.
+    /// Nodes that match nothing keep their locations in the expanded file.
+    pub fn map_node(&self, node: &mut PathNodeInfo) {
+        if let Some(info) = self.functions.get(&node.full_path) {
+            node.file_path = info.file_path.clone();
+            node.span = info.span;
+            node.unsafe_blocks = info.unsafe_blocks.clone();
+            for op in &mut node.unsafe_operations {
+                let original = info.unsafe_operations.iter()
+                    .find(|original| original.code_snippet == op.code_snippet);
+                op.span = original.and_then(|original| original.span);
+                op.line_number = original.and_then(|original| original.line_number);
+            }
+            return;
+        }
+        
+        let Some(file_path) = self.generated_function_file(&node.full_path) else {
+            return;
+        };
+        node.file_path = file_path;
+        node.span = None;
+        node.unsafe_blocks.clear();
+        for op in &mut node.unsafe_operations {
+            op.span = None;
+            op.line_number = None;
+        }
+    }
+    
+    /// Point an unsafe declaration at the original source: the same declaration in the same
+    /// module, or the file of its module for declarations generated by macros
+    pub fn map_declaration(&self, declaration: &mut UnsafeDeclaration) {
+        let original = self.declarations.iter().find(|original| {
+            original.kind == declaration.kind
+                && original.trait_name == declaration.trait_name
+                && original.self_type == declaration.self_type
+                && original.module_path == declaration.module_path
+        });
+        if let Some(original) = original {
+            declaration.file_path = original.file_path.clone();
+            declaration.span = original.span;
+        } else if let Some(file_path) = self.module_file(&declaration.module_path) {
+            declaration.file_path = file_path;
+            declaration.span = None;
+        }
+        if let Some(definition) = &mut declaration.type_definition {
+            let path = scope::qualify(&definition.module_path, &definition.name);
+            self.map_type_definition(&path, definition);
+        }
+    }
+    
+    /// Replace a type definition from the expanded file with the one written in the source
+    pub fn map_type_definition(&self, path: &str, definition: &mut TypeDefinition) {
+        if let Some(original) = self.type_definitions.get(path) {
+            *definition = original.clone();
+        } else if let Some(file_path) = self.module_file(&definition.module_path) {
+            definition.file_path = file_path;
+        }
+    }
+    
+    /// File of a function that only exists in the expanded code: the file defining its owner
+    /// type for methods (e.g. `crate::a::<Foo as Clone>::clone` from `#[derive(Clone)]`),
+    /// otherwise the file of the innermost enclosing module
+    fn generated_function_file(&self, full_path: &str) -> Option<String> {
+        let (parent, _) = full_path.rsplit_once("::")?;
+        let (module_path, owner) = match parent.rsplit_once("::<") {
+            Some((module_path, owner)) => (module_path, owner.split(" as ").next().unwrap_or(owner)),
+            None => parent.rsplit_once("::").unwrap_or(("", parent)),
+        };
+        let type_path = format!("{}::{}", module_path, owner);
+        if let Some(definition) = self.type_definitions.get(&type_path) {
+            return Some(definition.file_path.clone());
+        }
+        self.module_file(parent)
+    }
+    
+    /// File of the innermost module enclosing an item path
+    fn module_file(&self, path: &str) -> Option<String> {
+        let mut current = path;
+        loop {
+            if let Some(file_path) = self.module_files.get(current) {
+                return Some(file_path.clone());
+            }
+            current = current.rsplit_once("::")?.0;
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::analysis::call_graph::CallGraph;
+use crate::models::TypeHint;
+use crate::visitors::scope;
+
+/// Resolves the receiver type hints recorded by `CallVisitor` against the type
+/// definitions and function signatures of the whole crate
+pub struct TypeResolver<'a> {
+    graph: &'a CallGraph,
+    /// Type paths by type name, for written types that are not fully qualified
+    types_by_name: HashMap<&'a str, Vec<&'a str>>,
+    /// Trait paths by trait name
+    traits_by_name: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> TypeResolver<'a> {
+    pub fn new(graph: &'a CallGraph) -> Self {
+        let mut types_by_name: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (path, definition) in &graph.type_definitions {
+            types_by_name.entry(definition.name.as_str()).or_default().push(path.as_str());
+        }
+        let mut traits_by_name: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (path, definition) in &graph.traits {
+            traits_by_name.entry(definition.name.as_str()).or_default().push(path.as_str());
+        }
+        
+        TypeResolver {
+            graph,
+            types_by_name,
+            traits_by_name,
+        }
+    }
+    
+    /// Methods called by `receiver.method()`; None when the receiver type is unknown
+    /// or the type has no such method in the crate
+    pub fn resolve_method_call(&self, receiver: &TypeHint, method: &str) -> Option<Vec<String>> {
+        let type_path = self.resolve_hint(receiver)?;
+        let methods = self.methods_of(&type_path, method);
+        if methods.is_empty() {
+            None
+        } else {
+            Some(methods)
+        }
+    }
+    
+    /// Crate functions a call may reach: a path call when `receiver` is None, otherwise a method
+    /// call on the receiver; empty when the callee is not a function of the crate
+    pub fn resolve_call(&self, callee: &str, receiver: Option<&TypeHint>) -> Vec<String> {
+        let dispatch = match receiver {
+            None => self.resolve_trait_call(callee),
+            Some(TypeHint::Bounds(traits)) => self.resolve_dispatch(traits, callee),
+            Some(_) => None,
+        };
+        if let Some((_, methods)) = dispatch {
+            return methods;
+        }
+        
+        match receiver {
+            None => self.graph.resolve_callee(callee).into_iter()
+                .filter(|path| self.graph.functions.contains_key(path))
+                .collect(),
+            Some(receiver) => self.resolve_method_call(receiver, callee).unwrap_or_default(),
+        }
+    }
+    
+    /// Path of the crate type a hint stands for
+    pub fn resolve_hint(&self, hint: &TypeHint) -> Option<String> {
+        match hint {
+            TypeHint::Type(written) => self.resolve_type(written, ""),
+            TypeHint::ReturnOf(function) => self.resolve_return_of(function),
+            TypeHint::Field(base, field) => {
+                let base_type = self.resolve_hint(base)?;
+                let definition = self.graph.type_definitions.get(&base_type)?;
+                let field_type = definition.field_types.get(field)?;
+                self.resolve_type(field_type, &definition.module_path)
+            },
+            TypeHint::MethodReturn(base, method) => {
+                let base_type = self.resolve_hint(base)?;
+                let returned = self.methods_of(&base_type, method).iter()
+                    .filter_map(|path| self.graph.functions.get(path))
+                    .find_map(|info| {
+                        let return_type = info.return_type.as_ref()?;
+                        self.resolve_type(return_type, &info.module_path)
+                    });
+                
+                // unwrap()、clone()、lock() 等方法返回接收者自身的类型
+                match returned {
+                    Some(type_path) => Some(type_path),
+                    None if scope::is_transparent_method(method) => Some(base_type),
+                    None => None,
+                }
+            },
+            // 只知道实现的trait时无法确定具体类型，由 resolve_dispatch 处理
+            TypeHint::Bounds(_) | TypeHint::Unknown => None,
+        }
+    }
+    
+    /// Implementations a call of `method` on a value only known to implement `traits` may
+    /// reach: the method in every impl of the trait declaring it, and the trait's default
+    /// method. Returns the trait name together with the targets.
+    pub fn resolve_dispatch(&self, traits: &[String], method: &str) -> Option<(String, Vec<String>)> {
+        let mut visited = HashSet::new();
+        traits.iter().find_map(|written| self.dispatch_targets(written, "", method, &mut visited))
+    }
+    
+    /// Trait dispatch for a path call like `Trait::method(&x)` on a trait of the crate
+    pub fn resolve_trait_call(&self, callee: &str) -> Option<(String, Vec<String>)> {
+        let (parent, method) = callee.rsplit_once("::")?;
+        let trait_path = self.resolve_trait(parent, "")?;
+        self.dispatch_targets(&trait_path, "", method, &mut HashSet::new())
+    }
+    
+    fn dispatch_targets(&self, written: &str, module_path: &str, method: &str, visited: &mut HashSet<String>) -> Option<(String, Vec<String>)> {
+        let trait_path = self.resolve_trait(written, module_path);
+        let definition = trait_path.as_ref().map(|path| &self.graph.traits[path]);
+        // 标准库等外部trait只能按名称匹配crate中的impl
+        let name = match definition {
+            Some(definition) => definition.name.clone(),
+            None => written.rsplit("::").next().unwrap_or(written).to_string(),
+        };
+        if !visited.insert(name.clone()) {
+            return None;
+        }
+        
+        if let (Some(trait_path), Some(definition)) = (&trait_path, definition) {
+            // 方法不是该trait声明的，到父trait中查找
+            if !definition.methods.iter().any(|m| m == method) {
+                return definition.supertraits.iter()
+                    .find_map(|supertrait| self.dispatch_targets(supertrait, &definition.module_path, method, visited));
+            }
+            
+            let mut targets = self.graph.trait_impls.get(&(name.clone(), method.to_string())).cloned().unwrap_or_default();
+            let default_method = format!("{}::{}", trait_path, method);
+            if self.graph.functions.contains_key(&default_method) {
+                targets.push(default_method);
+            }
+            targets.sort();
+            targets.dedup();
+            return if targets.is_empty() { None } else { Some((name, targets)) };
+        }
+        
+        let mut targets = self.graph.trait_impls.get(&(name.clone(), method.to_string()))?.clone();
+        targets.sort();
+        Some((name, targets))
+    }
+    
+    /// Resolve a type path as written in the given module
+    pub fn resolve_type(&self, written: &str, module_path: &str) -> Option<String> {
+        let types = &self.graph.type_definitions;
+        resolve_item_path(self.graph, written, module_path, &self.types_by_name, |path| types.contains_key(path), |path| &types[path].module_path)
+    }
+    
+    /// Resolve a trait path as written in the given module
+    pub fn resolve_trait(&self, written: &str, module_path: &str) -> Option<String> {
+        let traits = &self.graph.traits;
+        resolve_item_path(self.graph, written, module_path, &self.traits_by_name, |path| traits.contains_key(path), |path| &traits[path].module_path)
+    }
+    
+    /// Type returned by a function call; tuple structs and enum variants construct their own type
+    fn resolve_return_of(&self, function: &str) -> Option<String> {
+        let mut functions = self.graph.resolve_callee(function);
+        let owner_type = function.rsplit_once("::")
+            .and_then(|(parent, name)| Some((self.resolve_type(parent, "")?, name)));
+        
+        // Type::function 的路径可能不精确（例如经过use导入），按类型查找关联函数
+        if let Some((type_path, name)) = &owner_type
+            && !functions.iter().any(|path| self.graph.functions.contains_key(path)) {
+            functions = self.methods_of(type_path, name);
+        }
+        
+        let returned = functions.iter()
+            .filter_map(|path| self.graph.functions.get(path))
+            .find_map(|info| {
+                let return_type = info.return_type.as_ref()?;
+                self.resolve_type(return_type, &info.module_path)
+            });
+        if returned.is_some() {
+            return returned;
+        }
+        
+        if functions.iter().any(|path| self.graph.functions.contains_key(path)) {
+            return None;
+        }
+        self.resolve_type(function, "").or(owner_type.map(|(type_path, _)| type_path))
+    }
+    
+    /// Methods named `method` of a crate type, including trait methods implemented for it.
+    /// Impl blocks usually sit in the module of the type; impls elsewhere are matched by
+    /// type name when their module has no type of that name.
+    pub fn methods_of(&self, type_path: &str, method: &str) -> Vec<String> {
+        let Some(definition) = self.graph.type_definitions.get(type_path) else {
+            return Vec::new();
+        };
+        let Some(methods) = self.graph.methods_by_owner.get(&(definition.name.clone(), method.to_string())) else {
+            return Vec::new();
+        };
+        
+        let mut candidates: Vec<String> = methods.iter()
+            .filter(|path| self.graph.functions[*path].module_path == definition.module_path)
+            .cloned()
+            .collect();
+        if candidates.is_empty() {
+            candidates = methods.iter()
+                .filter(|path| {
+                    let module_path = &self.graph.functions[*path].module_path;
+                    !self.graph.type_definitions.contains_key(&scope::qualify(module_path, &definition.name))
+                })
+                .cloned()
+                .collect();
+        }
+        
+        candidates.sort();
+        candidates
+    }
+}
+
+// Resolve the path of a type or trait as written in a module: the path itself, a path relative
+// to the module (including `self::` and `super::`), either of them through imports and
+// re-exports, else by name, preferring the module's own item
+fn resolve_item_path<'a>(
+    graph: &CallGraph,
+    written: &str,
+    module_path: &str,
+    by_name: &HashMap<&'a str, Vec<&'a str>>,
+    is_known: impl Fn(&str) -> bool,
+    module_of: impl Fn(&str) -> &'a String,
+) -> Option<String> {
+    if is_known(written) {
+        return Some(written.to_string());
+    }
+    
+    // 相对于所在模块的路径，包括 self:: 和 super::
+    let mut module: Vec<&str> = module_path.split("::").filter(|s| !s.is_empty()).collect();
+    let mut segments: Vec<&str> = written.split("::").collect();
+    while let Some(first) = segments.first() {
+        match *first {
+            "self" => {},
+            "super" => {
+                module.pop();
+            },
+            _ => break,
+        }
+        segments.remove(0);
+    }
+    let relative = scope::qualify(&module.join("::"), &segments.join("::"));
+    if let Some(path) = graph.follow_imports(&relative, &is_known).or_else(|| graph.follow_imports(written, &is_known)) {
+        return Some(path);
+    }
+    
+    // 路径无法解析时按名称查找，同名项不唯一时优先当前模块
+    let name = segments.last()?;
+    let candidates = by_name.get(name)?;
+    match candidates.as_slice() {
+        [only] => Some(only.to_string()),
+        _ => candidates.iter()
+            .find(|path| module_of(path) == module_path)
+            .map(|path| path.to_string()),
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use globset::Glob;
+use serde::Deserialize;
+
+use crate::models::{CrateTarget, TargetKind};
+
+/// A Cargo workspace (or a single package) read from `Cargo.toml`
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub packages: Vec<Package>,
+}
+
+/// A package and the targets the analyzer knows how to read
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub name: String,
+    pub manifest_dir: PathBuf,
+    pub targets: Vec<PackageTarget>,
+    pub features: BTreeMap<String, Vec<String>>, // [features] 表：特性 -> 它启用的特性和依赖
+}
+
+/// A target together with the root file of its crate (`src/lib.rs`, `src/main.rs`, ...)
+#[derive(Debug, Clone)]
+pub struct PackageTarget {
+    pub target: CrateTarget,
+    pub root_file: PathBuf,
+}
+
+// Only the parts of the manifest that are needed to locate targets
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Manifest {
+    package: Option<ManifestPackage>,
+    workspace: Option<ManifestWorkspace>,
+    lib: Option<ManifestTarget>,
+    bin: Vec<ManifestTarget>,
+    features: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestPackage {
+    name: String,
+    #[serde(default)]
+    autobins: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ManifestWorkspace {
+    members: Vec<String>,
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ManifestTarget {
+    name: Option<String>,
+    path: Option<String>,
+}
+
+impl Workspace {
+    /// Read `Cargo.toml` in the given directory; returns None when there is no manifest
+    pub fn load(dir_path: &Path) -> io::Result<Option<Workspace>> {
+        let manifest_path = dir_path.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            return Ok(None);
+        }
+        
+        let manifest = read_manifest(&manifest_path)?;
+        let mut packages = Vec::new();
+        
+        // 根目录本身也可以是一个包（非虚拟工作区）
+        if let Some(package) = Package::from_manifest(dir_path, &manifest) {
+            packages.push(package);
+        }
+        
+        if let Some(workspace) = &manifest.workspace {
+            let excluded: Vec<PathBuf> = workspace.exclude.iter()
+                .map(|path| dir_path.join(path))
+                .collect();
+            
+            for member_dir in expand_members(dir_path, &workspace.members) {
+                if excluded.iter().any(|path| member_dir.starts_with(path)) {
+                    continue;
+                }
+                
+                let member_manifest = member_dir.join("Cargo.toml");
+                if !member_manifest.is_file() {
+                    continue;
+                }
+                
+                match read_manifest(&member_manifest) {
+                    Ok(manifest) => {
+                        if let Some(package) = Package::from_manifest(&member_dir, &manifest) {
+                            packages.push(package);
+                        }
+                    }
+                    Err(e) => eprintln!("Error reading {}: {}", member_manifest.display(), e),
+                }
+            }
+        }
+        
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        packages.dedup_by(|a, b| a.manifest_dir == b.manifest_dir);
+        
+        Ok(Some(Workspace {
+            root: dir_path.to_path_buf(),
+            packages,
+        }))
+    }
+}
+
+impl Package {
+    fn from_manifest(manifest_dir: &Path, manifest: &Manifest) -> Option<Package> {
+        let package = manifest.package.as_ref()?;
+        let mut targets = Vec::new();
+        
+        // 库目标：[lib] path 或默认的 src/lib.rs
+        let lib_path = manifest.lib.as_ref()
+            .and_then(|lib| lib.path.as_ref())
+            .map(|path| manifest_dir.join(path))
+            .unwrap_or_else(|| manifest_dir.join("src/lib.rs"));
+        if lib_path.is_file() {
+            let name = manifest.lib.as_ref()
+                .and_then(|lib| lib.name.clone())
+                .unwrap_or_else(|| package.name.replace('-', "_"));
+            targets.push(PackageTarget::new(&package.name, name, TargetKind::Lib, lib_path));
+        }
+        
+        // 显式声明的二进制目标
+        for bin in &manifest.bin {
+            let Some(name) = bin.name.clone() else {
+                continue;
+            };
+            let root_file = match &bin.path {
+                Some(path) => manifest_dir.join(path),
+                None => default_bin_path(manifest_dir, &name, &package.name),
+            };
+            if root_file.is_file() {
+                targets.push(PackageTarget::new(&package.name, name, TargetKind::Bin, root_file));
+            }
+        }
+        
+        // 自动发现的二进制目标：src/main.rs 和 src/bin/ 下的文件
+        if package.autobins.unwrap_or(true) {
+            let mut discovered = Vec::new();
+            let main_rs = manifest_dir.join("src/main.rs");
+            if main_rs.is_file() {
+                discovered.push((package.name.clone(), main_rs));
+            }
+            
+            if let Ok(entries) = fs::read_dir(manifest_dir.join("src/bin")) {
+                for entry in entries.filter_map(|e| e.ok()) {
+                    let path = entry.path();
+                    if path.is_file() && path.extension().is_some_and(|ext| ext == "rs") {
+                        if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                            discovered.push((stem.to_string(), path));
+                        }
+                    } else if path.join("main.rs").is_file()
+                        && let Some(name) = path.file_name().and_then(|s| s.to_str()) {
+                        discovered.push((name.to_string(), path.join("main.rs")));
+                    }
+                }
+            }
+            
+            for (name, root_file) in discovered {
+                let already_declared = targets.iter()
+                    .any(|t| t.root_file == root_file || (t.target.kind == TargetKind::Bin && t.target.name == name));
+                if !already_declared {
+                    targets.push(PackageTarget::new(&package.name, name, TargetKind::Bin, root_file));
+                }
+            }
+        }
+        
+        targets.sort_by(|a, b| a.target.cmp(&b.target));
+        
+        Some(Package {
+            name: package.name.clone(),
+            manifest_dir: manifest_dir.to_path_buf(),
+            targets,
+            features: manifest.features.clone(),
+        })
+    }
+    
+    /// Features enabled when the package is built with the requested features (`name` or
+    /// `package/name`) and, unless disabled, `default`, including the features they enable
+    /// in turn. Features of dependencies are not tracked; an optional dependency enabled
+    /// through `dep/feature` counts as its implicit feature.
+    pub fn enabled_features(&self, requested: &[String], default_features: bool) -> BTreeSet<String> {
+        let mut pending: Vec<String> = requested.iter()
+            .filter_map(|feature| match feature.split_once('/') {
+                Some((package, feature)) => (package == self.name).then(|| feature.to_string()),
+                None => Some(feature.clone()),
+            })
+            .collect();
+        if default_features && self.features.contains_key("default") {
+            pending.push("default".to_string());
+        }
+        
+        let mut enabled = BTreeSet::new();
+        while let Some(feature) = pending.pop() {
+            if !enabled.insert(feature.clone()) {
+                continue;
+            }
+            for implied in self.features.get(&feature).into_iter().flatten() {
+                // dep:foo 只启用依赖，foo?/bar 不会启用可选依赖本身
+                if implied.starts_with("dep:") || implied.contains("?/") {
+                    continue;
+                }
+                let name = implied.split('/').next().unwrap_or(implied);
+                pending.push(name.to_string());
+            }
+        }
+        enabled
+    }
+}
+
+impl PackageTarget {
+    fn new(package: &str, name: String, kind: TargetKind, root_file: PathBuf) -> Self {
+        PackageTarget {
+            target: CrateTarget {
+                package: package.to_string(),
+                name,
+                kind,
+            },
+            root_file,
+        }
+    }
+}
+
+fn read_manifest(manifest_path: &Path) -> io::Result<Manifest> {
+    let content = fs::read_to_string(manifest_path)?;
+    toml::from_str(&content).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Invalid manifest {}: {}", manifest_path.display(), e))
+    })
+}
+
+// Cargo looks for src/bin/<name>.rs, then src/bin/<name>/main.rs, and uses src/main.rs
+// for the binary named after the package
+fn default_bin_path(manifest_dir: &Path, name: &str, package_name: &str) -> PathBuf {
+    let bin_file = manifest_dir.join("src/bin").join(format!("{}.rs", name));
+    if bin_file.is_file() {
+        return bin_file;
+    }
+    let bin_dir_main = manifest_dir.join("src/bin").join(name).join("main.rs");
+    if bin_dir_main.is_file() || name != package_name {
+        return bin_dir_main;
+    }
+    manifest_dir.join("src/main.rs")
+}
+
+/// Expand workspace member entries; a glob is allowed in the last path component (e.g. `crates/*`)
+fn expand_members(root: &Path, members: &[String]) -> Vec<PathBuf> {
+    let mut member_dirs = Vec::new();
+    
+    for member in members {
+        let member_path = Path::new(member);
+        let last = member_path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+        
+        if !last.contains(['*', '?', '[']) {
+            member_dirs.push(root.join(member_path));
+            continue;
+        }
+        
+        let matcher = match Glob::new(last) {
+            Ok(glob) => glob.compile_matcher(),
+            Err(e) => {
+                eprintln!("Invalid workspace member pattern '{}': {}", member, e);
+                continue;
+            }
+        };
+        
+        let parent = root.join(member_path.parent().unwrap_or(Path::new("")));
+        if let Ok(entries) = fs::read_dir(&parent) {
+            let mut matched: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|path| path.is_dir() && path.file_name().is_some_and(|name| matcher.is_match(name)))
+                .collect();
+            matched.sort();
+            member_dirs.extend(matched);
+        }
+    }
+    
+    member_dirs
+}
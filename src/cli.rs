@@ -0,0 +1,274 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use clap::{ArgAction, Parser};
+
+use analysis::{AnalysisConfig, OutputFormat, StaticAnalyzer, Verbosity};
+
+/// Find call paths from public Rust APIs to internal unsafe code
+#[derive(Debug, Parser)]
+#[command(name = "analysis", version, about)]
+pub struct Cli {
+    /// Rust file or directory to analyze
+    pub input: PathBuf,
+    
+    /// Output file; its extension picks the format when --format is not given
+    /// [default: ./<input name>_unsafe_paths.rs or ./<file stem>_analysis.rs]
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+    
+    /// Output format(s): rust, json, jsonl, sarif. Repeat or comma-separate to write several
+    /// reports; the output file extension is then replaced per format
+    #[arg(short, long, value_name = "FORMAT", value_delimiter = ',')]
+    pub format: Vec<OutputFormat>,
+    
+    /// Project configuration file [default: analysis.toml next to the nearest Cargo.toml]
+    #[arg(short, long, value_name = "FILE", conflicts_with = "no_config")]
+    pub config: Option<PathBuf>,
+    
+    /// Ignore any analysis.toml
+    #[arg(long)]
+    pub no_config: bool,
+    
+    /// Maximum depth of call chains to search [default: 20]
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+    
+    /// Skip files larger than this many megabytes [default: 10]
+    #[arg(long, value_name = "MB")]
+    pub file_size_limit: Option<u64>,
+    
+    /// Give up on a single file after this many seconds [default: 30]
+    #[arg(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+    
+    /// Number of worker threads [default: number of CPUs]
+    #[arg(short = 'j', long, value_name = "N")]
+    pub threads: Option<usize>,
+    
+    /// Also analyze binary targets of Cargo packages (only library targets by default)
+    #[arg(long)]
+    pub bins: bool,
+    
+    /// Treat the input as a whole crate expanded into one file by `cargo expand` or
+    /// `rustc -Zunpretty=expanded`, so that code generated by derives and procedural macros
+    /// is analyzed too
+    #[arg(long)]
+    pub expanded: bool,
+    
+    /// Original sources of an --expanded input, used to map findings back to their files
+    /// [default: the input's directory if it contains lib.rs or main.rs, directly or under src/]
+    #[arg(long, value_name = "DIR", requires = "expanded")]
+    pub source_dir: Option<PathBuf>,
+    
+    /// Evaluate `#[cfg]` for this target triple instead of the host, e.g. x86_64-pc-windows-msvc
+    #[arg(long, value_name = "TRIPLE")]
+    pub target: Option<String>,
+    
+    /// Cargo features to enable (comma-separated or repeatable; `package/feature` selects
+    /// the package in a workspace)
+    #[arg(short = 'F', long, value_name = "FEATURES", value_delimiter = ',')]
+    pub features: Vec<String>,
+    
+    /// Enable all features
+    #[arg(long)]
+    pub all_features: bool,
+    
+    /// Do not enable the `default` feature
+    #[arg(long)]
+    pub no_default_features: bool,
+    
+    /// Enable an extra cfg option, like rustc: `name` or `name="value"` (repeatable)
+    #[arg(long = "cfg", value_name = "SPEC")]
+    pub cfg_options: Vec<String>,
+    
+    /// Analyze the code of every `#[cfg]` configuration and annotate each finding with the
+    /// condition under which it exists
+    #[arg(long, conflicts_with_all = ["target", "features", "all_features", "no_default_features", "cfg_options"])]
+    pub all_cfgs: bool,
+    
+    /// Only analyze files matching this glob, relative to the input directory (repeatable;
+    /// replaces `include` from the configuration file)
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+    
+    /// Skip files matching this glob, relative to the input directory (repeatable;
+    /// replaces `exclude` from the configuration file)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+    
+    /// Save the findings as a baseline that later runs can be compared with
+    #[arg(long, value_name = "FILE")]
+    pub save_baseline: Option<PathBuf>,
+    
+    /// Compare the findings with a saved baseline, list the added, removed and changed ones
+    /// and exit with status 1 if there are new ones
+    #[arg(long, value_name = "FILE")]
+    pub baseline: Option<PathBuf>,
+    
+    /// Also write the differences from the --baseline as a JSON document
+    #[arg(long, value_name = "FILE", requires = "baseline")]
+    pub diff_output: Option<PathBuf>,
+    
+    /// Parse every file again instead of reusing cached extractions of unchanged files
+    #[arg(long)]
+    pub no_cache: bool,
+    
+    /// Directory of the extraction cache [default: target/analysis-cache in the input directory]
+    #[arg(long, value_name = "DIR", conflicts_with = "no_cache")]
+    pub cache_dir: Option<PathBuf>,
+    
+    /// Print per-file and call graph details
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+    
+    /// Only print errors
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+impl Cli {
+    pub fn verbosity(&self) -> Verbosity {
+        if self.quiet {
+            Verbosity::Quiet
+        } else if self.verbose > 0 {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        }
+    }
+    
+    /// Path of the configuration file to use, if any
+    pub fn config_path(&self) -> Option<PathBuf> {
+        if self.no_config {
+            return None;
+        }
+        self.config.clone().or_else(|| AnalysisConfig::discover(&self.input))
+    }
+    
+    /// Original sources to map the findings of an --expanded input to, if any
+    pub fn expanded_source_dir(&self) -> Option<PathBuf> {
+        if self.source_dir.is_some() {
+            return self.source_dir.clone();
+        }
+        // cargo expand 的输出通常保存在crate目录中
+        let dir = self.input.parent()?;
+        let source_root = StaticAnalyzer::find_source_root(dir);
+        ["lib.rs", "main.rs"].iter()
+            .any(|root| source_root.join(root).is_file())
+            .then(|| dir.to_path_buf())
+    }
+    
+    /// Directory of the extraction cache, if caching is enabled; only directory inputs are
+    /// cached by default
+    pub fn cache_dir(&self) -> Option<PathBuf> {
+        if self.no_cache {
+            return None;
+        }
+        if self.cache_dir.is_some() {
+            return self.cache_dir.clone();
+        }
+        self.input.is_dir().then(|| self.input.join("target").join("analysis-cache"))
+    }
+    
+    /// Load the configuration file and apply command-line overrides on top of it
+    pub fn load_config(&self) -> io::Result<(AnalysisConfig, Option<PathBuf>)> {
+        let config_path = self.config_path();
+        let mut config = match &config_path {
+            Some(path) => AnalysisConfig::load(path)?,
+            None => AnalysisConfig::default(),
+        };
+        
+        // 命令行参数优先于配置文件
+        if self.max_depth.is_some() {
+            config.max_search_depth = self.max_depth;
+        }
+        if self.file_size_limit.is_some() {
+            config.file_size_limit_mb = self.file_size_limit;
+        }
+        if self.timeout.is_some() {
+            config.timeout_seconds = self.timeout;
+        }
+        if !self.include.is_empty() {
+            config.include = self.include.clone();
+        }
+        if !self.exclude.is_empty() {
+            config.exclude = self.exclude.clone();
+        }
+        if self.target.is_some() {
+            config.cfg.target = self.target.clone();
+        }
+        if !self.features.is_empty() {
+            config.cfg.features = self.features.clone();
+        }
+        if !self.cfg_options.is_empty() {
+            config.cfg.options = self.cfg_options.clone();
+        }
+        config.cfg.all_features |= self.all_features;
+        config.cfg.no_default_features |= self.no_default_features;
+        config.cfg.all_configurations |= self.all_cfgs;
+        
+        Ok((config, config_path))
+    }
+    
+    /// Report files to write, one per requested format
+    pub fn output_targets(&self) -> Vec<(PathBuf, OutputFormat)> {
+        let base_path = self.output.clone().unwrap_or_else(|| default_output_path(&self.input));
+        
+        // 未指定格式时按输出文件扩展名选择，保持与之前的行为一致
+        if self.format.is_empty() {
+            let format = OutputFormat::from_path(&base_path);
+            return vec![(base_path, format)];
+        }
+        
+        // 去掉重复的格式，保留第一次出现的顺序
+        let mut formats = Vec::new();
+        for format in &self.format {
+            if !formats.contains(format) {
+                formats.push(*format);
+            }
+        }
+        
+        // 只有一种格式且显式给出输出文件时，直接使用该文件
+        if formats.len() == 1 && self.output.is_some() {
+            return vec![(base_path, formats[0])];
+        }
+        
+        formats.into_iter()
+            .map(|format| (base_path.with_extension(format.extension()), format))
+            .collect()
+    }
+}
+
+/// Default report path in the current directory, named after the input
+fn default_output_path(input_path: &Path) -> PathBuf {
+    // 获取当前工作目录
+    let current_dir = match std::env::current_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("Error getting current directory: {}, falling back to input directory", e);
+            if input_path.is_dir() {
+                input_path.to_path_buf()
+            } else {
+                input_path.parent().unwrap_or(Path::new(".")).to_path_buf()
+            }
+        }
+    };
+    
+    // 从输入路径获取一个有意义的文件名
+    let file_name = if input_path.is_dir() {
+        // 如果输入是目录，使用目录名作为文件名的一部分
+        let dir_name = input_path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("project");
+        format!("{}_unsafe_paths.rs", dir_name)
+    } else {
+        // 如果输入是文件，使用文件名作为文件名的一部分
+        let stem = input_path.file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or("file");
+        format!("{}_analysis.rs", stem)
+    };
+    
+    // 将文件放在当前目录下
+    current_dir.join(file_name)
+}
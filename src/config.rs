@@ -0,0 +1,146 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::visitors::cfg::CfgSet;
+use crate::{DEFAULT_FILE_SIZE_LIMIT, DEFAULT_MAX_SEARCH_DEPTH, DEFAULT_TIMEOUT_SECONDS};
+
+/// Per-project settings, read from `analysis.toml` next to `Cargo.toml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalysisConfig {
+    pub max_search_depth: Option<usize>,
+    pub file_size_limit_mb: Option<u64>,
+    pub timeout_seconds: Option<u64>,
+    pub known_unsafe_functions: Vec<String>, // 额外视为unsafe的函数路径，例如 "ffi::raw_read"
+    pub unsafe_keywords: Vec<String>,        // 额外的方法名关键词，供 has_unsafe_keywords 使用
+    pub ignored_modules: Vec<String>,        // 从调用图中排除的模块，例如 "crate::tests"
+    pub include: Vec<String>,                // 只分析匹配的文件（相对于分析目录的glob）
+    pub exclude: Vec<String>,                // 跳过匹配的文件
+    pub entry_points: EntryPointRules,
+    pub cfg: CfgConfig,
+}
+
+/// Rules adjusting which functions are used as path starting points
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EntryPointRules {
+    pub include: Vec<String>, // 额外作为入口点的函数路径模式，例如 "crate::ffi::*"
+    pub exclude: Vec<String>, // 不作为入口点的函数路径模式
+}
+
+/// Which `#[cfg]` configuration of the code is analyzed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CfgConfig {
+    pub target: Option<String>,     // 目标三元组，例如 "x86_64-pc-windows-msvc"，默认为运行分析器的平台
+    pub features: Vec<String>,      // 启用的Cargo特性，工作区中可以写作 "包名/特性"
+    pub all_features: bool,         // 启用所有特性
+    pub no_default_features: bool,  // 不启用 default 特性
+    pub options: Vec<String>,       // 额外的cfg选项，与 rustc --cfg 相同，例如 "tokio_unstable" 或 "foo=\"bar\""
+    pub all_configurations: bool,   // 不按配置删除代码，而是标注每个发现存在的条件
+}
+
+impl CfgConfig {
+    /// The target and extra options to evaluate `#[cfg]` against; features are added per
+    /// crate since default features depend on the package
+    pub fn cfg_set(&self) -> io::Result<CfgSet> {
+        let mut set = match &self.target {
+            Some(target) => CfgSet::for_target(target)?,
+            None => CfgSet::host(),
+        };
+        for option in &self.options {
+            set = set.with_option(option)?;
+        }
+        if self.all_features {
+            set = set.with_all_features();
+        }
+        Ok(set)
+    }
+}
+
+impl AnalysisConfig {
+    pub const FILE_NAME: &'static str = "analysis.toml";
+    
+    /// Load a configuration file
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Invalid configuration {}: {}", path.display(), e))
+        })
+    }
+    
+    /// Find `analysis.toml` next to the nearest `Cargo.toml` at or above the input path,
+    /// or directly in the input directory when there is no manifest
+    pub fn discover(input_path: &Path) -> Option<PathBuf> {
+        let start = if input_path.is_dir() {
+            input_path
+        } else {
+            input_path.parent().unwrap_or(Path::new("."))
+        };
+        
+        for dir in start.ancestors() {
+            if dir.join("Cargo.toml").is_file() {
+                let config_path = dir.join(Self::FILE_NAME);
+                return config_path.is_file().then_some(config_path);
+            }
+        }
+        
+        let config_path = start.join(Self::FILE_NAME);
+        config_path.is_file().then_some(config_path)
+    }
+    
+    pub fn max_search_depth(&self) -> usize {
+        self.max_search_depth.unwrap_or(DEFAULT_MAX_SEARCH_DEPTH)
+    }
+    
+    pub fn file_size_limit_mb(&self) -> u64 {
+        self.file_size_limit_mb.unwrap_or(DEFAULT_FILE_SIZE_LIMIT)
+    }
+    
+    pub fn timeout_seconds(&self) -> u64 {
+        self.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECONDS)
+    }
+    
+    /// Check if a function or module path lies in one of the ignored modules
+    pub fn is_ignored_module(&self, path: &str) -> bool {
+        self.ignored_modules.iter().any(|module| {
+            path == module || path.strip_prefix(module.as_str()).is_some_and(|rest| rest.starts_with("::"))
+        })
+    }
+}
+
+/// Compiled glob patterns, matched against file paths or `::`-separated item paths
+#[derive(Debug, Clone, Default)]
+pub struct PathPatterns {
+    set: Option<GlobSet>,
+}
+
+impl PathPatterns {
+    pub fn new(patterns: &[String]) -> io::Result<Self> {
+        if patterns.is_empty() {
+            return Ok(PathPatterns::default());
+        }
+        
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = Glob::new(pattern)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid glob '{}': {}", pattern, e)))?;
+            builder.add(glob);
+        }
+        
+        let set = builder.build()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        Ok(PathPatterns { set: Some(set) })
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.set.is_none()
+    }
+    
+    pub fn is_match<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.set.as_ref().is_some_and(|set| set.is_match(path))
+    }
+}
@@ -2,9 +2,14 @@ pub mod models;
 pub mod visitors;
 pub mod analysis;
 pub mod utils;
+pub mod report;
+pub mod config;
+pub mod unsafe_apis;
 
 // Re-export main types for convenience
-pub use analysis::analyzer::StaticAnalyzer;
+pub use analysis::analyzer::{StaticAnalyzer, Verbosity};
+pub use report::OutputFormat;
+pub use config::AnalysisConfig;
 
 // Default configuration constants
 pub const DEFAULT_MAX_SEARCH_DEPTH: usize = 20;
@@ -1,6 +1,11 @@
-use std::path::PathBuf;
-// 从分析模块导入所需的结构体和常量
-use analysis::{StaticAnalyzer, DEFAULT_MAX_SEARCH_DEPTH, DEFAULT_FILE_SIZE_LIMIT, DEFAULT_TIMEOUT_SECONDS};
+mod cli;
+
+use std::fs::File;
+use std::io::BufWriter;
+use clap::Parser;
+// 从分析模块导入所需的结构体
+use analysis::{StaticAnalyzer, Verbosity};
+use cli::Cli;
 
 fn main() -> std::io::Result<()> {
     // 设置 panic 处理程序以防止在 panic 时立即退出
@@ -9,107 +14,157 @@ fn main() -> std::io::Result<()> {
         eprintln!("Attempting to recover and continue...");
     }));
 
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: {} <Rust project or file path> [output file]", args[0]);
-        eprintln!("  <Rust project or file path>: Path to a Rust file or directory");
-        eprintln!("  [output file]: Optional path to save results (default: ./unsafe_paths.rs)");
-        return Ok(());
+    let cli = Cli::parse();
+    let verbosity = cli.verbosity();
+    
+    if let Some(threads) = cli.threads
+        && let Err(e) = rayon::ThreadPoolBuilder::new().num_threads(threads).build_global() {
+        eprintln!("Error configuring {} worker threads: {}", threads, e);
     }
-
-    let input_path = PathBuf::from(&args[1]);
-    let output_path = if args.len() >= 3 {
-        PathBuf::from(&args[2])
-    } else {
-        // 获取当前工作目录
-        let current_dir = match std::env::current_dir() {
-            Ok(dir) => dir,
-            Err(e) => {
-                eprintln!("Error getting current directory: {}, falling back to input directory", e);
-                if input_path.is_dir() {
-                    input_path.clone()
-                } else {
-                    input_path.parent().unwrap_or(&PathBuf::from(".")).to_path_buf()
-                }
-            }
-        };
-        
-        // 从输入路径获取一个有意义的文件名
-        let file_name = if input_path.is_dir() {
-            // 如果输入是目录，使用目录名作为文件名的一部分
-            let dir_name = input_path.file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("project");
-            format!("{}_unsafe_paths.rs", dir_name)
-        } else {
-            // 如果输入是文件，使用文件名作为文件名的一部分
-            let stem = input_path.file_stem()
-                .and_then(|name| name.to_str())
-                .unwrap_or("file");
-            format!("{}_analysis.rs", stem)
-        };
-        
-        // 将文件放在当前目录下
-        current_dir.join(file_name)
+    
+    let input_path = cli.input.clone();
+    let output_targets = cli.output_targets();
+    
+    // 配置文件无效时直接报错退出，而不是静默使用默认值
+    let (config, config_path) = match cli.load_config() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error loading configuration: {}", e);
+            std::process::exit(1);
+        }
     };
+    if let Some(path) = &config_path
+        && verbosity >= Verbosity::Normal {
+        println!("Using configuration: {}", path.display());
+    }
+    
+    // 使用基线时，分析不完整（路径错误、文件无法解析或panic）也以非零状态退出，
+    // 否则缺少的发现会被当作已经消除
+    let uses_baseline = cli.baseline.is_some() || cli.save_baseline.is_some();
     
     // Use catch_unwind to capture all possible panics
     let result = std::panic::catch_unwind(|| {
         let analyzer = StaticAnalyzer::new(
-            DEFAULT_MAX_SEARCH_DEPTH,
-            DEFAULT_FILE_SIZE_LIMIT,
-            DEFAULT_TIMEOUT_SECONDS
-        );
+            config.max_search_depth(),
+            config.file_size_limit_mb(),
+            config.timeout_seconds()
+        )
+        .with_verbosity(verbosity)
+        .with_binaries(cli.bins)
+        .with_config(config)?;
+        let analyzer = match cli.cache_dir() {
+            Some(cache_dir) => analyzer.with_cache(&cache_dir),
+            None => analyzer,
+        };
         
         // Validate path existence
         if !input_path.exists() {
             eprintln!("Error: Path does not exist: {}", input_path.display());
-            return Ok(());
+            return Ok(uses_baseline);
         }
         
-        println!("Starting analysis: {}", input_path.display());
+        if verbosity >= Verbosity::Normal {
+            println!("Starting analysis: {}", input_path.display());
+        }
         
         // If it's a directory, analyze all files in parallel, otherwise analyze single file
-        if input_path.is_dir() {
+        let mut incomplete = false;
+        if cli.expanded {
+            if !input_path.is_file() {
+                eprintln!("Expanded input must be a single file: {}", input_path.display());
+                return Ok(uses_baseline);
+            }
+            let source_dir = cli.expanded_source_dir();
+            if source_dir.is_none() && verbosity >= Verbosity::Normal {
+                println!("No original sources found, findings refer to the expanded file");
+            }
+            if let Err(e) = analyzer.analyze_expanded(&input_path, source_dir.as_deref()) {
+                eprintln!("Error analyzing expanded file: {}, but will continue with processed results", e);
+                incomplete = true;
+            }
+        } else if input_path.is_dir() {
             // Add error recovery handling
             if let Err(e) = analyzer.analyze_directory_parallel(&input_path) {
                 eprintln!("Error analyzing directory: {}, but will continue with processed files", e);
+                incomplete = true;
                 // Continue to try writing results even if there's an error
             }
-        } else if input_path.extension().map_or(false, |ext| ext == "rs") {
+        } else if input_path.extension().is_some_and(|ext| ext == "rs") {
             match analyzer.analyze_file(&input_path) {
                 Ok(Some(result)) => {
                     analyzer.add_result(result);
                 },
                 Ok(None) => {
-                    println!("File {} does not need analysis or has no valid results", input_path.display());
+                    if verbosity >= Verbosity::Normal {
+                        println!("File {} does not need analysis or has no valid results", input_path.display());
+                    }
                 },
                 Err(e) => {
                     eprintln!("Error analyzing file: {}, but will continue execution", e);
+                    incomplete = true;
                 }
             }
         } else {
             eprintln!("Path must be a Rust file (.rs) or a directory containing Rust files: {}", 
                     input_path.display());
-            return Ok(());
+            return Ok(uses_baseline);
         }
         
-        // Write results
-        if let Err(e) = analyzer.write_results_to_file(&output_path) {
-            eprintln!("Error writing results: {}", e);
+        // Write results in every requested format
+        for (output_path, format) in &output_targets {
+            if let Err(e) = analyzer.write_results(output_path, *format) {
+                eprintln!("Error writing {} results: {}", format, e);
+            } else if verbosity >= Verbosity::Normal {
+                println!("Analysis complete! Results saved to: {}", output_path.display());
+            }
         }
         
-        println!("Analysis complete! Results saved to: {}", output_path.display());
+        let failed_files = analyzer.failed_files();
+        if uses_baseline && failed_files > 0 {
+            eprintln!("{} files could not be analyzed, their findings are missing from the baseline comparison", failed_files);
+        }
+        let mut failed = uses_baseline && (incomplete || failed_files > 0);
         
-        Ok(())
+        // 先与旧基线比较，再保存新基线，两者可以是同一个文件
+        if let Some(baseline_path) = &cli.baseline {
+            let diff = match analyzer.compare_with_baseline(baseline_path) {
+                Ok(diff) => diff,
+                Err(e) => {
+                    eprintln!("Error comparing with baseline {}: {}", baseline_path.display(), e);
+                    std::process::exit(1);
+                }
+            };
+            if verbosity >= Verbosity::Normal {
+                println!("Compared with baseline {}: {} added, {} removed, {} changed", 
+                        baseline_path.display(), diff.added.len(), diff.removed.len(), diff.changed.len());
+                diff.write_summary(std::io::stdout().lock())?;
+            }
+            if let Some(diff_path) = &cli.diff_output
+                && let Err(e) = File::create(diff_path).and_then(|file| diff.write_json(BufWriter::new(file))) {
+                eprintln!("Error writing baseline differences: {}", e);
+            }
+            failed |= diff.has_new_findings();
+        }
+        if let Some(baseline_path) = &cli.save_baseline
+            && let Err(e) = analyzer.save_baseline(baseline_path) {
+            eprintln!("Error saving baseline: {}", e);
+            failed = true;
+        }
+        
+        Ok(failed)
     });
 
     // Handle overall panic
     match result {
-        Ok(io_result) => io_result,
+        // 与基线相比出现新的发现或无法完成比较时以非零状态退出，供CI使用
+        Ok(Ok(true)) => std::process::exit(1),
+        Ok(io_result) => io_result.map(|_| ()),
         Err(_) => {
             eprintln!("Program encountered an unrecoverable error, but has attempted to save existing results");
+            if uses_baseline {
+                std::process::exit(1);
+            }
             Ok(())
         }
     }
-}
\ No newline at end of file
+}
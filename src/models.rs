@@ -1,7 +1,11 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize, Serializer};
 
 // Unsafe operation type
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum UnsafeOperationType {
     RawPointerDereference,  // 裸指针解引用
     UnsafeFunctionCall,     // 调用unsafe函数
@@ -9,34 +13,130 @@ pub enum UnsafeOperationType {
     InlineAssembly,         // 内联汇编
     UnionFieldAccess,       // 访问联合体字段
     MutStaticAccess,        // 访问可变静态变量
+    FfiCall { abi: String, link_name: String }, // 调用extern块中声明的函数或访问其中的静态变量
     Other(String),          // 其他类型的unsafe操作
 }
 
-impl UnsafeOperationType {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for UnsafeOperationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            UnsafeOperationType::RawPointerDereference => "裸指针解引用".to_string(),
-            UnsafeOperationType::UnsafeFunctionCall => "调用unsafe函数".to_string(),
-            UnsafeOperationType::UnsafeMethodCall => "调用unsafe方法".to_string(),
-            UnsafeOperationType::InlineAssembly => "内联汇编".to_string(),
-            UnsafeOperationType::UnionFieldAccess => "访问联合体字段".to_string(),
-            UnsafeOperationType::MutStaticAccess => "访问可变静态变量".to_string(),
-            UnsafeOperationType::Other(desc) => format!("其他unsafe操作: {}", desc),
+            UnsafeOperationType::RawPointerDereference => write!(f, "裸指针解引用"),
+            UnsafeOperationType::UnsafeFunctionCall => write!(f, "调用unsafe函数"),
+            UnsafeOperationType::UnsafeMethodCall => write!(f, "调用unsafe方法"),
+            UnsafeOperationType::InlineAssembly => write!(f, "内联汇编"),
+            UnsafeOperationType::UnionFieldAccess => write!(f, "访问联合体字段"),
+            UnsafeOperationType::MutStaticAccess => write!(f, "访问可变静态变量"),
+            UnsafeOperationType::FfiCall { abi, link_name } => write!(f, "FFI调用 extern \"{}\" {}", abi, link_name),
+            UnsafeOperationType::Other(desc) => write!(f, "其他unsafe操作: {}", desc),
         }
     }
 }
 
 // Detailed information about an unsafe operation
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UnsafeOperation {
     pub operation_type: UnsafeOperationType,  // 操作类型
     pub description: String,                  // 描述文本
     pub code_snippet: String,                 // 代码片段
     pub line_number: Option<usize>,           // 行号（可选）
+    pub span: Option<SourceSpan>,             // 完整位置（可选）
+    #[serde(default)]
+    pub confidence: Confidence,               // 判断依据的可靠程度
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<UnsafeApiCategory>,  // 调用标准库unsafe API时的危险类别
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub macro_origin: Option<String>,         // 由本crate的宏展开生成时的宏名，例如 "read_at!"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cfg: Option<String>,                  // 全部配置模式下操作存在的条件，例如 "all(unix, feature = \"simd\")"
+}
+
+/// How an unsafe operation was recognized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    High, // 由声明或语法确定：crate中的unsafe fn、extern块、联合体、static mut、内联汇编、已知的unsafe函数路径
+    #[default]
+    Low,  // 按名称或表达式形式推测，例如名为 read/add 的方法、可能是裸指针的解引用
+}
+
+/// Kind of danger of an unsafe API of `core`, `alloc` or `std`, as listed in `unsafe_apis.toml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnsafeApiCategory {
+    RawPointer,      // 通过裸指针读写内存
+    Ownership,       // 从裸指针恢复所有权、修改引用计数或提前析构
+    Uninitialized,   // 未初始化内存
+    UncheckedBounds, // 省略边界检查
+    UncheckedValue,  // 省略值合法性检查（UTF-8、非空、Pin等）
+    Transmute,       // 按另一种类型重新解释内存
+    Allocation,      // 手动分配和释放内存
+    ForeignData,     // 读取外部代码提供的数据
+    GlobalState,     // 修改进程的全局状态
+    TargetFeature,   // 要求CPU支持特定指令集的平台内建函数
+}
+
+impl fmt::Display for UnsafeApiCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            UnsafeApiCategory::RawPointer => "裸指针读写",
+            UnsafeApiCategory::Ownership => "所有权与释放",
+            UnsafeApiCategory::Uninitialized => "未初始化内存",
+            UnsafeApiCategory::UncheckedBounds => "省略边界检查",
+            UnsafeApiCategory::UncheckedValue => "省略值检查",
+            UnsafeApiCategory::Transmute => "类型重解释",
+            UnsafeApiCategory::Allocation => "手动内存分配",
+            UnsafeApiCategory::ForeignData => "外部数据",
+            UnsafeApiCategory::GlobalState => "全局状态",
+            UnsafeApiCategory::TargetFeature => "目标特性",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+// Source location range; lines and columns are 1-based
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SourceSpan {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl SourceSpan {
+    // Build a span covering the start of `start` to the end of `end`,
+    // None when the tokens carry no location (e.g. not parsed from a file)
+    pub fn from_range(start: proc_macro2::Span, end: proc_macro2::Span) -> Option<Self> {
+        let (start, end) = (start.start(), end.end());
+        if start.line == 0 {
+            return None;
+        }
+        Some(SourceSpan {
+            start_line: start.line,
+            start_column: start.column + 1,
+            end_line: end.line,
+            end_column: end.column + 1,
+        })
+    }
+    
+    pub fn from_span(span: proc_macro2::Span) -> Option<Self> {
+        Self::from_range(span, span)
+    }
+    
+    // Whether `other` lies within this span
+    pub fn contains(&self, other: &SourceSpan) -> bool {
+        (self.start_line, self.start_column) <= (other.start_line, other.start_column)
+            && (other.end_line, other.end_column) <= (self.end_line, self.end_column)
+    }
+}
+
+impl fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}-{}:{}", self.start_line, self.start_column, self.end_line, self.end_column)
+    }
 }
 
 // Type definition
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TypeDefinition {
     pub name: String,           // Type name
     pub module_path: String,    // Module path
@@ -44,6 +144,10 @@ pub struct TypeDefinition {
     pub source_code: String,    // Source code of type definition
     pub file_path: String,      // File path
     pub constructors: Vec<String>, // Constructors and related impl blocks
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub field_types: BTreeMap<String, String>, // 字段名 -> 字段的主要类型（按源码写法），用于推断方法调用的接收者类型
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_union: bool,         // 是否为联合体，读取其字段需要unsafe
 }
 
 impl TypeDefinition {
@@ -69,8 +173,20 @@ impl TypeDefinition {
     }
 }
 
+// Trait definition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraitDefinition {
+    pub name: String,                 // Trait name
+    pub module_path: String,          // Module path
+    pub visibility: VisibilityKind,   // Visibility
+    pub file_path: String,            // File path
+    pub methods: Vec<String>,         // 声明的所有方法
+    pub default_methods: Vec<String>, // 带默认实现的方法
+    pub supertraits: Vec<String>,     // 父trait（按源码写法）
+}
+
 // Function basic information
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FunctionInfo {
     pub name: String,
     pub module_path: String,
@@ -79,15 +195,30 @@ pub struct FunctionInfo {
     pub is_unsafe_fn: bool,
     pub file_path: String,
     pub source_code: String,
+    #[serde(serialize_with = "serialize_sorted_set")]
     pub param_custom_types: HashSet<String>, // Custom types used in function parameters
+    #[serde(serialize_with = "serialize_sorted_set")]
     pub return_custom_types: HashSet<String>, // Custom types used in function return
     pub has_self_param: bool, // 是否包含&self参数
     pub owner_type: Option<String>, // 函数所属的类型
     pub unsafe_operations: Vec<UnsafeOperation>, // Unsafe operations in this function
+    pub span: Option<SourceSpan>, // Location of the function definition
+    pub unsafe_blocks: Vec<SourceSpan>, // Locations of unsafe blocks in this function
+    #[serde(default)]
+    pub return_type: Option<String>, // 返回值的主要类型（按源码写法，Self已替换为impl类型）
+    #[serde(default)]
+    pub trait_name: Option<String>, // trait impl中的方法和trait默认方法所属的trait名称
+    #[serde(default)]
+    pub parent: Option<String>, // 闭包、嵌套函数和函数体内的常量/静态变量所在的外层节点
+    #[serde(default)]
+    pub exported_symbol: Option<String>, // #[no_mangle]/#[export_name] 导出的 extern 函数的符号名，外部代码可以直接调用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cfg: Option<String>, // 全部配置模式下函数存在的条件
 }
 
 // Function visibility
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum VisibilityKind {
     Public,       // pub fn
     Crate,        // pub(crate) fn
@@ -95,17 +226,19 @@ pub enum VisibilityKind {
     Restricted,   // pub(in path) fn or pub(super) fn
 }
 
-impl VisibilityKind {
-    // Convert visibility to string representation
-    pub fn to_string(&self) -> String {
+// Convert visibility to string representation
+impl fmt::Display for VisibilityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            VisibilityKind::Public => "pub ".to_string(),
-            VisibilityKind::Crate => "pub(crate) ".to_string(),
-            VisibilityKind::Module => "".to_string(), // No prefix for private functions
-            VisibilityKind::Restricted => "pub(restricted) ".to_string(),
+            VisibilityKind::Public => write!(f, "pub "),
+            VisibilityKind::Crate => write!(f, "pub(crate) "),
+            VisibilityKind::Module => Ok(()), // No prefix for private functions
+            VisibilityKind::Restricted => write!(f, "pub(restricted) "),
         }
     }
-    
+}
+
+impl VisibilityKind {
     // Check if it's public visibility
     pub fn is_public(&self) -> bool {
         matches!(self, VisibilityKind::Public)
@@ -113,29 +246,283 @@ impl VisibilityKind {
 }
 
 // Function call relationship
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionCall {
     pub caller: String,    // Full path of the caller
-    pub callee: String,    // Full path of the callee
+    pub callee: String,    // Full path of the callee; the method name for method calls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receiver: Option<TypeHint>, // Receiver type of a method call, resolved when the call graph is built
+}
+
+/// What is statically known about the type of an expression, recorded per file and
+/// resolved against crate-wide type definitions and function signatures afterwards
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TypeHint {
+    Type(String),                          // 类型路径，例如 crate::foo::Bar
+    Bounds(Vec<String>),                   // 只知道实现了哪些trait：泛型参数、dyn Trait、impl Trait
+    ReturnOf(String),                      // 函数的返回类型，例如 crate::foo::Bar::new
+    Field(Box<TypeHint>, String),          // 结构体字段的类型
+    MethodReturn(Box<TypeHint>, String),   // 在接收者上调用的方法的返回类型
+    Unknown,                               // 无法推断（例如没有类型标注的闭包参数）
+}
+
+/// An expression that is an unsafe operation only if it reads a union field, accesses a
+/// `static mut`, calls into an `extern` block or calls an `unsafe fn` of the crate, which is
+/// decided once the whole crate is known. Field and path candidates are only recorded in an
+/// unsafe context; calls are recorded everywhere so that resolved calls replace name heuristics
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CandidateOperation {
+    pub function: String,          // 所在节点
+    pub target: CandidateTarget,
+    pub code_snippet: String,
+    pub span: Option<SourceSpan>,
+}
+
+/// What a candidate operation accesses
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateTarget {
+    FieldOf(TypeHint), // 读取该类型线索所指的值的字段，值为联合体时是unsafe操作
+    Path(String),      // 路径表达式（导入已解析），指向 static mut 或外部静态变量时是unsafe操作
+    Call(String),      // 函数调用的路径（导入已解析），指向外部函数或crate中的unsafe fn时是unsafe操作
+    MethodCall(TypeHint, String), // 方法调用的接收者类型线索和方法名，解析到unsafe方法时是unsafe操作
+}
+
+/// A `macro_rules!` macro defined in the crate, kept as text so that files extracted later
+/// (e.g. child modules) can expand it
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MacroDefinition {
+    pub name: String,
+    pub rules: String, // macro_rules! 花括号中的规则
+}
+
+/// An entry of the textual scope of `macro_rules!` macros at some point of a file
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MacroScope {
+    Defined(MacroDefinition), // 之前定义的宏，包括 #[macro_use] 内联模块中的定义
+    Module(PathBuf),          // 之前声明的 #[macro_use] mod 文件，其末尾可见的宏同样可用
+}
+
+/// An invocation of a crate macro that was expanded; the generated code carries its span
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MacroExpansion {
+    pub name: String,
+    pub span: Option<SourceSpan>,
+}
+
+/// Code that only exists when a `#[cfg]` condition holds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CfgRegion {
+    pub span: SourceSpan,
+    pub condition: String, // 按源码写法，例如 "target_os = \"linux\""
+}
+
+/// A function or static declared in an `extern` block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForeignItem {
+    pub name: String,
+    pub module_path: String,
+    pub abi: String,       // 例如 "C"，extern 块没有写ABI时为 "C"
+    pub link_name: String, // 链接的符号名：#[link_name] 或项名
+    pub is_static: bool,
+    pub file_path: String,
+    pub span: Option<SourceSpan>,
 }
 
 // Information for a single function in a path
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PathNodeInfo {
     pub full_path: String,       // Full path of the function
+    pub file_path: String,       // File that defines the function
     pub visibility: VisibilityKind, // Function visibility
     pub source_code: String,     // Function source code
+    #[serde(serialize_with = "serialize_sorted_set")]
     pub param_custom_types: HashSet<String>, // Custom types used in function parameters
+    #[serde(serialize_with = "serialize_sorted_set")]
     pub return_custom_types: HashSet<String>, // Custom types used in function return
     pub has_self_param: bool,    // 是否包含&self参数
     pub owner_type: Option<String>, // 函数所属的类型名称
     pub unsafe_operations: Vec<UnsafeOperation>, // Unsafe operations in this function
+    pub span: Option<SourceSpan>, // Location of the function definition
+    pub unsafe_blocks: Vec<SourceSpan>, // Locations of unsafe blocks in this function
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unresolved_calls: Vec<String>, // 接收者类型无法确定、且crate中存在同名方法的方法调用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dispatched_via: Option<String>, // 上一个节点经由此trait动态分发调用到本节点
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>, // 闭包、嵌套函数等所在的外层节点
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exported_symbol: Option<String>, // 导出给外部代码的符号名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cfg: Option<String>, // 全部配置模式下函数存在的条件
+}
+
+// Functions, calls and types extracted from a single file, merged later into one crate-wide graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileExtraction {
+    pub file_path: String,
+    pub module_path: Vec<String>, // Crate-rooted module path of the file
+    pub functions: HashMap<String, FunctionInfo>,
+    pub calls: Vec<FunctionCall>,
+    pub type_definitions: HashMap<String, TypeDefinition>,
+    #[serde(default)]
+    pub traits: HashMap<String, TraitDefinition>, // Trait definitions by trait path
+    #[serde(default)]
+    pub imports: HashMap<String, ModuleImports>, // `use` declarations by module path
+    #[serde(default)]
+    pub modules: HashMap<String, VisibilityKind>, // Modules declared in this file and their visibility
+    #[serde(default)]
+    pub submodules: Vec<ModuleFile>, // Files of the out-of-line `mod foo;` declarations in this file
+    #[serde(default)]
+    pub missing_modules: Vec<PathBuf>, // Files the `mod foo;` declarations were looked up in but that do not exist
+    #[serde(default)]
+    pub unsafe_declarations: Vec<UnsafeDeclaration>, // `unsafe impl`, `unsafe trait` and `global_asm!` declarations in this file
+    #[serde(default)]
+    pub mutable_statics: BTreeSet<String>, // Keys of the `static mut` items in this file
+    #[serde(default)]
+    pub candidate_operations: Vec<CandidateOperation>, // Union field reads, `static mut` accesses and calls to confirm crate-wide
+    #[serde(default)]
+    pub foreign_items: HashMap<String, ForeignItem>, // Functions and statics declared in `extern` blocks, by item path
+    #[serde(default)]
+    pub macros: Vec<MacroScope>, // `macro_rules!` macros in scope at the end of the file, exported by a `#[macro_use]` declaration of it
+    #[serde(default)]
+    pub macro_expansions: Vec<MacroExpansion>, // Invocations of crate macros expanded in this file
+    #[serde(default)]
+    pub cfg: Vec<String>, // `#[cfg]` conditions of the file's `mod` declarations and its `#![cfg]`, all configurations mode only
+    #[serde(default)]
+    pub cfg_regions: Vec<CfgRegion>, // Code of this file under `#[cfg]` conditions, all configurations mode only
+}
+
+/// Kind of an unsafe declaration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnsafeDeclarationKind {
+    Impl,  // unsafe impl Trait for Type
+    Trait, // unsafe trait Trait
+    GlobalAsm, // global_asm!(...)
+}
+
+impl fmt::Display for UnsafeDeclarationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnsafeDeclarationKind::Impl => write!(f, "unsafe impl"),
+            UnsafeDeclarationKind::Trait => write!(f, "unsafe trait"),
+            UnsafeDeclarationKind::GlobalAsm => write!(f, "global_asm!"),
+        }
+    }
+}
+
+/// An `unsafe impl` or `unsafe trait` declaration, or a module-level `global_asm!`. The compiler
+/// trusts these without any check, so they are reported for review on their own, independent
+/// of call paths.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsafeDeclaration {
+    pub kind: UnsafeDeclarationKind,
+    pub trait_name: String,          // 实现或定义的trait（按源码写法），global_asm! 为宏的路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_type: Option<String>,   // unsafe impl 的类型（按源码写法，含泛型参数）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_type_name: Option<String>, // unsafe impl 的类型名（最后一段，不含泛型参数和引用），用于查找类型定义
+    pub module_path: String,
+    pub file_path: String,
+    pub span: Option<SourceSpan>,
+    pub generic_bounds: Vec<String>, // 泛型参数及其约束（包括where子句），例如 "T: Clone"
+    pub missing_bounds: Vec<String>, // unsafe impl Send/Sync 的泛型参数缺少的约束，例如 "T: Send"
+    pub source_code: String,         // impl块、trait定义或宏调用的源码
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub type_definition: Option<TypeDefinition>, // 实现类型在crate中的定义，构建调用图后填充
+}
+
+// e.g. `unsafe impl Send for Wrapper<T>`, `unsafe trait RawBuffer` or `global_asm!`
+impl fmt::Display for UnsafeDeclaration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.kind, &self.self_type) {
+            (UnsafeDeclarationKind::GlobalAsm, _) => write!(f, "{}", self.kind),
+            (_, Some(self_type)) => write!(f, "{} {} for {}", self.kind, self.trait_name, self_type),
+            (_, None) => write!(f, "{} {}", self.kind, self.trait_name),
+        }
+    }
+}
+
+/// The `use` declarations of a module (or block), with crate-rooted targets
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleImports {
+    pub names: BTreeMap<String, String>, // 导入的名称（或别名） -> 目标路径，外部crate的路径保持原样
+    pub globs: Vec<String>,              // glob导入（use foo::*）的模块路径
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub exported: BTreeSet<String>,      // 通过 pub use 重导出的名称
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exported_globs: Vec<String>,     // 通过 pub use foo::* 重导出的模块路径
+}
+
+/// A source file of a crate and the module it defines
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ModuleFile {
+    pub file_path: PathBuf,
+    pub module_path: Vec<String>, // 以crate为根的模块路径，例如 ["crate", "foo", "bar"]
+    pub owns_directory: bool,     // crate根、mod.rs和#[path]加载的文件的子模块位于同一目录，foo.rs的子模块位于foo/
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cfg: Vec<String>,         // 加载该文件的各级 mod 声明上的 #[cfg] 条件
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub macros: Vec<MacroScope>,  // 声明该文件的文件中 mod 声明之前可见的宏，不含上层文件传入的宏
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub macro_use: bool,          // mod 声明带有 #[macro_use]，文件末尾可见的宏在声明之后仍然可用
 }
 
 // Analysis result for a single file
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileAnalysisResult {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<CrateTarget>, // 所属的Cargo包和目标，非Cargo项目为None
     pub file_path: String,
     pub paths: Vec<Vec<PathNodeInfo>>, // Modified to store detailed function info
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub type_definitions: HashMap<String, TypeDefinition>, // Related custom type definitions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unsafe_declarations: Vec<UnsafeDeclaration>, // `unsafe impl`, `unsafe trait` and `global_asm!` declarations in this file
+}
+
+// Kind of a Cargo target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetKind {
+    Lib,
+    Bin,
+}
+
+impl fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetKind::Lib => write!(f, "lib"),
+            TargetKind::Bin => write!(f, "bin"),
+        }
+    }
+}
+
+/// A library or binary target of a Cargo package; each target is analyzed as its own crate
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CrateTarget {
+    pub package: String,
+    pub name: String,
+    pub kind: TargetKind,
+}
+
+impl fmt::Display for CrateTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({} {})", self.package, self.kind, self.name)
+    }
+}
+
+// Serialize sets in sorted order so that reports do not change between runs
+fn serialize_sorted_set<S: Serializer>(set: &HashSet<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut items: Vec<&String> = set.iter().collect();
+    items.sort();
+    items.serialize(serializer)
+}
+
+// Serialize maps with sorted keys for the same reason
+fn serialize_sorted_map<S: Serializer, V: Serialize>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
 }
\ No newline at end of file
@@ -0,0 +1,284 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::{CrateTarget, FileAnalysisResult, PathNodeInfo, UnsafeOperationType};
+use crate::report::sarif::{PUBLIC_UNSAFE_PATH_RULE, UNSAFE_DECLARATION_RULE};
+
+/// Version of the baseline file format, bumped whenever a fingerprint changes meaning
+pub const BASELINE_SCHEMA_VERSION: u32 = 1;
+
+/// Findings of one run, saved so that later runs only report what changed. Findings are
+/// identified by function paths and operations by their code, never by line numbers, so
+/// that moving code around does not make old findings look new.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub schema_version: u32,
+    pub tool: String,
+    pub tool_version: String,
+    pub generated_at: String,
+    pub findings: Vec<Finding>,
+}
+
+/// A public entry point reaching an unsafe function, or an unsafe declaration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    pub rule: String, // 与SARIF规则相同：public-unsafe-path 或 unsafe-declaration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<CrateTarget>,
+    pub entry_point: String, // 入口函数路径；unsafe声明为 "模块路径: 声明"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unsafe_function: Option<String>, // 路径到达的unsafe函数
+    pub file_path: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<String>, // 每条路径经过的函数，例如 "crate::a -> crate::b -> crate::c"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub operations: Vec<String>, // 路径上unsafe操作的指纹
+}
+
+/// A finding present in both runs whose routes or operations differ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedFinding {
+    pub finding: Finding, // 当前的结果
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_routes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_routes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_operations: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_operations: Vec<String>,
+}
+
+/// Differences between a baseline and the current run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BaselineDiff {
+    pub added: Vec<Finding>,
+    pub removed: Vec<Finding>,
+    pub changed: Vec<ChangedFinding>,
+}
+
+impl Finding {
+    /// Identity of the finding across runs
+    fn key(&self) -> (String, String, String, String) {
+        let target = self.target.as_ref().map(|target| target.to_string()).unwrap_or_default();
+        (self.rule.clone(), target, self.entry_point.clone(), self.unsafe_function.clone().unwrap_or_default())
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.unsafe_function {
+            // 入口函数自身包含unsafe代码时只写一次
+            Some(unsafe_function) if *unsafe_function != self.entry_point => {
+                write!(f, "{} -> {}", self.entry_point, unsafe_function)?
+            },
+            _ => write!(f, "{}", self.entry_point)?,
+        }
+        if let Some(target) = &self.target {
+            write!(f, " [{}]", target)?;
+        }
+        write!(f, " ({})", self.file_path)
+    }
+}
+
+impl Baseline {
+    /// Collect the findings of a run: one per entry point and unsafe function it reaches,
+    /// merging the routes between them, and one per unsafe declaration
+    pub fn new(results: &[FileAnalysisResult]) -> Self {
+        let mut findings: BTreeMap<_, Finding> = BTreeMap::new();
+        
+        for result in results {
+            for path in &result.paths {
+                let (Some(entry), Some(unsafe_fn)) = (path.first(), path.last()) else {
+                    continue;
+                };
+                let finding = Finding {
+                    rule: PUBLIC_UNSAFE_PATH_RULE.to_string(),
+                    target: result.target.clone(),
+                    entry_point: entry.full_path.clone(),
+                    unsafe_function: Some(unsafe_fn.full_path.clone()),
+                    file_path: result.file_path.clone(),
+                    routes: Vec::new(),
+                    operations: Vec::new(),
+                };
+                let finding = findings.entry(finding.key()).or_insert(finding);
+                
+                let route: Vec<&str> = path.iter().map(|node| node.full_path.as_str()).collect();
+                finding.routes.push(route.join(" -> "));
+                finding.operations.extend(path.iter().flat_map(operation_fingerprints));
+            }
+            
+            for declaration in &result.unsafe_declarations {
+                let finding = Finding {
+                    rule: UNSAFE_DECLARATION_RULE.to_string(),
+                    target: result.target.clone(),
+                    entry_point: format!("{}: {}", declaration.module_path, declaration),
+                    unsafe_function: None,
+                    file_path: declaration.file_path.clone(),
+                    routes: Vec::new(),
+                    operations: Vec::new(),
+                };
+                findings.insert(finding.key(), finding);
+            }
+        }
+        
+        let findings = findings.into_values()
+            .map(|mut finding| {
+                finding.routes.sort();
+                finding.routes.dedup();
+                finding.operations.sort();
+                finding.operations.dedup();
+                finding
+            })
+            .collect();
+        
+        Baseline {
+            schema_version: BASELINE_SCHEMA_VERSION,
+            tool: env!("CARGO_PKG_NAME").to_string(),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: chrono::Local::now().to_rfc3339(),
+            findings,
+        }
+    }
+    
+    /// Load a saved baseline
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let baseline: Baseline = serde_json::from_str(&content).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Invalid baseline {}: {}", path.display(), e))
+        })?;
+        if baseline.schema_version != BASELINE_SCHEMA_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!(
+                "Baseline {} has schema version {}, expected {}; save it again",
+                path.display(), baseline.schema_version, BASELINE_SCHEMA_VERSION)));
+        }
+        Ok(baseline)
+    }
+    
+    /// Write the baseline as a pretty-printed JSON document
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+    
+    /// Compare the findings of a later run with this baseline
+    pub fn compare(&self, current: &Baseline) -> BaselineDiff {
+        let mut previous: HashMap<_, &Finding> = self.findings.iter()
+            .map(|finding| (finding.key(), finding))
+            .collect();
+        let mut diff = BaselineDiff::default();
+        
+        for finding in &current.findings {
+            let Some(old) = previous.remove(&finding.key()) else {
+                diff.added.push(finding.clone());
+                continue;
+            };
+            let change = ChangedFinding {
+                finding: finding.clone(),
+                added_routes: difference(&finding.routes, &old.routes),
+                removed_routes: difference(&old.routes, &finding.routes),
+                added_operations: difference(&finding.operations, &old.operations),
+                removed_operations: difference(&old.operations, &finding.operations),
+            };
+            if !(change.added_routes.is_empty() && change.removed_routes.is_empty()
+                && change.added_operations.is_empty() && change.removed_operations.is_empty()) {
+                diff.changed.push(change);
+            }
+        }
+        
+        // 保持基线中的顺序
+        diff.removed = self.findings.iter()
+            .filter(|finding| previous.contains_key(&finding.key()))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+impl BaselineDiff {
+    /// Whether the current run has exposure the baseline did not: a new entry point reaching
+    /// unsafe code, a new unsafe declaration, or a new unsafe operation on a known path.
+    /// Routes that only pass through different functions do not count.
+    pub fn has_new_findings(&self) -> bool {
+        !self.added.is_empty() || self.changed.iter().any(|change| !change.added_operations.is_empty())
+    }
+    
+    /// Write the differences as a pretty-printed JSON document
+    pub fn write_json<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+    
+    /// Write a human-readable list of the differences
+    pub fn write_summary<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for finding in &self.added {
+            writeln!(writer, "+ {}", finding)?;
+            for operation in &finding.operations {
+                writeln!(writer, "    + {}", operation)?;
+            }
+        }
+        for finding in &self.removed {
+            writeln!(writer, "- {}", finding)?;
+        }
+        for change in &self.changed {
+            writeln!(writer, "~ {}", change.finding)?;
+            for route in &change.added_routes {
+                writeln!(writer, "    + route: {}", route)?;
+            }
+            for route in &change.removed_routes {
+                writeln!(writer, "    - route: {}", route)?;
+            }
+            for operation in &change.added_operations {
+                writeln!(writer, "    + {}", operation)?;
+            }
+            for operation in &change.removed_operations {
+                writeln!(writer, "    - {}", operation)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fingerprints of the unsafe operations in a function: the function path, the kind of the
+/// operation and its code with whitespace collapsed, numbered when the same code occurs
+/// more than once. Line numbers and report text are left out so that unrelated edits and
+/// rewording the reports do not change them.
+fn operation_fingerprints(node: &PathNodeInfo) -> Vec<String> {
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    node.unsafe_operations.iter()
+        .map(|op| {
+            let code = op.code_snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+            let fingerprint = format!("{}: {}: {}", node.full_path, operation_kind(&op.operation_type), code);
+            let count = occurrences.entry(fingerprint.clone()).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                fingerprint
+            } else {
+                format!("{} #{}", fingerprint, count)
+            }
+        })
+        .collect()
+}
+
+/// Serialized name of the kind of an operation, e.g. `raw_pointer_dereference` or `ffi_call`
+fn operation_kind(operation_type: &UnsafeOperationType) -> String {
+    match serde_json::to_value(operation_type) {
+        Ok(Value::String(name)) => name,
+        // 带数据的变体序列化为 {"ffi_call": {...}}
+        Ok(Value::Object(variant)) => variant.keys().next().cloned().unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Entries of `a` that are not in `b`, in the order of `a`
+fn difference(a: &[String], b: &[String]) -> Vec<String> {
+    let b: BTreeSet<&String> = b.iter().collect();
+    a.iter().filter(|item| !b.contains(item)).cloned().collect()
+}
@@ -0,0 +1,58 @@
+use std::io::{self, Write};
+use serde::{Deserialize, Serialize};
+
+use crate::models::FileAnalysisResult;
+
+/// Version of the JSON report schema, bumped whenever a field is renamed, removed or changes meaning
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Top-level document of the JSON report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonReport {
+    pub schema_version: u32,
+    pub tool: String,
+    pub tool_version: String,
+    pub generated_at: String,
+    pub results: Vec<FileAnalysisResult>,
+}
+
+impl JsonReport {
+    pub fn new(results: Vec<FileAnalysisResult>) -> Self {
+        JsonReport {
+            schema_version: JSON_SCHEMA_VERSION,
+            tool: env!("CARGO_PKG_NAME").to_string(),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: chrono::Local::now().to_rfc3339(),
+            results,
+        }
+    }
+}
+
+/// One line of the JSON Lines report: a single file result tagged with the schema version
+#[derive(Debug, Serialize)]
+struct JsonLinesRecord<'a> {
+    schema_version: u32,
+    #[serde(flatten)]
+    result: &'a FileAnalysisResult,
+}
+
+/// Write all results as one pretty-printed JSON document
+pub fn write_json<W: Write>(mut writer: W, results: &[FileAnalysisResult]) -> io::Result<()> {
+    let report = JsonReport::new(results.to_vec());
+    serde_json::to_writer_pretty(&mut writer, &report)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Write one compact JSON object per analyzed file
+pub fn write_json_lines<W: Write>(mut writer: W, results: &[FileAnalysisResult]) -> io::Result<()> {
+    for result in results {
+        let record = JsonLinesRecord {
+            schema_version: JSON_SCHEMA_VERSION,
+            result,
+        };
+        serde_json::to_writer(&mut writer, &record)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+pub mod json;
+pub mod sarif;
+pub mod baseline;
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Output format of the analysis report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Rust,      // 带注释的伪Rust源码（默认）
+    Json,      // 单个JSON文档
+    JsonLines, // 每行一个JSON记录
+    Sarif,     // SARIF 2.1.0，用于代码扫描平台
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 4] = [OutputFormat::Rust, OutputFormat::Json, OutputFormat::JsonLines, OutputFormat::Sarif];
+    
+    /// Name used on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Rust => "rust",
+            OutputFormat::Json => "json",
+            OutputFormat::JsonLines => "jsonl",
+            OutputFormat::Sarif => "sarif",
+        }
+    }
+    
+    /// File extension of reports in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Rust => "rs",
+            OutputFormat::Json => "json",
+            OutputFormat::JsonLines => "jsonl",
+            OutputFormat::Sarif => "sarif",
+        }
+    }
+    
+    /// Pick the format from the output file extension, falling back to the Rust report
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => OutputFormat::Json,
+            Some("jsonl") | Some("ndjson") => OutputFormat::JsonLines,
+            Some("sarif") => OutputFormat::Sarif,
+            _ => OutputFormat::Rust,
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OutputFormat::ALL.iter()
+            .find(|format| format.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = OutputFormat::ALL.iter().map(|format| format.name()).collect();
+                format!("unknown output format '{}', expected one of: {}", s, names.join(", "))
+            })
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Write};
+use serde_json::{json, Value};
+
+use crate::models::{Confidence, CrateTarget, FileAnalysisResult, PathNodeInfo, SourceSpan, UnsafeDeclaration};
+
+/// SARIF version written by this module
+pub const SARIF_VERSION: &str = "2.1.0";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Rule reported for every public entry point that reaches unsafe code
+pub const PUBLIC_UNSAFE_PATH_RULE: &str = "public-unsafe-path";
+
+/// Rule reported for every `unsafe impl` and `unsafe trait` declaration and `global_asm!` invocation
+pub const UNSAFE_DECLARATION_RULE: &str = "unsafe-declaration";
+
+/// Write all results as a single SARIF 2.1.0 log
+pub fn write_sarif<W: Write>(mut writer: W, results: &[FileAnalysisResult]) -> io::Result<()> {
+    let log = build_sarif_log(results);
+    serde_json::to_writer_pretty(&mut writer, &log)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Build the SARIF log, one `result` per public entry point and per unsafe declaration
+pub fn build_sarif_log(results: &[FileAnalysisResult]) -> Value {
+    let mut sarif_results = Vec::new();
+    
+    for result in results {
+        // 同一个入口点的所有路径合并为一个result，每条路径对应一个codeFlow
+        let mut paths_by_entry: BTreeMap<&str, Vec<&Vec<PathNodeInfo>>> = BTreeMap::new();
+        for path in &result.paths {
+            if let Some(entry) = path.first() {
+                paths_by_entry.entry(entry.full_path.as_str()).or_default().push(path);
+            }
+        }
+        
+        for (entry_path, paths) in paths_by_entry {
+            sarif_results.push(build_result(entry_path, &paths, result.target.as_ref()));
+        }
+        
+        for declaration in &result.unsafe_declarations {
+            sarif_results.push(build_declaration_result(declaration, result.target.as_ref()));
+        }
+    }
+    
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": [{
+                        "id": PUBLIC_UNSAFE_PATH_RULE,
+                        "name": "PublicUnsafePath",
+                        "shortDescription": { "text": "公共函数可以到达unsafe代码" },
+                        "fullDescription": {
+                            "text": "一个非unsafe声明的公共函数可以直接或经由私有函数到达包含unsafe代码的函数，调用方无需unsafe块即可触发这些unsafe操作。"
+                        },
+                        "defaultConfiguration": { "level": "warning" }
+                    }, {
+                        "id": UNSAFE_DECLARATION_RULE,
+                        "name": "UnsafeDeclaration",
+                        "shortDescription": { "text": "unsafe impl、unsafe trait 声明或 global_asm!" },
+                        "fullDescription": {
+                            "text": "unsafe impl 由实现者保证trait要求的不变量（例如手动实现的Send/Sync），unsafe trait 要求所有实现者保证其不变量，编译器不做检查。global_asm! 中的汇编代码直接链接进crate。手动实现Send/Sync而泛型参数缺少相同约束时报告为警告。"
+                        },
+                        "defaultConfiguration": { "level": "note" }
+                    }]
+                }
+            },
+            // SourceSpan 的列按字符计数，SARIF 默认按UTF-16代码单元计数
+            "columnKind": "unicodeCodePoints",
+            "results": sarif_results
+        }]
+    })
+}
+
+fn build_result(entry_path: &str, paths: &[&Vec<PathNodeInfo>], target: Option<&CrateTarget>) -> Value {
+    let entry = &paths[0][0];
+    
+    let mut targets: Vec<&str> = paths.iter()
+        .filter_map(|path| path.last())
+        .map(|node| node.full_path.as_str())
+        .collect();
+    targets.sort();
+    targets.dedup();
+    
+    let code_flows: Vec<Value> = paths.iter()
+        .map(|path| {
+            let locations: Vec<Value> = path.iter()
+                .enumerate()
+                .map(|(i, node)| {
+                    let mut text = if i == 0 {
+                        format!("公共入口点: {}", node.full_path)
+                    } else if i + 1 == path.len() {
+                        format!("不安全实现: {}", node.full_path)
+                    } else {
+                        format!("中间函数: {}", node.full_path)
+                    };
+                    // 闭包、嵌套函数等需要指明所在的外层节点
+                    if let Some(parent) = &node.parent {
+                        text.push_str(&format!(" (位于 {} 中)", parent));
+                    }
+                    if let Some(symbol) = &node.exported_symbol {
+                        text.push_str(&format!(" (导出符号 {})", symbol));
+                    }
+                    if let Some(condition) = &node.cfg {
+                        text.push_str(&format!(" (仅在 cfg({}) 时存在)", condition));
+                    }
+                    if let Some(trait_name) = &node.dispatched_via {
+                        text.push_str(&format!(" (经由trait {} 动态分发)", trait_name));
+                    }
+                    // 接收者类型未知的方法调用可能遗漏了路径
+                    if !node.unresolved_calls.is_empty() {
+                        text.push_str(&format!(" (未解析的方法调用: {})", node.unresolved_calls.join(", ")));
+                    }
+                    json!({ "location": location(&node.file_path, node.span, Some(&text)) })
+                })
+                .collect();
+            json!({ "threadFlows": [{ "locations": locations }] })
+        })
+        .collect();
+    
+    // 路径上所有函数中的unsafe块和unsafe操作作为相关位置
+    let mut related_locations = Vec::new();
+    let mut seen_nodes = HashSet::new();
+    for path in paths {
+        for node in path.iter() {
+            if !seen_nodes.insert(node.full_path.as_str()) {
+                continue;
+            }
+            for block in &node.unsafe_blocks {
+                let text = format!("unsafe块 ({})", node.full_path);
+                related_locations.push(location(&node.file_path, Some(*block), Some(&text)));
+            }
+            for op in &node.unsafe_operations {
+                let mut text = format!("{} ({}): {}", op.operation_type, node.full_path, op.code_snippet);
+                if let Some(category) = op.category {
+                    text.push_str(&format!(" [{}]", category));
+                }
+                if let Some(macro_name) = &op.macro_origin {
+                    text.push_str(&format!(" (展开自宏 {})", macro_name));
+                }
+                if let Some(condition) = &op.cfg {
+                    text.push_str(&format!(" (仅在 cfg({}) 时存在)", condition));
+                }
+                // 按名称推测的操作可能是误报
+                if op.confidence == Confidence::Low {
+                    text.push_str(" (启发式)");
+                }
+                related_locations.push(location(&node.file_path, op.span, Some(&text)));
+            }
+        }
+    }
+    for (id, related) in related_locations.iter_mut().enumerate() {
+        related["id"] = json!(id);
+    }
+    
+    let mut result = json!({
+        "ruleId": PUBLIC_UNSAFE_PATH_RULE,
+        "level": "warning",
+        "message": {
+            "text": format!("公共函数 {} 可以到达unsafe代码: {}", entry_path, targets.join(", "))
+        },
+        "locations": [location(&entry.file_path, entry.span, None)],
+        "codeFlows": code_flows,
+        "relatedLocations": related_locations,
+        // 指纹只依赖函数路径，代码行移动后仍能与之前的结果对应
+        "partialFingerprints": {
+            "entryPoint/v1": entry_path,
+            "unsafeTargets/v1": targets.join(",")
+        }
+    });
+    
+    // 工作区中不同目标的函数路径都以crate::开头，用包和目标区分
+    if let Some(target) = target {
+        result["properties"] = json!({
+            "package": target.package,
+            "target": target.name,
+            "targetKind": target.kind
+        });
+        result["partialFingerprints"]["crateTarget/v1"] = json!(format!("{}/{}/{}", target.package, target.kind, target.name));
+    }
+    
+    result
+}
+
+fn build_declaration_result(declaration: &UnsafeDeclaration, target: Option<&CrateTarget>) -> Value {
+    let mut text = declaration.to_string();
+    if !declaration.missing_bounds.is_empty() {
+        text.push_str(&format!("，泛型参数缺少约束: {}", declaration.missing_bounds.join(", ")));
+    }
+    
+    // 缺少Send/Sync约束的实现可能导致数据竞争
+    let level = if declaration.missing_bounds.is_empty() { "note" } else { "warning" };
+    
+    let mut related_locations = Vec::new();
+    if let Some(type_def) = &declaration.type_definition {
+        let text = format!("类型定义: {}", type_def.name);
+        let mut related = location(&type_def.file_path, None, Some(&text));
+        related["id"] = json!(0);
+        related_locations.push(related);
+    }
+    
+    let mut result = json!({
+        "ruleId": UNSAFE_DECLARATION_RULE,
+        "level": level,
+        "message": { "text": text },
+        "locations": [location(&declaration.file_path, declaration.span, None)],
+        "relatedLocations": related_locations,
+        "properties": {
+            "kind": declaration.kind,
+            "trait": declaration.trait_name,
+            "selfType": declaration.self_type,
+            "genericBounds": declaration.generic_bounds,
+            "missingBounds": declaration.missing_bounds
+        },
+        // 指纹只依赖模块、trait和类型，代码行移动后仍能与之前的结果对应
+        "partialFingerprints": {
+            "unsafeDeclaration/v1": format!("{}: {}", declaration.module_path, declaration)
+        }
+    });
+    
+    if let Some(target) = target {
+        result["properties"]["package"] = json!(target.package);
+        result["properties"]["target"] = json!(target.name);
+        result["properties"]["targetKind"] = json!(target.kind);
+        result["partialFingerprints"]["crateTarget/v1"] = json!(format!("{}/{}/{}", target.package, target.kind, target.name));
+    }
+    
+    result
+}
+
+/// Build a SARIF physical location, with a region only when the span is known
+fn location(file_path: &str, span: Option<SourceSpan>, message: Option<&str>) -> Value {
+    let mut physical = json!({
+        "artifactLocation": { "uri": path_to_uri(file_path) }
+    });
+    if let Some(span) = span {
+        physical["region"] = json!({
+            "startLine": span.start_line,
+            "startColumn": span.start_column,
+            "endLine": span.end_line,
+            "endColumn": span.end_column
+        });
+    }
+    
+    let mut location = json!({ "physicalLocation": physical });
+    if let Some(text) = message {
+        location["message"] = json!({ "text": text });
+    }
+    location
+}
+
+/// Convert a file path to a SARIF artifact URI
+fn path_to_uri(file_path: &str) -> String {
+    let normalized = file_path.replace('\\', "/");
+    let normalized = normalized.strip_prefix("./").unwrap_or(&normalized);
+    if normalized.starts_with('/') {
+        format!("file://{}", normalized)
+    } else {
+        normalized.to_string()
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+use serde::Deserialize;
+
+use crate::models::UnsafeApiCategory;
+
+/// The database of unsafe `core`/`alloc`/`std` APIs, embedded at compile time
+const DATABASE_SOURCE: &str = include_str!("unsafe_apis.toml");
+
+static DATABASE: LazyLock<UnsafeApiDatabase> = LazyLock::new(|| {
+    UnsafeApiDatabase::parse(DATABASE_SOURCE).expect("embedded unsafe_apis.toml is invalid")
+});
+
+/// The embedded database of unsafe standard library APIs
+pub fn database() -> &'static UnsafeApiDatabase {
+    &DATABASE
+}
+
+// 类别名是 UnsafeApiCategory 的键，拼错的类别名会导致解析失败
+#[derive(Deserialize)]
+struct RawDatabase {
+    #[serde(default)]
+    derefs: HashMap<String, String>,
+    #[serde(default)]
+    receiver_required: HashSet<String>,
+    #[serde(flatten)]
+    categories: HashMap<UnsafeApiCategory, RawCategory>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCategory {
+    #[serde(default)]
+    functions: Vec<String>,
+    #[serde(default)]
+    modules: Vec<String>,
+    #[serde(default)]
+    methods: HashMap<String, Vec<String>>,
+}
+
+/// Unsafe functions and methods of `core`, `alloc` and `std` with their category of danger
+pub struct UnsafeApiDatabase {
+    functions: HashMap<String, Vec<(Vec<String>, UnsafeApiCategory)>>, // 按函数名索引，路径已规范为 core::
+    modules: Vec<(Vec<String>, UnsafeApiCategory)>,                    // 其中的函数都是unsafe的模块
+    methods: HashMap<String, Vec<(String, UnsafeApiCategory)>>,        // 按方法名索引的接收者类型
+    derefs: HashMap<String, String>,
+    receiver_required: HashSet<String>,
+}
+
+impl UnsafeApiDatabase {
+    /// Parse a database in the format of `unsafe_apis.toml`
+    pub fn parse(source: &str) -> Result<Self, toml::de::Error> {
+        let raw: RawDatabase = toml::from_str(source)?;
+        let mut functions: HashMap<String, Vec<_>> = HashMap::new();
+        let mut methods: HashMap<String, Vec<_>> = HashMap::new();
+        let mut modules = Vec::new();
+        
+        for (category, raw_category) in raw.categories {
+            for path in raw_category.functions {
+                let segments = split_path(&path);
+                let name = segments.last().cloned().unwrap_or_default();
+                functions.entry(name).or_default().push((segments, category));
+            }
+            modules.extend(raw_category.modules.iter().map(|path| (split_path(path), category)));
+            for (receiver, names) in raw_category.methods {
+                for name in names {
+                    methods.entry(name).or_default().push((receiver.clone(), category));
+                }
+            }
+        }
+        
+        // 同名的项按类别排序，查找结果不依赖哈希表的顺序
+        for entries in functions.values_mut() {
+            entries.sort_by_key(|(_, category)| *category);
+        }
+        for entries in methods.values_mut() {
+            entries.sort_by_key(|(_, category)| *category);
+        }
+        modules.sort();
+        
+        Ok(UnsafeApiDatabase { functions, modules, methods, derefs: raw.derefs, receiver_required: raw.receiver_required })
+    }
+    
+    /// Category of the function a written path calls: the full path (`std::ptr::read`) or a
+    /// suffix of at least two segments (`ptr::read`, `Box::from_raw`). `Type::method` also
+    /// matches unsafe methods called with path syntax, e.g. `MaybeUninit::assume_init(x)`, and
+    /// any function in a listed module matches, e.g. `std::arch::x86_64::_mm_pause()`.
+    pub fn function(&self, segments: &[String]) -> Option<UnsafeApiCategory> {
+        let segments = normalize(segments);
+        let name = segments.last()?;
+        let matched = self.functions.get(name).and_then(|entries| {
+            entries.iter().find_map(|(path, category)| {
+                let is_match = path == &segments || (segments.len() >= 2 && path.ends_with(&segments));
+                is_match.then_some(*category)
+            })
+        });
+        matched
+            .or_else(|| self.module_function(&segments))
+            .or_else(|| match segments.as_slice() {
+                [.., receiver, method] => self.method(receiver, method),
+                _ => None,
+            })
+    }
+    
+    /// Category of a function in one of the listed modules, called by its full path
+    fn module_function(&self, segments: &[String]) -> Option<UnsafeApiCategory> {
+        self.modules.iter()
+            .find(|(module, _)| segments.len() > module.len() && segments.starts_with(module))
+            .map(|(_, category)| *category)
+    }
+    
+    /// Category of a free function called by its name alone, e.g. after `use std::mem::transmute`;
+    /// associated functions cannot be called this way
+    pub fn function_named(&self, name: &str) -> Option<UnsafeApiCategory> {
+        self.functions.get(name)?.iter()
+            .find(|(path, _)| path.len() < 2 || !path[path.len() - 2].starts_with(char::is_uppercase))
+            .map(|(_, category)| *category)
+    }
+    
+    /// Category of a method called on a receiver of the given type, following derefs such as
+    /// `Vec` to slices; generic arguments and module paths of the type are ignored
+    pub fn method(&self, receiver_type: &str, name: &str) -> Option<UnsafeApiCategory> {
+        let entries = self.methods.get(name)?;
+        let mut receiver = Some(receiver_key(receiver_type));
+        while let Some(current) = receiver {
+            if let Some((_, category)) = entries.iter().find(|(entry, _)| *entry == current) {
+                return Some(*category);
+            }
+            receiver = self.derefs.get(&current).cloned();
+        }
+        None
+    }
+    
+    /// Receiver types that have an unsafe method with this name
+    pub fn method_receivers(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.methods.get(name).into_iter().flatten().map(|(receiver, _)| receiver.as_str())
+    }
+    
+    /// Category of any unsafe method with this name, for receivers of unknown type; None for
+    /// names that common safe methods share, such as `add` or `read`
+    pub fn method_named(&self, name: &str) -> Option<UnsafeApiCategory> {
+        if self.receiver_required.contains(name) {
+            return None;
+        }
+        self.methods.get(name)?.first().map(|(_, category)| *category)
+    }
+}
+
+/// Segments of a path written in the database, normalized
+fn split_path(path: &str) -> Vec<String> {
+    normalize(&path.split("::").map(String::from).collect::<Vec<_>>())
+}
+
+/// `std::` and `alloc::` re-export the items of `core::`, compare paths under a single root
+fn normalize(segments: &[String]) -> Vec<String> {
+    let mut normalized = segments.to_vec();
+    if let Some(first) = normalized.first_mut()
+        && (first == "std" || first == "alloc") {
+        *first = "core".to_string();
+    }
+    normalized
+}
+
+/// Key of a receiver type in the database: the last path segment without generic arguments,
+/// with integer and float types collapsed to `{integer}` and `{float}`
+fn receiver_key(receiver_type: &str) -> String {
+    let base = receiver_type.split('<').next().unwrap_or(receiver_type).trim();
+    let name = base.rsplit("::").next().unwrap_or(base).trim();
+    match name {
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => "{integer}".to_string(),
+        "f32" | "f64" => "{float}".to_string(),
+        _ => name.to_string(),
+    }
+}
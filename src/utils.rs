@@ -6,7 +6,7 @@ pub fn beautify_source_code(source_code: &str) -> String {
     // 尝试使用prettyplease进行格式化
     let result = catch_unwind(|| {
         // 直接格式化方法
-        if let Ok(parsed) = syn::parse_str::<syn::File>(&format!("{}", source_code)) {
+        if let Ok(parsed) = syn::parse_str::<syn::File>(source_code) {
             return prettyplease::unparse(&parsed);
         }
         
@@ -156,10 +156,10 @@ pub fn enhanced_format_source_code(source_code: &str) -> String {
         
         // Adjust indent level for the next line based on braces in this line
         let open_count = trimmed.matches('{').count() + trimmed.matches('[').count() + 
-                        (trimmed.matches('(').count() - trimmed.matches(')').count()).max(0);
+                        trimmed.matches('(').count().saturating_sub(trimmed.matches(')').count());
         
         let close_count = trimmed.matches('}').count() + trimmed.matches(']').count() + 
-                         (trimmed.matches(')').count() - trimmed.matches('(').count()).max(0);
+                         trimmed.matches(')').count().saturating_sub(trimmed.matches('(').count());
         
         // Adjust for opening braces
         indent_level += open_count;
@@ -1,19 +1,46 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use quote::ToTokens;
 use syn::{
+    spanned::Spanned,
     visit::{self, Visit},
     UseTree,
 };
-use quote::ToTokens;
 
-use crate::models::FunctionCall;
+use crate::models::{CandidateOperation, CandidateTarget, FunctionCall, ModuleImports, SourceSpan, TypeHint};
+use crate::visitors::scope::{self, ImplScope};
+
+/// Imports and item names visible in a module or block
+#[derive(Debug, Default)]
+struct ImportScope {
+    imports: ModuleImports,
+    items: HashSet<String>, // 在该模块或块中定义的项（模块、函数、类型等）
+    is_module: bool,        // 模块不继承外层模块的导入，块会继承
+    function: Option<String>, // 块所在的节点，块中定义的函数、常量和静态变量以它为前缀
+    nested_items: HashSet<String>, // 块中定义的函数、常量、静态变量和extern块中声明的项
+}
 
 /// Visitor for collecting function call relationships
 pub struct CallVisitor {
     pub current_module_path: Vec<String>,
     pub current_function: Option<String>,
+    pub current_impl: Option<ImplScope>, // Current impl block, used for keys and Self/self calls
     pub calls: Vec<FunctionCall>,
-    // Mapping of imported modules and aliases
-    pub imports: HashMap<String, String>,
+    // Imports of each module, by module path
+    pub imports: HashMap<String, ModuleImports>,
+    // 当前可见的导入，每个模块和块一层
+    import_scopes: Vec<ImportScope>,
+    // 局部变量的类型线索，每个块一层
+    pub locals: Vec<HashMap<String, TypeHint>>,
+    // 当前trait定义的路径，用于默认方法中的 self 和 Self
+    pub current_trait: Option<String>,
+    // 泛型参数 -> trait约束，每个泛型作用域（impl、trait、函数）一层
+    pub generic_bounds: Vec<HashMap<String, Vec<String>>>,
+    // 每个节点中已访问的闭包数量，与FunctionVisitor按相同顺序编号
+    closure_counts: HashMap<String, usize>,
+    // unsafe上下文中可能读取联合体字段、访问 static mut 或调用外部函数的表达式
+    pub candidate_operations: Vec<CandidateOperation>,
+    // 是否在unsafe块或unsafe fn的函数体内
+    in_unsafe_context: bool,
 }
 
 impl CallVisitor {
@@ -21,11 +48,25 @@ impl CallVisitor {
         CallVisitor {
             current_module_path: Vec::new(),
             current_function: None,
+            current_impl: None,
             calls: Vec::new(),
             imports: HashMap::new(),
+            import_scopes: Vec::new(),
+            locals: Vec::new(),
+            current_trait: None,
+            generic_bounds: Vec::new(),
+            closure_counts: HashMap::new(),
+            candidate_operations: Vec::new(),
+            in_unsafe_context: false,
         }
     }
     
+    /// Start visiting from the given module path, e.g. `crate::foo` for `src/foo.rs`
+    pub fn with_module_path(mut self, module_path: Vec<String>) -> Self {
+        self.current_module_path = module_path;
+        self
+    }
+    
     /// Get current module path
     pub fn get_current_module_path(&self) -> String {
         self.current_module_path.join("::")
@@ -38,85 +79,500 @@ impl CallVisitor {
             self.calls.push(FunctionCall {
                 caller: caller.clone(),
                 callee,
+                receiver: None,
             });
         }
     }
     
     /// Resolve path, handling imports and aliases
     pub fn resolve_path(&self, path: &syn::Path) -> String {
-        let path_str = path.to_token_stream().to_string().replace(' ', "");
-        
-        // Check if it's an imported module or alias
-        if path.segments.len() > 0 {
-            let first_segment = &path.segments[0].ident.to_string();
-            if let Some(import) = self.imports.get(first_segment) {
-                // Replace first part of path with full imported path
-                return path_str.replacen(first_segment, import, 1);
+        // Self::method 指向当前impl块的类型
+        if path.segments.len() == 2
+            && path.segments[0].ident == "Self"
+            && let Some(impl_scope) = &self.current_impl {
+            let method = path.segments[1].ident.to_string();
+            return impl_scope.self_method_key(&self.get_current_module_path(), &method);
+        }
+        
+        let segments: Vec<String> = path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+        if path.leading_colon.is_some() {
+            return format!("::{}", segments.join("::"));
+        }
+        self.resolve_segments(&segments, false)
+    }
+    
+    /// Resolve a written type path like a function path; `Self` is the current impl type
+    pub fn resolve_type_path(&self, written: &str) -> String {
+        if written == "Self"
+            && let Some(impl_scope) = &self.current_impl {
+            return scope::qualify(&self.get_current_module_path(), &impl_scope.self_type);
+        }
+        
+        let segments: Vec<String> = written.split("::").map(String::from).collect();
+        self.resolve_segments(&segments, false)
+    }
+    
+    /// Turn a path written in the current scope into a crate-rooted path: `crate::`, `self::`
+    /// and `super::` prefixes and imported names are resolved. Other paths in expressions are
+    /// qualified with the current module; glob imports and re-exports are followed when the
+    /// call graph is built. In `use` declarations, paths that start with neither a local item
+    /// nor an import name belong to another crate and stay as they are.
+    fn resolve_segments(&self, segments: &[String], in_use: bool) -> String {
+        let mut module_path = self.current_module_path.clone();
+        let mut rest = segments;
+        
+        match rest.first().map(String::as_str) {
+            Some("crate") => return segments.join("::"),
+            Some("self") => rest = &rest[1..],
+            Some("super") => {
+                while rest.first().map(String::as_str) == Some("super") {
+                    module_path.pop();
+                    rest = &rest[1..];
+                }
+            },
+            Some(first) => {
+                if rest.len() == 1 && let Some(item) = self.lookup_nested_item(first) {
+                    return item;
+                }
+                if let Some(target) = self.lookup_import(first) {
+                    return std::iter::once(target).chain(rest[1..].iter().cloned()).collect::<Vec<_>>().join("::");
+                }
+                if in_use && !self.is_local_item(first) {
+                    return segments.join("::");
+                }
+            },
+            None => {},
+        }
+        
+        scope::qualify(&module_path.join("::"), &rest.join("::"))
+    }
+    
+    /// Target of an imported name, looking through enclosing blocks up to the module
+    fn lookup_import(&self, name: &str) -> Option<String> {
+        for import_scope in self.import_scopes.iter().rev() {
+            if let Some(target) = import_scope.imports.names.get(name) {
+                return Some(target.clone());
+            }
+            if import_scope.is_module {
+                break;
+            }
+        }
+        None
+    }
+    
+    /// Key of a function, const or static defined in an enclosing block of the current body,
+    /// e.g. `crate::foo::outer::inner`
+    fn lookup_nested_item(&self, name: &str) -> Option<String> {
+        for import_scope in self.import_scopes.iter().rev() {
+            if import_scope.is_module {
+                break;
+            }
+            if import_scope.items.contains(name) {
+                return match &import_scope.function {
+                    Some(function) if import_scope.nested_items.contains(name) => Some(scope::item_key(Some(function), "", name)),
+                    _ => None,
+                };
+            }
+        }
+        None
+    }
+    
+    /// Whether an item of this name is defined in the current module or an enclosing block
+    fn is_local_item(&self, name: &str) -> bool {
+        for import_scope in self.import_scopes.iter().rev() {
+            if import_scope.items.contains(name) {
+                return true;
+            }
+            if import_scope.is_module {
+                break;
             }
         }
+        false
+    }
+    
+    /// Start an import scope for the items of a module or block. `use` declarations apply to
+    /// the whole scope, so they are all processed before any item is visited.
+    fn enter_import_scope<'a>(&mut self, items: impl Iterator<Item = &'a syn::Item> + Clone, is_module: bool) {
+        let nested_items = items.clone()
+            .filter_map(|item| match item {
+                syn::Item::Fn(item) if !is_module => Some(item.sig.ident.to_string()),
+                syn::Item::Const(item) if !is_module => Some(item.ident.to_string()),
+                syn::Item::Static(item) if !is_module => Some(item.ident.to_string()),
+                _ => None,
+            })
+            .chain(items.clone().filter(|_| !is_module).flat_map(foreign_item_names))
+            .collect();
+        let item_names = items.clone()
+            .filter_map(|item| match item {
+                syn::Item::Mod(item) => Some(item.ident.to_string()),
+                syn::Item::Fn(item) => Some(item.sig.ident.to_string()),
+                syn::Item::Struct(item) => Some(item.ident.to_string()),
+                syn::Item::Enum(item) => Some(item.ident.to_string()),
+                syn::Item::Union(item) => Some(item.ident.to_string()),
+                syn::Item::Trait(item) => Some(item.ident.to_string()),
+                syn::Item::Type(item) => Some(item.ident.to_string()),
+                syn::Item::Const(item) => Some(item.ident.to_string()),
+                syn::Item::Static(item) => Some(item.ident.to_string()),
+                _ => None,
+            })
+            .chain(items.clone().flat_map(foreign_item_names))
+            .collect();
+        self.import_scopes.push(ImportScope {
+            imports: ModuleImports::default(),
+            items: item_names,
+            is_module,
+            function: if is_module { None } else { self.current_function.clone() },
+            nested_items,
+        });
         
-        // If it's a relative path (not starting with crate:: or ::), add current module path
-        if !path_str.starts_with("crate::") && !path_str.starts_with("::") {
-            let module_path = self.get_current_module_path();
-            if !module_path.is_empty() {
-                return format!("{}::{}", module_path, path_str);
+        for item in items {
+            if let syn::Item::Use(item_use) = item {
+                let prefix = if item_use.leading_colon.is_some() { vec![String::new()] } else { Vec::new() };
+                let is_public = matches!(item_use.vis, syn::Visibility::Public(_));
+                self.process_use(&item_use.tree, &prefix, is_public);
+            }
+        }
+        
+        // 模块级导入输出给调用图，用于展开glob导入和重导出
+        if is_module && let Some(import_scope) = self.import_scopes.last() {
+            let imports = self.imports.entry(self.get_current_module_path()).or_default();
+            imports.names.extend(import_scope.imports.names.clone());
+            imports.globs.extend(import_scope.imports.globs.iter().cloned());
+            imports.exported.extend(import_scope.imports.exported.iter().cloned());
+            imports.exported_globs.extend(import_scope.imports.exported_globs.iter().cloned());
+        }
+    }
+    
+    /// Type hint for a written type, e.g. a `let` annotation or a parameter type
+    pub fn type_hint_of_type(&self, ty: &syn::Type) -> TypeHint {
+        if let Some(traits) = self.trait_bounds_of_type(ty) {
+            return TypeHint::Bounds(traits);
+        }
+        let self_type = self.current_impl.as_ref().map(|impl_scope| impl_scope.self_type.as_str());
+        match scope::principal_type(ty, self_type) {
+            Some(written) => TypeHint::Type(self.resolve_type_path(&written)),
+            None => TypeHint::Unknown,
+        }
+    }
+    
+    /// Traits a type is only known to implement: `dyn Trait`, `impl Trait`, bounded generic
+    /// parameters and `Self` in a trait, also behind references and smart pointers
+    pub fn trait_bounds_of_type(&self, ty: &syn::Type) -> Option<Vec<String>> {
+        let traits = match ty {
+            syn::Type::TraitObject(object) => self.trait_paths(&object.bounds),
+            syn::Type::ImplTrait(impl_trait) => self.trait_paths(&impl_trait.bounds),
+            syn::Type::Reference(type_ref) => return self.trait_bounds_of_type(&type_ref.elem),
+            syn::Type::Paren(paren) => return self.trait_bounds_of_type(&paren.elem),
+            syn::Type::Group(group) => return self.trait_bounds_of_type(&group.elem),
+            syn::Type::Path(type_path) if type_path.qself.is_none() => {
+                if let Some(ident) = type_path.path.get_ident() {
+                    return self.generic_param_bounds(&ident.to_string());
+                }
+                let last = type_path.path.segments.last()?;
+                if !scope::is_transparent_wrapper(&last.ident.to_string()) {
+                    return None;
+                }
+                let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+                    return None;
+                };
+                return args.args.iter().find_map(|arg| match arg {
+                    syn::GenericArgument::Type(inner) => self.trait_bounds_of_type(inner),
+                    _ => None,
+                });
+            },
+            _ => Vec::new(),
+        };
+        
+        if traits.is_empty() { None } else { Some(traits) }
+    }
+    
+    /// Trait bounds of a generic parameter in scope, `Self` inside a trait definition
+    pub fn generic_param_bounds(&self, name: &str) -> Option<Vec<String>> {
+        if name == "Self" && self.current_impl.is_none() {
+            return self.current_trait.clone().map(|trait_path| vec![trait_path]);
+        }
+        self.generic_bounds.iter()
+            .rev()
+            .find_map(|scope| scope.get(name).cloned())
+            .filter(|traits| !traits.is_empty())
+    }
+    
+    /// Resolved paths of the traits in a bound list, lifetimes skipped
+    pub fn trait_paths(&self, bounds: &syn::punctuated::Punctuated<syn::TypeParamBound, syn::Token![+]>) -> Vec<String> {
+        bounds.iter()
+            .filter_map(|bound| match bound {
+                syn::TypeParamBound::Trait(trait_bound) => {
+                    let written = trait_bound.path.segments.iter()
+                        .map(|segment| segment.ident.to_string())
+                        .collect::<Vec<_>>()
+                        .join("::");
+                    Some(self.resolve_type_path(&written))
+                },
+                _ => None,
+            })
+            .collect()
+    }
+    
+    /// Start a generic scope with the bounds declared in generics and the where clause
+    fn enter_generics(&mut self, generics: &syn::Generics) {
+        let mut bounds: HashMap<String, Vec<String>> = HashMap::new();
+        for param in generics.type_params() {
+            bounds.entry(param.ident.to_string()).or_default().extend(self.trait_paths(&param.bounds));
+        }
+        if let Some(where_clause) = &generics.where_clause {
+            for predicate in &where_clause.predicates {
+                if let syn::WherePredicate::Type(predicate) = predicate
+                    && let syn::Type::Path(bounded) = &predicate.bounded_ty
+                    && let Some(ident) = bounded.path.get_ident()
+                    && bounds.contains_key(&ident.to_string()) {
+                    let traits = self.trait_paths(&predicate.bounds);
+                    bounds.entry(ident.to_string()).or_default().extend(traits);
+                }
             }
         }
+        self.generic_bounds.push(bounds);
+    }
+    
+    /// Receiver of a path call dispatched through a trait: `T::method(..)` on a generic
+    /// parameter, `Self::method(..)` in a trait and `<X as Trait>::method(..)`
+    pub fn path_call_receiver(&self, expr_path: &syn::ExprPath) -> Option<TypeHint> {
+        if let Some(qself) = &expr_path.qself {
+            return match self.type_hint_of_type(&qself.ty) {
+                TypeHint::Unknown => None,
+                hint => Some(hint),
+            };
+        }
+        
+        let segments = &expr_path.path.segments;
+        if segments.len() != 2 {
+            return None;
+        }
+        self.generic_param_bounds(&segments[0].ident.to_string()).map(TypeHint::Bounds)
+    }
+    
+    /// What can be told about the type of an expression without type checking
+    pub fn type_hint_of_expr(&self, expr: &syn::Expr) -> TypeHint {
+        let hint = match expr {
+            syn::Expr::Path(expr_path) => match expr_path.path.get_ident() {
+                Some(ident) => self.lookup_local(&ident.to_string()),
+                None => TypeHint::Unknown,
+            },
+            // 构造函数和普通函数调用：使用被调用函数的返回类型
+            syn::Expr::Call(call) => match &*call.func {
+                syn::Expr::Path(func) => TypeHint::ReturnOf(self.resolve_path(&func.path)),
+                _ => TypeHint::Unknown,
+            },
+            syn::Expr::Struct(expr_struct) => {
+                let written = expr_struct.path.segments.iter()
+                    .map(|segment| segment.ident.to_string())
+                    .collect::<Vec<_>>()
+                    .join("::");
+                TypeHint::Type(self.resolve_type_path(&written))
+            },
+            syn::Expr::MethodCall(call) => {
+                TypeHint::MethodReturn(Box::new(self.type_hint_of_expr(&call.receiver)), call.method.to_string())
+            },
+            syn::Expr::Field(field) => {
+                let member = match &field.member {
+                    syn::Member::Named(ident) => ident.to_string(),
+                    syn::Member::Unnamed(index) => index.index.to_string(),
+                };
+                TypeHint::Field(Box::new(self.type_hint_of_expr(&field.base)), member)
+            },
+            syn::Expr::Cast(cast) => self.type_hint_of_type(&cast.ty),
+            syn::Expr::Reference(reference) => self.type_hint_of_expr(&reference.expr),
+            syn::Expr::Paren(paren) => self.type_hint_of_expr(&paren.expr),
+            syn::Expr::Group(group) => self.type_hint_of_expr(&group.expr),
+            syn::Expr::Try(expr_try) => self.type_hint_of_expr(&expr_try.expr),
+            syn::Expr::Unary(unary) if matches!(unary.op, syn::UnOp::Deref(_)) => self.type_hint_of_expr(&unary.expr),
+            _ => TypeHint::Unknown,
+        };
         
-        path_str
+        // 基础未知时整个表达式也未知
+        match &hint {
+            TypeHint::Field(base, _) | TypeHint::MethodReturn(base, _) if **base == TypeHint::Unknown => TypeHint::Unknown,
+            _ => hint,
+        }
+    }
+    
+    /// Look up a local variable from the innermost block outwards
+    pub fn lookup_local(&self, name: &str) -> TypeHint {
+        self.locals.iter()
+            .rev()
+            .find_map(|scope| scope.get(name).cloned())
+            .unwrap_or(TypeHint::Unknown)
     }
     
-    /// Process import statement
-    pub fn process_use(&mut self, use_tree: &UseTree, prefix: &str) {
+    /// Whether a local variable (or parameter) of this name is in scope
+    pub fn is_local(&self, name: &str) -> bool {
+        self.locals.iter().any(|scope| scope.contains_key(name))
+    }
+    
+    /// Record an expression of the current node whose target decides whether it is an unsafe operation
+    fn add_candidate<T: ToTokens + Spanned>(&mut self, target: CandidateTarget, expr: &T) {
+        if let Some(function) = &self.current_function {
+            self.candidate_operations.push(CandidateOperation {
+                function: function.clone(),
+                target,
+                code_snippet: expr.to_token_stream().to_string(),
+                span: SourceSpan::from_span(expr.span()),
+            });
+        }
+    }
+    
+    /// Bind the variables of a pattern in the innermost block
+    pub fn bind_pattern(&mut self, pat: &syn::Pat, hint: TypeHint) {
+        match pat {
+            syn::Pat::Ident(pat_ident) => {
+                if let Some(scope) = self.locals.last_mut() {
+                    scope.insert(pat_ident.ident.to_string(), hint);
+                }
+            },
+            syn::Pat::Type(pat_type) => {
+                let hint = self.type_hint_of_type(&pat_type.ty);
+                self.bind_pattern(&pat_type.pat, hint);
+            },
+            syn::Pat::Reference(pat_ref) => self.bind_pattern(&pat_ref.pat, hint),
+            // 其他模式（元组、结构体解构等）中的变量类型未知，但仍需遮蔽外层同名变量
+            _ => {
+                let mut names = Vec::new();
+                collect_pattern_names(pat, &mut names);
+                for name in names {
+                    if let Some(scope) = self.locals.last_mut() {
+                        scope.insert(name, TypeHint::Unknown);
+                    }
+                }
+            },
+        }
+    }
+    
+    /// Start a function body scope holding its parameters
+    fn enter_function(&mut self, sig: &syn::Signature) {
+        self.locals.push(HashMap::new());
+        for input in &sig.inputs {
+            match input {
+                syn::FnArg::Receiver(_) => {
+                    // trait默认方法中的self只知道实现了该trait
+                    let hint = match (&self.current_impl, &self.current_trait) {
+                        (Some(impl_scope), _) => TypeHint::Type(scope::qualify(&self.get_current_module_path(), &impl_scope.self_type)),
+                        (None, Some(trait_path)) => TypeHint::Bounds(vec![trait_path.clone()]),
+                        (None, None) => TypeHint::Unknown,
+                    };
+                    if let Some(scope) = self.locals.last_mut() {
+                        scope.insert("self".to_string(), hint);
+                    }
+                },
+                syn::FnArg::Typed(pat_type) => {
+                    let hint = self.type_hint_of_type(&pat_type.ty);
+                    self.bind_pattern(&pat_type.pat, hint);
+                },
+            }
+        }
+    }
+    
+    /// Visit the initializer of a const or static as a node of its own
+    fn visit_initializer(&mut self, full_path: String, expr: &syn::Expr) {
+        let prev_function = self.current_function.replace(full_path);
+        let prev_locals = std::mem::take(&mut self.locals);
+        let prev_in_unsafe = std::mem::take(&mut self.in_unsafe_context);
+        self.visit_expr(expr);
+        self.in_unsafe_context = prev_in_unsafe;
+        self.locals = prev_locals;
+        self.current_function = prev_function;
+    }
+    
+    /// Process import statement, `prefix` holds the segments of the enclosing use path;
+    /// names of a `pub use` are also recorded as re-exports
+    pub fn process_use(&mut self, use_tree: &UseTree, prefix: &[String], is_public: bool) {
         match use_tree {
             UseTree::Path(use_path) => {
-                let next_prefix = if prefix.is_empty() {
-                    use_path.ident.to_string()
-                } else {
-                    format!("{}::{}", prefix, use_path.ident)
-                };
-                self.process_use(&*use_path.tree, &next_prefix);
+                let mut next_prefix = prefix.to_vec();
+                next_prefix.push(use_path.ident.to_string());
+                self.process_use(&use_path.tree, &next_prefix, is_public);
             },
             UseTree::Name(use_name) => {
-                let full_path = if prefix.is_empty() {
-                    use_name.ident.to_string()
+                // use foo::{self} 导入模块 foo 本身
+                let (name, segments) = if use_name.ident == "self" {
+                    (prefix.last().cloned().unwrap_or_default(), prefix.to_vec())
                 } else {
-                    format!("{}::{}", prefix, use_name.ident)
+                    let mut segments = prefix.to_vec();
+                    segments.push(use_name.ident.to_string());
+                    (use_name.ident.to_string(), segments)
                 };
-                // Add to import mapping
-                self.imports.insert(use_name.ident.to_string(), full_path);
+                let target = self.resolve_use_path(&segments);
+                self.add_import(name, target, is_public);
             },
             UseTree::Rename(use_rename) => {
-                let full_path = if prefix.is_empty() {
-                    use_rename.ident.to_string()
-                } else {
-                    format!("{}::{}", prefix, use_rename.ident)
-                };
-                // Add alias to import mapping
-                self.imports.insert(use_rename.rename.to_string(), full_path);
+                let mut segments = prefix.to_vec();
+                if use_rename.ident != "self" {
+                    segments.push(use_rename.ident.to_string());
+                }
+                let target = self.resolve_use_path(&segments);
+                // Add alias to import mapping; `as _` only brings trait methods into scope
+                if use_rename.rename != "_" {
+                    self.add_import(use_rename.rename.to_string(), target, is_public);
+                }
             },
             UseTree::Glob(_) => {
-                // Handling wildcard imports is complex, simplified here
+                let target = self.resolve_use_path(prefix);
+                if let Some(import_scope) = self.import_scopes.last_mut() {
+                    if is_public {
+                        import_scope.imports.exported_globs.push(target.clone());
+                    }
+                    import_scope.imports.globs.push(target);
+                }
             },
             UseTree::Group(use_group) => {
                 for tree in &use_group.items {
-                    self.process_use(tree, prefix);
+                    self.process_use(tree, prefix, is_public);
                 }
             },
         }
     }
+    
+    /// Crate-rooted target of a use path; a leading empty segment stands for `::`
+    fn resolve_use_path(&self, segments: &[String]) -> String {
+        match segments.first() {
+            Some(first) if first.is_empty() => segments[1..].join("::"),
+            _ => self.resolve_segments(segments, true),
+        }
+    }
+    
+    fn add_import(&mut self, name: String, target: String, is_public: bool) {
+        if let Some(import_scope) = self.import_scopes.last_mut() {
+            if is_public {
+                import_scope.imports.exported.insert(name.clone());
+            }
+            import_scope.imports.names.insert(name, target);
+        }
+    }
+}
+
+impl Default for CallVisitor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'ast> Visit<'ast> for CallVisitor {
+    /// Visit file, the root of its module
+    fn visit_file(&mut self, i: &'ast syn::File) {
+        self.enter_import_scope(i.items.iter(), true);
+        visit::visit_file(self, i);
+        self.import_scopes.pop();
+    }
+    
     /// Visit module
     fn visit_item_mod(&mut self, i: &'ast syn::ItemMod) {
         self.current_module_path.push(i.ident.to_string());
         
         // Visit module contents
         if let Some((_, items)) = &i.content {
+            self.enter_import_scope(items.iter(), true);
             for item in items {
                 visit::visit_item(self, item);
             }
+            self.import_scopes.pop();
         }
         
         self.current_module_path.pop();
@@ -125,44 +581,148 @@ impl<'ast> Visit<'ast> for CallVisitor {
     /// Visit function definition
     fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
         let name = i.sig.ident.to_string();
-        let module_path = self.get_current_module_path();
-        let full_path = if module_path.is_empty() {
-            name
-        } else {
-            format!("{}::{}", module_path, name)
+        // 与FunctionVisitor使用相同的键，函数体内定义的函数以外层节点为前缀
+        let full_path = scope::item_key(self.current_function.as_deref(), &self.get_current_module_path(), &name);
+        
+        // 嵌套函数看不到外层函数的局部变量，结束后恢复外层函数
+        let prev_function = self.current_function.replace(full_path);
+        let prev_locals = std::mem::take(&mut self.locals);
+        let prev_in_unsafe = std::mem::replace(&mut self.in_unsafe_context, i.sig.unsafety.is_some());
+        self.enter_generics(&i.sig.generics);
+        self.enter_function(&i.sig);
+        
+        // Visit function body
+        self.visit_block(&i.block);
+        
+        self.generic_bounds.pop();
+        self.in_unsafe_context = prev_in_unsafe;
+        self.locals = prev_locals;
+        self.current_function = prev_function;
+    }
+    
+    /// Visit impl block
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        let Some(impl_scope) = ImplScope::from_item_impl(i) else {
+            return;
+        };
+        
+        let prev_impl = self.current_impl.replace(impl_scope);
+        self.enter_generics(&i.generics);
+        visit::visit_item_impl(self, i);
+        self.generic_bounds.pop();
+        self.current_impl = prev_impl;
+    }
+    
+    /// Visit trait definition
+    fn visit_item_trait(&mut self, i: &'ast syn::ItemTrait) {
+        let trait_path = scope::qualify(&self.get_current_module_path(), &i.ident.to_string());
+        
+        // trait可以定义在impl块或函数体内，保存外层状态
+        let prev_trait = self.current_trait.replace(trait_path);
+        let prev_impl = self.current_impl.take();
+        self.enter_generics(&i.generics);
+        visit::visit_item_trait(self, i);
+        self.generic_bounds.pop();
+        self.current_impl = prev_impl;
+        self.current_trait = prev_trait;
+    }
+    
+    /// Visit trait method with a default implementation, keyed as `module::Trait::method`
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        let (Some(trait_path), Some(block)) = (&self.current_trait, &i.default) else {
+            return;
         };
+        let full_path = format!("{}::{}", trait_path, i.sig.ident);
         
-        self.current_function = Some(full_path);
+        let prev_function = self.current_function.replace(full_path);
+        let prev_locals = std::mem::take(&mut self.locals);
+        let prev_in_unsafe = std::mem::replace(&mut self.in_unsafe_context, i.sig.unsafety.is_some());
+        self.enter_generics(&i.sig.generics);
+        self.enter_function(&i.sig);
         
         // Visit function body
-        visit::visit_block(self, &i.block);
+        self.visit_block(block);
         
-        self.current_function = None;
+        self.generic_bounds.pop();
+        self.in_unsafe_context = prev_in_unsafe;
+        self.locals = prev_locals;
+        self.current_function = prev_function;
     }
     
     /// Visit function in impl block
     fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
         let name = i.sig.ident.to_string();
         let module_path = self.get_current_module_path();
-        let full_path = if module_path.is_empty() {
-            name
-        } else {
-            format!("{}::{}", module_path, name)
+        // 与FunctionVisitor使用相同的键：module::Type::method 或 module::<Type as Trait>::method
+        let full_path = match &self.current_impl {
+            Some(impl_scope) => impl_scope.method_key(&module_path, &name),
+            None => scope::qualify(&module_path, &name),
         };
         
-        self.current_function = Some(full_path);
+        let prev_function = self.current_function.replace(full_path);
+        let prev_locals = std::mem::take(&mut self.locals);
+        let prev_in_unsafe = std::mem::replace(&mut self.in_unsafe_context, i.sig.unsafety.is_some());
+        self.enter_generics(&i.sig.generics);
+        self.enter_function(&i.sig);
         
         // Visit function body
-        visit::visit_block(self, &i.block);
+        self.visit_block(&i.block);
         
-        self.current_function = None;
+        self.generic_bounds.pop();
+        self.in_unsafe_context = prev_in_unsafe;
+        self.locals = prev_locals;
+        self.current_function = prev_function;
+    }
+    
+    /// Visit const item, keyed like a function of the same name
+    fn visit_item_const(&mut self, i: &'ast syn::ItemConst) {
+        let full_path = scope::item_key(self.current_function.as_deref(), &self.get_current_module_path(), &i.ident.to_string());
+        self.visit_initializer(full_path, &i.expr);
+    }
+    
+    /// Visit static item, keyed like a function of the same name
+    fn visit_item_static(&mut self, i: &'ast syn::ItemStatic) {
+        let full_path = scope::item_key(self.current_function.as_deref(), &self.get_current_module_path(), &i.ident.to_string());
+        self.visit_initializer(full_path, &i.expr);
+    }
+    
+    /// Visit associated const in impl block, keyed like a method
+    fn visit_impl_item_const(&mut self, i: &'ast syn::ImplItemConst) {
+        let module_path = self.get_current_module_path();
+        let name = i.ident.to_string();
+        let full_path = match &self.current_impl {
+            Some(impl_scope) => impl_scope.method_key(&module_path, &name),
+            None => scope::qualify(&module_path, &name),
+        };
+        self.visit_initializer(full_path, &i.expr);
     }
     
     /// Visit function call expression
     fn visit_expr_call(&mut self, i: &'ast syn::ExprCall) {
         // Check if called expression is a path (function name)
+        // 调用在任何上下文中都记录为候选：解析到本crate的函数时按声明判断，取代按名称的推测
         if let syn::Expr::Path(expr_path) = &*i.func {
-            self.handle_call(&expr_path.path);
+            match (self.path_call_receiver(expr_path), &self.current_function) {
+                (Some(receiver), Some(caller)) => {
+                    let method = expr_path.path.segments.last().map(|segment| segment.ident.to_string()).unwrap_or_default();
+                    self.calls.push(FunctionCall {
+                        caller: caller.clone(),
+                        callee: method.clone(),
+                        receiver: Some(receiver.clone()),
+                    });
+                    self.add_candidate(CandidateTarget::MethodCall(receiver, method), i);
+                },
+                _ => {
+                    self.handle_call(&expr_path.path);
+                    if expr_path.qself.is_none() {
+                        let callee = self.resolve_path(&expr_path.path);
+                        self.add_candidate(CandidateTarget::Call(callee), i);
+                    }
+                },
+            }
+        } else {
+            // 例如直接调用的闭包 (|| ..)()
+            self.visit_expr(&i.func);
         }
         
         // Continue visiting arguments
@@ -173,22 +733,34 @@ impl<'ast> Visit<'ast> for CallVisitor {
     
     /// Visit method call expression
     fn visit_expr_method_call(&mut self, i: &'ast syn::ExprMethodCall) {
-        // Method calls are more complex, need type information for accurate resolution
-        // Simplified handling, just record method name
         if let Some(ref caller) = self.current_function {
             let method_name = i.method.to_string();
-            // Simplification: assume method is defined in current module
             let module_path = self.get_current_module_path();
-            let callee = if module_path.is_empty() {
-                method_name
-            } else {
-                format!("{}::{}", module_path, method_name)
-            };
+            let is_self_receiver = matches!(&*i.receiver, syn::Expr::Path(p) if p.path.is_ident("self"));
             
-            self.calls.push(FunctionCall {
-                caller: caller.clone(),
-                callee,
-            });
+            let call = match &self.current_impl {
+                // self.method() 调用当前impl类型的方法
+                Some(impl_scope) if is_self_receiver => FunctionCall {
+                    caller: caller.clone(),
+                    callee: impl_scope.self_method_key(&module_path, &method_name),
+                    receiver: None,
+                },
+                // 其他接收者记录类型线索，构建调用图时再结合整个crate的类型信息解析
+                _ => FunctionCall {
+                    caller: caller.clone(),
+                    callee: method_name,
+                    receiver: Some(self.type_hint_of_expr(&i.receiver)),
+                },
+            };
+            let target = match &call.receiver {
+                None => Some(CandidateTarget::Call(call.callee.clone())),
+                Some(TypeHint::Unknown) => None,
+                Some(receiver) => Some(CandidateTarget::MethodCall(receiver.clone(), call.callee.clone())),
+            };
+            if let Some(target) = target {
+                self.add_candidate(target, i);
+            }
+            self.calls.push(call);
         }
         
         // Continue visiting receiver and arguments
@@ -198,9 +770,136 @@ impl<'ast> Visit<'ast> for CallVisitor {
         }
     }
     
-    /// Visit import statement
-    fn visit_item_use(&mut self, i: &'ast syn::ItemUse) {
-        self.process_use(&i.tree, "");
-        visit::visit_item_use(self, i);
+    /// Visit block, variables bound inside go out of scope at its end
+    fn visit_block(&mut self, i: &'ast syn::Block) {
+        let items = i.stmts.iter().filter_map(|stmt| match stmt {
+            syn::Stmt::Item(item) => Some(item),
+            _ => None,
+        });
+        self.enter_import_scope(items, false);
+        self.locals.push(HashMap::new());
+        visit::visit_block(self, i);
+        self.locals.pop();
+        self.import_scopes.pop();
+    }
+    
+    /// Visit let statement: visit the initializer first, then bind the pattern
+    fn visit_local(&mut self, i: &'ast syn::Local) {
+        let hint = match &i.init {
+            Some(init) => {
+                self.visit_expr(&init.expr);
+                if let Some((_, diverge)) = &init.diverge {
+                    self.visit_expr(diverge);
+                }
+                self.type_hint_of_expr(&init.expr)
+            },
+            None => TypeHint::Unknown,
+        };
+        self.bind_pattern(&i.pat, hint);
     }
-}
\ No newline at end of file
+    
+    /// Visit closure, a node of its own called by the enclosing node; its parameters shadow outer variables
+    fn visit_expr_closure(&mut self, i: &'ast syn::ExprClosure) {
+        let prev_function = self.current_function.clone();
+        if let Some(parent) = prev_function.clone() {
+            let counter = self.closure_counts.entry(parent.clone()).or_default();
+            let closure = scope::closure_key(&parent, *counter);
+            *counter += 1;
+            self.calls.push(FunctionCall {
+                caller: parent,
+                callee: closure.clone(),
+                receiver: None,
+            });
+            self.current_function = Some(closure);
+        }
+        
+        self.locals.push(HashMap::new());
+        for input in &i.inputs {
+            self.bind_pattern(input, TypeHint::Unknown);
+        }
+        self.visit_expr(&i.body);
+        self.locals.pop();
+        self.current_function = prev_function;
+    }
+    
+    /// Visit unsafe block
+    fn visit_expr_unsafe(&mut self, i: &'ast syn::ExprUnsafe) {
+        let prev_in_unsafe = std::mem::replace(&mut self.in_unsafe_context, true);
+        visit::visit_expr_unsafe(self, i);
+        self.in_unsafe_context = prev_in_unsafe;
+    }
+    
+    /// Visit field access: in an unsafe context, a read of a union field if the base is a union
+    fn visit_expr_field(&mut self, i: &'ast syn::ExprField) {
+        if self.in_unsafe_context {
+            match self.type_hint_of_expr(&i.base) {
+                TypeHint::Unknown | TypeHint::Bounds(_) => {},
+                hint => self.add_candidate(CandidateTarget::FieldOf(hint), i),
+            }
+        }
+        visit::visit_expr_field(self, i);
+    }
+    
+    /// Visit assignment; writing a union field is safe, so only the base of the assigned field is visited
+    fn visit_expr_assign(&mut self, i: &'ast syn::ExprAssign) {
+        match &*i.left {
+            syn::Expr::Field(field) => self.visit_expr(&field.base),
+            left => self.visit_expr(left),
+        }
+        self.visit_expr(&i.right);
+    }
+    
+    /// Visit path expression: in an unsafe context, an access of a `static mut` if the path leads to one
+    fn visit_expr_path(&mut self, i: &'ast syn::ExprPath) {
+        let is_local = i.path.get_ident().is_some_and(|ident| self.is_local(&ident.to_string()));
+        if self.in_unsafe_context && i.qself.is_none() && !is_local {
+            let path = self.resolve_path(&i.path);
+            self.add_candidate(CandidateTarget::Path(path), i);
+        }
+        visit::visit_expr_path(self, i);
+    }
+    
+    /// Visit macro invocation; the argument of `addr_of!` and `addr_of_mut!` is a place
+    /// expression that may name a `static mut`, other macro arguments stay unparsed
+    fn visit_macro(&mut self, i: &'ast syn::Macro) {
+        let name = i.path.segments.last().map(|segment| segment.ident.to_string());
+        if self.in_unsafe_context
+            && matches!(name.as_deref(), Some("addr_of" | "addr_of_mut"))
+            && let Ok(place) = i.parse_body::<syn::Expr>() {
+            self.visit_expr(&place);
+        }
+        visit::visit_macro(self, i);
+    }
+    
+    /// Visit import statement, already recorded when its module or block was entered
+    fn visit_item_use(&mut self, _i: &'ast syn::ItemUse) {}
+}
+
+// Names of the functions and statics declared by an `extern` block item
+fn foreign_item_names(item: &syn::Item) -> Vec<String> {
+    let syn::Item::ForeignMod(foreign_mod) = item else {
+        return Vec::new();
+    };
+    foreign_mod.items.iter()
+        .filter_map(|item| match item {
+            syn::ForeignItem::Fn(item) => Some(item.sig.ident.to_string()),
+            syn::ForeignItem::Static(item) => Some(item.ident.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+// Names bound by a pattern
+fn collect_pattern_names(pat: &syn::Pat, names: &mut Vec<String>) {
+    match pat {
+        syn::Pat::Ident(pat_ident) => names.push(pat_ident.ident.to_string()),
+        syn::Pat::Tuple(tuple) => tuple.elems.iter().for_each(|p| collect_pattern_names(p, names)),
+        syn::Pat::TupleStruct(tuple) => tuple.elems.iter().for_each(|p| collect_pattern_names(p, names)),
+        syn::Pat::Struct(pat_struct) => pat_struct.fields.iter().for_each(|field| collect_pattern_names(&field.pat, names)),
+        syn::Pat::Slice(slice) => slice.elems.iter().for_each(|p| collect_pattern_names(p, names)),
+        syn::Pat::Reference(pat_ref) => collect_pattern_names(&pat_ref.pat, names),
+        syn::Pat::Type(pat_type) => collect_pattern_names(&pat_type.pat, names),
+        syn::Pat::Or(pat_or) => pat_or.cases.iter().for_each(|p| collect_pattern_names(p, names)),
+        _ => {}
+    }
+}
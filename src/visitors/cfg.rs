@@ -0,0 +1,551 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::visit_mut::{self, VisitMut};
+use syn::{Attribute, Expr, Item, Lit, Meta, Stmt, Token};
+
+use crate::models::{CfgRegion, SourceSpan};
+
+/// How `#[cfg]` attributes are treated when a crate is extracted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfgMode {
+    /// Drop the code that is not compiled for this configuration
+    Active(CfgSet),
+    /// Keep the code of every configuration and record the conditions it exists under
+    All,
+}
+
+/// A `#[cfg(...)]` predicate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfgPredicate {
+    Option(String, Option<String>), // unix 或 target_os = "linux"
+    All(Vec<CfgPredicate>),
+    Any(Vec<CfgPredicate>),
+    Not(Box<CfgPredicate>),
+}
+
+impl CfgPredicate {
+    /// Parse the predicate inside `cfg(...)`
+    pub fn from_meta(meta: &Meta) -> Option<Self> {
+        let name = meta.path().get_ident()?.to_string();
+        match meta {
+            Meta::Path(_) => Some(CfgPredicate::Option(name, None)),
+            Meta::NameValue(name_value) => match &name_value.value {
+                Expr::Lit(expr_lit) => match &expr_lit.lit {
+                    Lit::Str(value) => Some(CfgPredicate::Option(name, Some(value.value()))),
+                    _ => None,
+                },
+                _ => None,
+            },
+            Meta::List(list) => {
+                let nested = list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated).ok()?;
+                let mut predicates = nested.iter().map(Self::from_meta).collect::<Option<Vec<_>>>()?;
+                match name.as_str() {
+                    "all" => Some(CfgPredicate::All(predicates)),
+                    "any" => Some(CfgPredicate::Any(predicates)),
+                    "not" if predicates.len() == 1 => Some(CfgPredicate::Not(Box::new(predicates.remove(0)))),
+                    _ => None,
+                }
+            },
+        }
+    }
+    
+    /// The predicate of a `#[cfg(...)]` attribute, None for other attributes and predicates
+    /// that cannot be parsed
+    pub fn from_attribute(attr: &Attribute) -> Option<Self> {
+        if !attr.path().is_ident("cfg") {
+            return None;
+        }
+        Self::from_meta(&attr.parse_args::<Meta>().ok()?)
+    }
+}
+
+// 按源码写法输出，例如 all(unix, feature = "simd")
+impl fmt::Display for CfgPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |f: &mut fmt::Formatter<'_>, name: &str, predicates: &[CfgPredicate]| {
+            let inner: Vec<String> = predicates.iter().map(|p| p.to_string()).collect();
+            write!(f, "{}({})", name, inner.join(", "))
+        };
+        match self {
+            CfgPredicate::Option(name, None) => write!(f, "{}", name),
+            CfgPredicate::Option(name, Some(value)) => write!(f, "{} = {:?}", name, value),
+            CfgPredicate::All(predicates) => list(f, "all", predicates),
+            CfgPredicate::Any(predicates) => list(f, "any", predicates),
+            CfgPredicate::Not(predicate) => write!(f, "not({})", predicate),
+        }
+    }
+}
+
+/// The configuration a crate is compiled for: enabled names (`unix`), name-value pairs
+/// (`target_os = "linux"`) and features
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CfgSet {
+    names: BTreeSet<String>,
+    values: BTreeSet<(String, String)>,
+    features: BTreeSet<String>,
+    all_features: bool,
+}
+
+impl CfgSet {
+    /// The configuration of the machine the analyzer runs on, as a dev build
+    pub fn host() -> Self {
+        let env = if cfg!(target_env = "gnu") {
+            "gnu"
+        } else if cfg!(target_env = "msvc") {
+            "msvc"
+        } else if cfg!(target_env = "musl") {
+            "musl"
+        } else {
+            ""
+        };
+        let endian = if cfg!(target_endian = "big") { "big" } else { "little" };
+        Self::for_target_parts(std::env::consts::ARCH, "", std::env::consts::OS, env, endian)
+    }
+    
+    /// The configuration of a target triple such as `x86_64-pc-windows-msvc` or
+    /// `aarch64-apple-darwin`, as a dev build. Unknown parts are taken as written.
+    pub fn for_target(triple: &str) -> io::Result<Self> {
+        let parts: Vec<&str> = triple.split('-').collect();
+        let (arch, vendor, os, env) = match parts.as_slice() {
+            [arch, vendor, os, env] => (*arch, *vendor, *os, *env),
+            // 三段式的目标：arch-vendor-os（aarch64-apple-darwin）、arch-os-env（aarch64-linux-android）
+            // 或裸机目标 arch-none-abi（thumbv7em-none-eabihf）
+            [arch, "none", _] => (*arch, "unknown", "none", ""),
+            [arch, os @ "linux", env] => (*arch, "unknown", *os, *env),
+            [arch, vendor, os] => (*arch, *vendor, *os, ""),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid target triple '{}'", triple))),
+        };
+        // powerpc64le、mipsel 等小端变体的 target_arch 与大端相同
+        let endian = match arch {
+            "powerpc" | "powerpc64" | "mips" | "mips64" | "s390x" | "sparc" | "sparc64" | "sparcv9" => "big",
+            _ => "little",
+        };
+        let arch = match arch {
+            "i386" | "i586" | "i686" => "x86",
+            arch if arch.starts_with("armv") || arch.starts_with("thumbv") => "arm",
+            arch if arch.starts_with("riscv64") => "riscv64",
+            arch if arch.starts_with("riscv32") => "riscv32",
+            "powerpc64le" => "powerpc64",
+            "mipsel" => "mips",
+            "mips64el" => "mips64",
+            "sparcv9" => "sparc64",
+            arch => arch,
+        };
+        let (os, env) = match (os, env) {
+            ("darwin", env) => ("macos", env),
+            ("linux", env) if env.starts_with("android") => ("android", ""),
+            (os, env) if env.starts_with("gnu") => (os, "gnu"),
+            (os, env) if env.starts_with("musl") => (os, "musl"),
+            (os, env) if env.starts_with("eabi") => (os, ""),
+            (os, env) => (os, env),
+        };
+        Ok(Self::for_target_parts(arch, vendor, os, env, endian))
+    }
+    
+    fn for_target_parts(arch: &str, vendor: &str, os: &str, env: &str, endian: &str) -> Self {
+        let mut set = CfgSet::default();
+        let family = match os {
+            "windows" => Some("windows"),
+            "linux" | "android" | "macos" | "ios" | "tvos" | "watchos" | "visionos" | "freebsd" | "netbsd" | "openbsd"
+            | "dragonfly" | "solaris" | "illumos" | "haiku" | "redox" | "aix" | "hurd" | "nto" | "emscripten" | "fuchsia" => Some("unix"),
+            _ => None,
+        };
+        if let Some(family) = family {
+            set.names.insert(family.to_string());
+            set.insert_value("target_family", family);
+        }
+        if arch.starts_with("wasm") {
+            set.insert_value("target_family", "wasm");
+        }
+        
+        let vendor = match (vendor, os) {
+            ("", "macos" | "ios") => "apple",
+            ("", "windows") => "pc",
+            ("", _) => "unknown",
+            (vendor, _) => vendor,
+        };
+        let pointer_width = match arch {
+            "x86_64" | "aarch64" | "riscv64" | "powerpc64" | "mips64" | "s390x" | "sparc64" | "loongarch64" | "wasm64" => "64",
+            "avr" | "msp430" => "16",
+            _ => "32",
+        };
+        set.insert_value("target_arch", arch);
+        set.insert_value("target_vendor", vendor);
+        set.insert_value("target_os", os);
+        set.insert_value("target_env", env);
+        set.insert_value("target_pointer_width", pointer_width);
+        set.insert_value("target_endian", endian);
+        for width in ["8", "16", "32", "ptr"] {
+            set.insert_value("target_has_atomic", width);
+        }
+        if pointer_width == "64" {
+            set.insert_value("target_has_atomic", "64");
+        }
+        if arch == "x86_64" {
+            for feature in ["fxsr", "sse", "sse2"] {
+                set.insert_value("target_feature", feature);
+            }
+        }
+        
+        // cargo build 默认的dev配置
+        set.names.insert("debug_assertions".to_string());
+        set.insert_value("panic", "unwind");
+        set
+    }
+    
+    fn insert_value(&mut self, name: &str, value: &str) {
+        self.values.insert((name.to_string(), value.to_string()));
+    }
+    
+    /// Enable an option given as `name` or `name="value"`, like rustc's `--cfg`
+    pub fn with_option(mut self, option: &str) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid cfg option '{}'", option));
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+            None => (option.trim(), None),
+        };
+        if syn::parse_str::<syn::Ident>(name).is_err() {
+            return Err(invalid());
+        }
+        match (name, value) {
+            ("feature", Some(value)) => {
+                self.features.insert(value.to_string());
+            },
+            (name, Some(value)) => self.insert_value(name, value),
+            (name, None) => {
+                self.names.insert(name.to_string());
+            },
+        }
+        Ok(self)
+    }
+    
+    /// Enable Cargo features
+    pub fn with_features<I: IntoIterator<Item = String>>(mut self, features: I) -> Self {
+        self.features.extend(features);
+        self
+    }
+    
+    /// Treat every `feature = "..."` as enabled
+    pub fn with_all_features(mut self) -> Self {
+        self.all_features = true;
+        self
+    }
+    
+    pub fn is_enabled(&self, predicate: &CfgPredicate) -> bool {
+        match predicate {
+            CfgPredicate::Option(name, None) => self.names.contains(name),
+            CfgPredicate::Option(name, Some(value)) if name == "feature" => self.all_features || self.features.contains(value),
+            CfgPredicate::Option(name, Some(value)) => self.values.contains(&(name.clone(), value.clone())),
+            CfgPredicate::All(predicates) => predicates.iter().all(|p| self.is_enabled(p)),
+            CfgPredicate::Any(predicates) => predicates.iter().any(|p| self.is_enabled(p)),
+            CfgPredicate::Not(predicate) => !self.is_enabled(predicate),
+        }
+    }
+}
+
+/// Remove the code of a file that is not compiled for the configuration and apply its
+/// `cfg_attr` attributes. Predicates that cannot be parsed count as enabled.
+pub fn strip_inactive(syntax: &mut syn::File, set: &CfgSet) {
+    let mut stripper = CfgStripper { set };
+    if !stripper.keep(&mut syntax.attrs) {
+        syntax.items.clear();
+        return;
+    }
+    stripper.visit_file_mut(syntax);
+}
+
+struct CfgStripper<'a> {
+    set: &'a CfgSet,
+}
+
+impl CfgStripper<'_> {
+    /// Apply the `cfg_attr` attributes of a node and decide whether its `cfg` attributes keep it
+    fn keep(&self, attrs: &mut Vec<Attribute>) -> bool {
+        let mut expanded = Vec::with_capacity(attrs.len());
+        for attr in std::mem::take(attrs) {
+            if !attr.path().is_ident("cfg_attr") {
+                expanded.push(attr);
+                continue;
+            }
+            // #[cfg_attr(predicate, attr1, attr2)] 在条件成立时等价于 #[attr1] #[attr2]
+            let Ok(nested) = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) else {
+                expanded.push(attr);
+                continue;
+            };
+            let mut nested = nested.into_iter();
+            let enabled = nested.next()
+                .and_then(|meta| CfgPredicate::from_meta(&meta))
+                .is_none_or(|predicate| self.set.is_enabled(&predicate));
+            if enabled {
+                expanded.extend(nested.map(|meta| Attribute { meta, ..attr.clone() }));
+            }
+        }
+        *attrs = expanded;
+        
+        attrs.iter()
+            .filter_map(CfgPredicate::from_attribute)
+            .all(|predicate| self.set.is_enabled(&predicate))
+    }
+    
+    fn retain<T>(&self, items: &mut Vec<T>, attrs: impl Fn(&mut T) -> Option<&mut Vec<Attribute>>) {
+        items.retain_mut(|item| attrs(item).is_none_or(|attrs| self.keep(attrs)));
+    }
+    
+    fn retain_punctuated<T, P>(&self, items: &mut Punctuated<T, P>, attrs: impl Fn(&mut T) -> &mut Vec<Attribute>) {
+        *items = std::mem::take(items).into_pairs()
+            .filter_map(|mut pair| self.keep(attrs(pair.value_mut())).then_some(pair))
+            .collect();
+    }
+}
+
+impl VisitMut for CfgStripper<'_> {
+    fn visit_file_mut(&mut self, i: &mut syn::File) {
+        self.retain(&mut i.items, item_attrs);
+        visit_mut::visit_file_mut(self, i);
+    }
+    
+    fn visit_item_mod_mut(&mut self, i: &mut syn::ItemMod) {
+        if let Some((_, items)) = &mut i.content {
+            self.retain(items, item_attrs);
+        }
+        visit_mut::visit_item_mod_mut(self, i);
+    }
+    
+    fn visit_item_impl_mut(&mut self, i: &mut syn::ItemImpl) {
+        self.retain(&mut i.items, |item| match item {
+            syn::ImplItem::Const(item) => Some(&mut item.attrs),
+            syn::ImplItem::Fn(item) => Some(&mut item.attrs),
+            syn::ImplItem::Type(item) => Some(&mut item.attrs),
+            syn::ImplItem::Macro(item) => Some(&mut item.attrs),
+            _ => None,
+        });
+        visit_mut::visit_item_impl_mut(self, i);
+    }
+    
+    fn visit_item_trait_mut(&mut self, i: &mut syn::ItemTrait) {
+        self.retain(&mut i.items, |item| match item {
+            syn::TraitItem::Const(item) => Some(&mut item.attrs),
+            syn::TraitItem::Fn(item) => Some(&mut item.attrs),
+            syn::TraitItem::Type(item) => Some(&mut item.attrs),
+            syn::TraitItem::Macro(item) => Some(&mut item.attrs),
+            _ => None,
+        });
+        visit_mut::visit_item_trait_mut(self, i);
+    }
+    
+    fn visit_item_foreign_mod_mut(&mut self, i: &mut syn::ItemForeignMod) {
+        self.retain(&mut i.items, |item| match item {
+            syn::ForeignItem::Fn(item) => Some(&mut item.attrs),
+            syn::ForeignItem::Static(item) => Some(&mut item.attrs),
+            syn::ForeignItem::Type(item) => Some(&mut item.attrs),
+            syn::ForeignItem::Macro(item) => Some(&mut item.attrs),
+            _ => None,
+        });
+        visit_mut::visit_item_foreign_mod_mut(self, i);
+    }
+    
+    fn visit_block_mut(&mut self, i: &mut syn::Block) {
+        self.retain(&mut i.stmts, |stmt| match stmt {
+            Stmt::Local(local) => Some(&mut local.attrs),
+            Stmt::Item(item) => item_attrs(item),
+            Stmt::Expr(expr, _) => expr_attrs(expr),
+            Stmt::Macro(stmt_macro) => Some(&mut stmt_macro.attrs),
+        });
+        visit_mut::visit_block_mut(self, i);
+    }
+    
+    fn visit_expr_match_mut(&mut self, i: &mut syn::ExprMatch) {
+        self.retain(&mut i.arms, |arm| Some(&mut arm.attrs));
+        visit_mut::visit_expr_match_mut(self, i);
+    }
+    
+    fn visit_expr_struct_mut(&mut self, i: &mut syn::ExprStruct) {
+        self.retain_punctuated(&mut i.fields, |field| &mut field.attrs);
+        visit_mut::visit_expr_struct_mut(self, i);
+    }
+    
+    fn visit_fields_named_mut(&mut self, i: &mut syn::FieldsNamed) {
+        self.retain_punctuated(&mut i.named, |field| &mut field.attrs);
+        visit_mut::visit_fields_named_mut(self, i);
+    }
+    
+    fn visit_fields_unnamed_mut(&mut self, i: &mut syn::FieldsUnnamed) {
+        self.retain_punctuated(&mut i.unnamed, |field| &mut field.attrs);
+        visit_mut::visit_fields_unnamed_mut(self, i);
+    }
+    
+    fn visit_item_enum_mut(&mut self, i: &mut syn::ItemEnum) {
+        self.retain_punctuated(&mut i.variants, |variant| &mut variant.attrs);
+        visit_mut::visit_item_enum_mut(self, i);
+    }
+}
+
+fn item_attrs(item: &mut Item) -> Option<&mut Vec<Attribute>> {
+    match item {
+        Item::Const(item) => Some(&mut item.attrs),
+        Item::Enum(item) => Some(&mut item.attrs),
+        Item::ExternCrate(item) => Some(&mut item.attrs),
+        Item::Fn(item) => Some(&mut item.attrs),
+        Item::ForeignMod(item) => Some(&mut item.attrs),
+        Item::Impl(item) => Some(&mut item.attrs),
+        Item::Macro(item) => Some(&mut item.attrs),
+        Item::Mod(item) => Some(&mut item.attrs),
+        Item::Static(item) => Some(&mut item.attrs),
+        Item::Struct(item) => Some(&mut item.attrs),
+        Item::Trait(item) => Some(&mut item.attrs),
+        Item::TraitAlias(item) => Some(&mut item.attrs),
+        Item::Type(item) => Some(&mut item.attrs),
+        Item::Union(item) => Some(&mut item.attrs),
+        Item::Use(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
+
+// 语句位置上常见的表达式，其余表达式很少带有 #[cfg]
+fn expr_attrs(expr: &mut Expr) -> Option<&mut Vec<Attribute>> {
+    match expr {
+        Expr::Assign(expr) => Some(&mut expr.attrs),
+        Expr::Block(expr) => Some(&mut expr.attrs),
+        Expr::Call(expr) => Some(&mut expr.attrs),
+        Expr::ForLoop(expr) => Some(&mut expr.attrs),
+        Expr::If(expr) => Some(&mut expr.attrs),
+        Expr::Loop(expr) => Some(&mut expr.attrs),
+        Expr::Macro(expr) => Some(&mut expr.attrs),
+        Expr::Match(expr) => Some(&mut expr.attrs),
+        Expr::MethodCall(expr) => Some(&mut expr.attrs),
+        Expr::Path(expr) => Some(&mut expr.attrs),
+        Expr::Return(expr) => Some(&mut expr.attrs),
+        Expr::Unsafe(expr) => Some(&mut expr.attrs),
+        Expr::While(expr) => Some(&mut expr.attrs),
+        _ => None,
+    }
+}
+
+/// The conditions of the `#[cfg]` attributes among some attributes, as written
+pub fn conditions(attrs: &[Attribute]) -> Vec<String> {
+    attrs.iter()
+        .filter_map(CfgPredicate::from_attribute)
+        .map(|predicate| predicate.to_string())
+        .collect()
+}
+
+/// Combine the conditions some code exists under into one predicate, None when it always exists
+pub fn combine(conditions: &[String]) -> Option<String> {
+    let mut unique: Vec<&String> = Vec::new();
+    for condition in conditions {
+        if !unique.contains(&condition) {
+            unique.push(condition);
+        }
+    }
+    match unique.as_slice() {
+        [] => None,
+        [only] => Some((*only).clone()),
+        _ => Some(format!("all({})", unique.iter().map(|c| c.as_str()).collect::<Vec<_>>().join(", "))),
+    }
+}
+
+/// The regions of a file that only exist under `#[cfg]` conditions, for the all configurations mode
+pub fn collect_regions(syntax: &syn::File) -> Vec<CfgRegion> {
+    let mut collector = RegionCollector { regions: Vec::new() };
+    collector.visit_file(syntax);
+    collector.regions
+}
+
+struct RegionCollector {
+    regions: Vec<CfgRegion>,
+}
+
+impl RegionCollector {
+    fn record<T: Spanned>(&mut self, node: &T, attrs: &[Attribute]) {
+        let conditions = conditions(attrs);
+        if conditions.is_empty() {
+            return;
+        }
+        if let (Some(span), Some(condition)) = (SourceSpan::from_span(node.span()), combine(&conditions)) {
+            self.regions.push(CfgRegion { span, condition });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for RegionCollector {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.record(i, &i.attrs);
+        visit::visit_item_fn(self, i);
+    }
+    
+    fn visit_item_mod(&mut self, i: &'ast syn::ItemMod) {
+        self.record(i, &i.attrs);
+        visit::visit_item_mod(self, i);
+    }
+    
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        self.record(i, &i.attrs);
+        visit::visit_item_impl(self, i);
+    }
+    
+    fn visit_item_trait(&mut self, i: &'ast syn::ItemTrait) {
+        self.record(i, &i.attrs);
+        visit::visit_item_trait(self, i);
+    }
+    
+    fn visit_item_foreign_mod(&mut self, i: &'ast syn::ItemForeignMod) {
+        self.record(i, &i.attrs);
+        visit::visit_item_foreign_mod(self, i);
+    }
+    
+    fn visit_item_const(&mut self, i: &'ast syn::ItemConst) {
+        self.record(i, &i.attrs);
+        visit::visit_item_const(self, i);
+    }
+    
+    fn visit_item_static(&mut self, i: &'ast syn::ItemStatic) {
+        self.record(i, &i.attrs);
+        visit::visit_item_static(self, i);
+    }
+    
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.record(i, &i.attrs);
+        visit::visit_impl_item_fn(self, i);
+    }
+    
+    fn visit_impl_item_const(&mut self, i: &'ast syn::ImplItemConst) {
+        self.record(i, &i.attrs);
+        visit::visit_impl_item_const(self, i);
+    }
+    
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.record(i, &i.attrs);
+        visit::visit_trait_item_fn(self, i);
+    }
+    
+    fn visit_foreign_item_fn(&mut self, i: &'ast syn::ForeignItemFn) {
+        self.record(i, &i.attrs);
+        visit::visit_foreign_item_fn(self, i);
+    }
+    
+    fn visit_stmt(&mut self, i: &'ast Stmt) {
+        match i {
+            Stmt::Local(local) => self.record(i, &local.attrs),
+            Stmt::Macro(stmt_macro) => self.record(i, &stmt_macro.attrs),
+            Stmt::Expr(Expr::Block(expr), _) => self.record(i, &expr.attrs),
+            Stmt::Expr(Expr::Unsafe(expr), _) => self.record(i, &expr.attrs),
+            Stmt::Expr(Expr::Call(expr), _) => self.record(i, &expr.attrs),
+            Stmt::Expr(Expr::MethodCall(expr), _) => self.record(i, &expr.attrs),
+            Stmt::Expr(Expr::If(expr), _) => self.record(i, &expr.attrs),
+            Stmt::Expr(Expr::Assign(expr), _) => self.record(i, &expr.attrs),
+            Stmt::Expr(Expr::Macro(expr), _) => self.record(i, &expr.attrs),
+            // 语句中的项由各 visit_item_* 记录
+            _ => {},
+        }
+        visit::visit_stmt(self, i);
+    }
+    
+    fn visit_arm(&mut self, i: &'ast syn::Arm) {
+        self.record(i, &i.attrs);
+        visit::visit_arm(self, i);
+    }
+}
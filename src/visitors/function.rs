@@ -1,13 +1,23 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use syn::{
+    spanned::Spanned,
     visit::{self, Visit}, 
-    ItemFn, Visibility, ExprUnsafe, ImplItemFn, Expr, ExprCall, ExprMethodCall, ExprPath,
-    ExprUnary, UnOp, Pat, PathSegment,
-    // Removed unused import: spanned::Spanned
+    ItemFn, Visibility, ExprUnsafe, ImplItemFn, TraitItemFn, Expr, ExprCall, ExprMethodCall,
+    ExprUnary, UnOp,
 };
 use quote::ToTokens;
 
-use crate::models::{FunctionInfo, TypeDefinition, VisibilityKind, UnsafeOperation, UnsafeOperationType};
+use crate::unsafe_apis;
+use crate::visitors::scope::{self, ImplScope};
+use crate::models::{Confidence, ForeignItem, FunctionInfo, SourceSpan, TraitDefinition, TypeDefinition, VisibilityKind, UnsafeApiCategory, UnsafeDeclaration, UnsafeDeclarationKind, UnsafeOperation, UnsafeOperationType};
+
+/// State of the enclosing node, saved while a nested function, closure or initializer is visited
+struct FunctionFrame {
+    function: Option<String>,
+    has_unsafe: bool,
+    in_unsafe_block: bool,
+    unsafe_operations: Vec<UnsafeOperation>,
+}
 
 /// Visitor for collecting function information and detecting unsafe blocks
 pub struct FunctionVisitor {
@@ -20,34 +30,24 @@ pub struct FunctionVisitor {
     pub source_code: String,
     pub type_definitions: HashMap<String, TypeDefinition>, // Collected type definitions
     pub current_impl_type: Option<String>, // Current impl block's type name
+    pub current_impl: Option<ImplScope>, // Current impl block, used to qualify method keys
+    pub traits: HashMap<String, TraitDefinition>, // Collected trait definitions
+    pub current_trait: Option<String>, // Current trait definition's name
+    pub modules: HashMap<String, VisibilityKind>, // Declared modules (inline or `mod foo;`) and their visibility
     pub impl_blocks: HashMap<String, Vec<String>>, // Collection of complete impl blocks for each type
     pub in_unsafe_block: bool, // 是否在unsafe块内
     pub current_unsafe_operations: Vec<UnsafeOperation>, // 当前函数中的unsafe操作
-    pub known_unsafe_functions: HashSet<String>, // 已知的unsafe函数列表
+    pub known_unsafe_functions: HashSet<String>, // 配置文件中额外视为unsafe的函数路径
+    pub extra_unsafe_keywords: Vec<String>, // 配置文件中额外的unsafe方法名关键词
+    pub unsafe_declarations: Vec<UnsafeDeclaration>, // unsafe impl、unsafe trait 和 global_asm! 声明
+    pub mutable_statics: BTreeSet<String>, // static mut 项的键
+    pub foreign_items: HashMap<String, ForeignItem>, // extern 块中声明的函数和静态变量
+    function_stack: Vec<FunctionFrame>, // 外层节点的状态，访问嵌套的函数、闭包和初始化表达式时保存
+    closure_counts: HashMap<String, usize>, // 每个节点中已访问的闭包数量，用于生成闭包的键
 }
 
 impl FunctionVisitor {
     pub fn new(file_path: String, source_code: String) -> Self {
-        // 初始化已知的unsafe函数列表
-        let mut known_unsafe_functions = HashSet::new();
-        // 标准库中常见的unsafe函数
-        known_unsafe_functions.insert("std::mem::transmute".to_string());
-        known_unsafe_functions.insert("std::mem::transmute_copy".to_string());
-        known_unsafe_functions.insert("ptr::read".to_string());
-        known_unsafe_functions.insert("ptr::read_volatile".to_string());
-        known_unsafe_functions.insert("ptr::write".to_string());
-        known_unsafe_functions.insert("ptr::write_volatile".to_string());
-        known_unsafe_functions.insert("ptr::copy".to_string());
-        known_unsafe_functions.insert("ptr::copy_nonoverlapping".to_string());
-        known_unsafe_functions.insert("std::ptr::drop_in_place".to_string());
-        known_unsafe_functions.insert("from_raw_parts_mut".to_string());
-        known_unsafe_functions.insert("slice::from_raw_parts_mut".to_string());
-        known_unsafe_functions.insert("slice::from_raw_parts".to_string());
-        known_unsafe_functions.insert("core::slice::from_raw_parts_mut".to_string());
-        known_unsafe_functions.insert("core::slice::from_raw_parts".to_string());
-        known_unsafe_functions.insert("from_utf8_unchecked".to_string());
-        known_unsafe_functions.insert("from_utf8_unchecked_mut".to_string());
-        
         FunctionVisitor {
             current_module_path: Vec::new(),
             functions: HashMap::new(),
@@ -58,13 +58,41 @@ impl FunctionVisitor {
             source_code,
             type_definitions: HashMap::new(),
             current_impl_type: None,
+            current_impl: None,
+            traits: HashMap::new(),
+            current_trait: None,
+            modules: HashMap::new(),
             impl_blocks: HashMap::new(),
             in_unsafe_block: false,
             current_unsafe_operations: Vec::new(),
-            known_unsafe_functions,
+            known_unsafe_functions: HashSet::new(),
+            extra_unsafe_keywords: Vec::new(),
+            unsafe_declarations: Vec::new(),
+            mutable_statics: BTreeSet::new(),
+            foreign_items: HashMap::new(),
+            function_stack: Vec::new(),
+            closure_counts: HashMap::new(),
         }
     }
     
+    /// Start visiting from the given module path, e.g. `crate::foo` for `src/foo.rs`
+    pub fn with_module_path(mut self, module_path: Vec<String>) -> Self {
+        self.current_module_path = module_path;
+        self
+    }
+    
+    /// Treat additional function paths (e.g. from `analysis.toml`) as unsafe
+    pub fn with_known_unsafe_functions(mut self, functions: &[String]) -> Self {
+        self.known_unsafe_functions.extend(functions.iter().cloned());
+        self
+    }
+    
+    /// Treat method names containing any of the given keywords as unsafe calls
+    pub fn with_unsafe_keywords(mut self, keywords: &[String]) -> Self {
+        self.extra_unsafe_keywords.extend(keywords.iter().cloned());
+        self
+    }
+    
     /// Get current module path
     pub fn get_current_module_path(&self) -> String {
         self.current_module_path.join("::")
@@ -83,14 +111,13 @@ impl FunctionVisitor {
     /// Add function to result set
     pub fn add_function(&mut self, name: String, vis: &Visibility, fn_item: &ItemFn) {
         let module_path = self.get_current_module_path();
-        let full_path = if module_path.is_empty() {
-            name.clone()
-        } else {
-            format!("{}::{}", module_path, name)
-        };
+        // 函数体内定义的函数以外层节点为前缀
+        let parent = self.enclosing_function();
+        let full_path = scope::item_key(parent.as_deref(), &module_path, &name);
         
         self.current_function = Some(full_path.clone());
         self.has_unsafe = false;
+        self.in_unsafe_block = false;
         
         // Extract function source code
         let source_code = fn_item.to_token_stream().to_string();
@@ -140,22 +167,68 @@ impl FunctionVisitor {
             has_self_param,
             owner_type,
             unsafe_operations: Vec::new(),
+            span: Self::function_span(vis, &fn_item.sig, &fn_item.block),
+            unsafe_blocks: Vec::new(),
+            return_type: Self::return_type(&fn_item.sig, None),
+            trait_name: None,
+            parent,
+            exported_symbol: Self::exported_symbol(&fn_item.attrs, &fn_item.sig),
+            cfg: None,
         };
         
-        self.functions.insert(full_path, info);
+        self.insert_function(full_path, info);
+    }
+    
+    /// Symbol under which an `extern` function is exported by `#[no_mangle]` (also written
+    /// `#[unsafe(no_mangle)]`) or `#[export_name = "..."]`, i.e. foreign code can call it directly
+    pub fn exported_symbol(attrs: &[syn::Attribute], sig: &syn::Signature) -> Option<String> {
+        sig.abi.as_ref()?;
+        attrs.iter().find_map(|attr| Self::exported_symbol_of(&attr.meta, &sig.ident))
+    }
+    
+    fn exported_symbol_of(meta: &syn::Meta, ident: &syn::Ident) -> Option<String> {
+        match meta {
+            syn::Meta::Path(path) if path.is_ident("no_mangle") => Some(ident.to_string()),
+            syn::Meta::NameValue(name_value) if name_value.path.is_ident("export_name") => match &name_value.value {
+                Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(symbol), .. }) => Some(symbol.value()),
+                _ => None,
+            },
+            // Rust 2024 中写作 #[unsafe(no_mangle)]
+            syn::Meta::List(list) if list.path.is_ident("unsafe") => list.parse_args::<syn::Meta>().ok()
+                .and_then(|inner| Self::exported_symbol_of(&inner, ident)),
+            _ => None,
+        }
+    }
+    
+    /// Span of a function from its visibility (or signature) to the end of its body, attributes excluded
+    pub fn function_span(vis: &Visibility, sig: &syn::Signature, block: &syn::Block) -> Option<SourceSpan> {
+        let start = match vis {
+            Visibility::Inherited => sig.span(),
+            _ => vis.span(),
+        };
+        SourceSpan::from_range(start, block.span())
+    }
+
+    /// Principal return type of a function, used to infer method call receivers
+    pub fn return_type(sig: &syn::Signature, self_type: Option<&str>) -> Option<String> {
+        match &sig.output {
+            syn::ReturnType::Type(_, ty) => scope::principal_type(ty, self_type),
+            syn::ReturnType::Default => None,
+        }
     }
     
     /// Extract source code from impl block function
     pub fn add_impl_function(&mut self, name: String, vis: &Visibility, impl_fn: &ImplItemFn) {
         let module_path = self.get_current_module_path();
-        let full_path = if module_path.is_empty() {
-            name.clone()
-        } else {
-            format!("{}::{}", module_path, name)
+        // 方法以 module::Type::method 或 module::<Type as Trait>::method 为键，避免同名方法互相覆盖
+        let full_path = match &self.current_impl {
+            Some(impl_scope) => impl_scope.method_key(&module_path, &name),
+            None => scope::qualify(&module_path, &name),
         };
         
         self.current_function = Some(full_path.clone());
         self.has_unsafe = false;
+        self.in_unsafe_block = false;
         
         // Extract function source code
         let source_code = impl_fn.to_token_stream().to_string();
@@ -169,24 +242,11 @@ impl FunctionVisitor {
         // If method has self parameter and we know current impl type, add it to parameter types
         let has_self_param = impl_fn.sig.inputs.iter().any(|arg| matches!(arg, syn::FnArg::Receiver(_)));
         
-        // 确定函数所属的类型
-        let owner_type = if has_self_param {
-            if let Some(impl_type) = &self.current_impl_type {
-                // 如果在impl块中，我们知道类型
-                param_types.insert(impl_type.clone());
-                Some(impl_type.clone())
-            } else {
-                // 尝试从module_path中推断类型
-                let parts: Vec<&str> = module_path.split("::").collect();
-                if !parts.is_empty() {
-                    Some(parts.last().unwrap().to_string())
-                } else {
-                    None
-                }
-            }
-        } else {
-            None
-        };
+        // 确定函数所属的类型，没有self参数的关联函数同样属于impl的类型
+        let owner_type = self.current_impl_type.clone();
+        if has_self_param && let Some(impl_type) = &owner_type {
+            param_types.insert(impl_type.clone());
+        }
         
         let info = FunctionInfo {
             name,
@@ -201,11 +261,352 @@ impl FunctionVisitor {
             has_self_param,
             owner_type,
             unsafe_operations: Vec::new(),
+            span: Self::function_span(vis, &impl_fn.sig, &impl_fn.block),
+            unsafe_blocks: Vec::new(),
+            return_type: Self::return_type(&impl_fn.sig, self.current_impl_type.as_deref()),
+            trait_name: self.current_impl.as_ref().and_then(|impl_scope| impl_scope.trait_name.clone()),
+            parent: None,
+            exported_symbol: None,
+            cfg: None,
+        };
+        
+        self.insert_function(full_path, info);
+    }
+    
+    /// Add a trait method with a default implementation, keyed as `module::Trait::method`
+    pub fn add_trait_function(&mut self, trait_name: &str, vis: VisibilityKind, trait_fn: &TraitItemFn, block: &syn::Block) {
+        let module_path = self.get_current_module_path();
+        let name = trait_fn.sig.ident.to_string();
+        let full_path = scope::qualify(&module_path, &format!("{}::{}", trait_name, name));
+        
+        self.current_function = Some(full_path.clone());
+        self.has_unsafe = false;
+        self.in_unsafe_block = false;
+        
+        let (param_types, return_types) = self.analyze_function_signature(&trait_fn.sig);
+        let has_self_param = trait_fn.sig.inputs.iter().any(|arg| matches!(arg, syn::FnArg::Receiver(_)));
+        
+        let info = FunctionInfo {
+            name,
+            module_path,
+            // 默认方法与trait本身的可见性相同
+            visibility: vis,
+            has_internal_unsafe: false, // Updated later
+            is_unsafe_fn: trait_fn.sig.unsafety.is_some(),
+            file_path: self.file_path.clone(),
+            source_code: trait_fn.to_token_stream().to_string(),
+            param_custom_types: param_types,
+            return_custom_types: return_types,
+            has_self_param,
+            owner_type: None,
+            unsafe_operations: Vec::new(),
+            span: SourceSpan::from_range(trait_fn.sig.span(), block.span()),
+            unsafe_blocks: Vec::new(),
+            return_type: Self::return_type(&trait_fn.sig, None),
+            trait_name: Some(trait_name.to_string()),
+            parent: None,
+            exported_symbol: None,
+            cfg: None,
+        };
+        
+        self.insert_function(full_path, info);
+    }
+    
+    /// Add a closure as its own node, keyed as `parent::{closure#N}`. The body of a closure
+    /// inside an unsafe block stays in the unsafe context.
+    pub fn add_closure(&mut self, parent: String, closure: &syn::ExprClosure) {
+        let counter = self.closure_counts.entry(parent.clone()).or_default();
+        let full_path = scope::closure_key(&parent, *counter);
+        *counter += 1;
+        
+        self.current_function = Some(full_path.clone());
+        self.has_unsafe = false;
+        
+        let return_type = match &closure.output {
+            syn::ReturnType::Type(_, ty) => scope::principal_type(ty, self.current_impl_type.as_deref()),
+            syn::ReturnType::Default => None,
         };
         
+        let info = FunctionInfo {
+            name: full_path.rsplit("::").next().unwrap_or_default().to_string(),
+            module_path: self.get_current_module_path(),
+            visibility: VisibilityKind::Module,
+            has_internal_unsafe: false, // Updated later
+            is_unsafe_fn: false,
+            file_path: self.file_path.clone(),
+            source_code: closure.to_token_stream().to_string(),
+            param_custom_types: HashSet::new(),
+            return_custom_types: HashSet::new(),
+            has_self_param: false,
+            owner_type: None,
+            unsafe_operations: Vec::new(),
+            span: SourceSpan::from_span(closure.span()),
+            unsafe_blocks: Vec::new(),
+            return_type,
+            trait_name: None,
+            parent: Some(parent),
+            exported_symbol: None,
+            cfg: None,
+        };
+        
+        self.insert_function(full_path, info);
+    }
+    
+    /// Add the initializer of a const or static as its own node, keyed like a function of the
+    /// same name; associated consts are keyed like methods, e.g. `crate::foo::Bar::LIMIT`
+    pub fn add_initializer<T: ToTokens>(&mut self, name: String, vis: &Visibility, keyword: proc_macro2::Span, ty: &syn::Type, item: &T, associated: bool) {
+        let module_path = self.get_current_module_path();
+        let (full_path, owner_type, parent) = match &self.current_impl {
+            Some(impl_scope) if associated => (impl_scope.method_key(&module_path, &name), self.current_impl_type.clone(), None),
+            _ => {
+                let parent = self.enclosing_function();
+                (scope::item_key(parent.as_deref(), &module_path, &name), None, parent)
+            },
+        };
+        
+        self.current_function = Some(full_path.clone());
+        self.has_unsafe = false;
+        self.in_unsafe_block = false;
+        
+        let mut return_types = HashSet::new();
+        self.extract_custom_types(ty, &mut return_types);
+        
+        let info = FunctionInfo {
+            name,
+            module_path,
+            visibility: self.convert_visibility(vis),
+            has_internal_unsafe: false, // Updated later
+            is_unsafe_fn: false,
+            file_path: self.file_path.clone(),
+            source_code: item.to_token_stream().to_string(),
+            param_custom_types: HashSet::new(),
+            return_custom_types: return_types,
+            has_self_param: false,
+            owner_type,
+            unsafe_operations: Vec::new(),
+            // 从可见性（或const/static关键字）到结尾，不含属性
+            span: match vis {
+                Visibility::Inherited => SourceSpan::from_range(keyword, item.span()),
+                _ => SourceSpan::from_range(vis.span(), item.span()),
+            },
+            unsafe_blocks: Vec::new(),
+            return_type: scope::principal_type(ty, self.current_impl_type.as_deref()),
+            trait_name: None,
+            parent,
+            exported_symbol: None,
+            cfg: None,
+        };
+        
+        self.insert_function(full_path, info);
+    }
+    
+    /// Record an `unsafe impl Trait for Type`. For `Send` and `Sync` impls, type parameters
+    /// without a matching bound are reported as missing bounds.
+    pub fn add_unsafe_impl(&mut self, item: &syn::ItemImpl, trait_path: &syn::Path) {
+        let trait_name = scope::compact_tokens(trait_path);
+        let bounds = Self::generic_bounds(&item.generics);
+        
+        // 手动实现Send/Sync时，泛型参数通常也需要相应的约束，否则可能把非线程安全的类型跨线程传递；
+        // Sync实现中 T: Send 也可以接受（例如Mutex一类只交出独占访问的类型）
+        let marker = trait_path.segments.last().map(|segment| segment.ident.to_string());
+        let accepted: &[&str] = match marker.as_deref() {
+            Some("Send") => &["Send"],
+            Some("Sync") => &["Sync", "Send"],
+            _ => &[],
+        };
+        let missing_bounds = match marker {
+            Some(marker) if !accepted.is_empty() => bounds.iter()
+                .filter(|(_, param_bounds)| !param_bounds.iter().any(|bound| accepted.contains(&bound.rsplit("::").next().unwrap_or(bound))))
+                .map(|(param, _)| format!("{}: {}", param, marker))
+                .collect(),
+            _ => Vec::new(),
+        };
+        
+        let start = item.unsafety.map(|token| token.span).unwrap_or_else(|| item.impl_token.span);
+        self.unsafe_declarations.push(UnsafeDeclaration {
+            kind: UnsafeDeclarationKind::Impl,
+            trait_name,
+            self_type: Some(scope::compact_tokens(&item.self_ty)),
+            self_type_name: scope::impl_type_name(&item.self_ty),
+            module_path: self.get_current_module_path(),
+            file_path: self.file_path.clone(),
+            span: SourceSpan::from_range(start, item.span()),
+            generic_bounds: Self::format_generic_bounds(&bounds),
+            missing_bounds,
+            source_code: item.to_token_stream().to_string(),
+            type_definition: None,
+        });
+    }
+    
+    /// Record an `unsafe trait` definition
+    pub fn add_unsafe_trait(&mut self, item: &syn::ItemTrait) {
+        let start = match &item.vis {
+            Visibility::Inherited => item.unsafety.map(|token| token.span).unwrap_or_else(|| item.trait_token.span),
+            vis => vis.span(),
+        };
+        self.unsafe_declarations.push(UnsafeDeclaration {
+            kind: UnsafeDeclarationKind::Trait,
+            trait_name: item.ident.to_string(),
+            self_type: None,
+            self_type_name: None,
+            module_path: self.get_current_module_path(),
+            file_path: self.file_path.clone(),
+            span: SourceSpan::from_range(start, item.span()),
+            generic_bounds: Self::format_generic_bounds(&Self::generic_bounds(&item.generics)),
+            missing_bounds: Vec::new(),
+            source_code: item.to_token_stream().to_string(),
+            type_definition: None,
+        });
+    }
+    
+    /// Record a `global_asm!` invocation, assembly linked into the crate outside of any function
+    pub fn add_global_asm(&mut self, mac: &syn::Macro) {
+        self.unsafe_declarations.push(UnsafeDeclaration {
+            kind: UnsafeDeclarationKind::GlobalAsm,
+            trait_name: scope::compact_tokens(&mac.path),
+            self_type: None,
+            self_type_name: None,
+            module_path: self.get_current_module_path(),
+            file_path: self.file_path.clone(),
+            span: SourceSpan::from_span(mac.span()),
+            generic_bounds: Vec::new(),
+            missing_bounds: Vec::new(),
+            // 作为项的宏调用以分号结尾
+            source_code: format!("{};", mac.to_token_stream()),
+            type_definition: None,
+        });
+    }
+    
+    /// Record the functions and statics declared in an `extern` block, keyed like items
+    /// defined in its place; calls to them are FFI calls
+    pub fn add_foreign_items(&mut self, foreign_mod: &syn::ItemForeignMod) {
+        let module_path = self.get_current_module_path();
+        let abi = foreign_mod.abi.name.as_ref().map(|name| name.value()).unwrap_or_else(|| "C".to_string());
+        
+        for item in &foreign_mod.items {
+            // unsafe extern 块中的 safe fn 无需unsafe即可调用，syn将其解析为Verbatim，这里不会记录
+            let (ident, attrs, is_static) = match item {
+                syn::ForeignItem::Fn(item) => (&item.sig.ident, &item.attrs, false),
+                syn::ForeignItem::Static(item) => (&item.ident, &item.attrs, true),
+                _ => continue,
+            };
+            let link_name = attrs.iter()
+                .find_map(|attr| match &attr.meta {
+                    syn::Meta::NameValue(name_value) if name_value.path.is_ident("link_name") => match &name_value.value {
+                        Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(symbol), .. }) => Some(symbol.value()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .unwrap_or_else(|| ident.to_string());
+            
+            let name = ident.to_string();
+            let full_path = scope::item_key(self.current_function.as_deref(), &module_path, &name);
+            self.foreign_items.insert(full_path, ForeignItem {
+                name,
+                module_path: module_path.clone(),
+                abi: abi.clone(),
+                link_name,
+                is_static,
+                file_path: self.file_path.clone(),
+                span: SourceSpan::from_span(item.span()),
+            });
+        }
+    }
+    
+    /// Bounds of each type parameter, declared inline or in the where clause, in declaration order
+    pub fn generic_bounds(generics: &syn::Generics) -> Vec<(String, Vec<String>)> {
+        let mut bounds: Vec<(String, Vec<String>)> = generics.type_params()
+            .map(|param| (param.ident.to_string(), param.bounds.iter().map(scope::compact_tokens).collect()))
+            .collect();
+        if let Some(where_clause) = &generics.where_clause {
+            for predicate in &where_clause.predicates {
+                if let syn::WherePredicate::Type(predicate) = predicate
+                    && let syn::Type::Path(bounded) = &predicate.bounded_ty
+                    && let Some(ident) = bounded.path.get_ident()
+                    && let Some((_, param_bounds)) = bounds.iter_mut().find(|(param, _)| ident == param) {
+                    param_bounds.extend(predicate.bounds.iter().map(scope::compact_tokens));
+                }
+            }
+        }
+        bounds
+    }
+    
+    /// Format parameter bounds like `T: Clone + Send`, parameters without bounds as `T`
+    fn format_generic_bounds(bounds: &[(String, Vec<String>)]) -> Vec<String> {
+        bounds.iter()
+            .map(|(param, param_bounds)| {
+                if param_bounds.is_empty() {
+                    param.clone()
+                } else {
+                    format!("{}: {}", param, param_bounds.join(" + "))
+                }
+            })
+            .collect()
+    }
+    
+    /// Node whose body contains the node being added: the function, closure or initializer
+    /// that was current before `enter_function`
+    fn enclosing_function(&self) -> Option<String> {
+        self.function_stack.last().and_then(|frame| frame.function.clone())
+    }
+    
+    /// Whether the code being visited can call unsafe functions: inside an `unsafe` block, or
+    /// in the body of an `unsafe fn` or of a closure within it
+    fn in_unsafe_context(&self) -> bool {
+        if self.in_unsafe_block {
+            return true;
+        }
+        let mut current = self.current_function.as_ref();
+        while let Some(path) = current {
+            let Some(info) = self.functions.get(path) else {
+                return false;
+            };
+            if info.is_unsafe_fn {
+                return true;
+            }
+            // 嵌套函数不继承外层函数的unsafe上下文，闭包继承
+            if !scope::is_closure_key(path) {
+                return false;
+            }
+            current = info.parent.as_ref();
+        }
+        false
+    }
+    
+    /// Add a node as the current one. A node already added under the same key, e.g. the
+    /// `#[cfg(unix)]` variant of a function that also has a `#[cfg(windows)]` variant, keeps
+    /// its unsafe blocks and operations, and the new definition adds its own to them.
+    fn insert_function(&mut self, full_path: String, mut info: FunctionInfo) {
+        if let Some(previous) = self.functions.remove(&full_path) {
+            info.unsafe_blocks = previous.unsafe_blocks;
+            self.current_unsafe_operations = previous.unsafe_operations;
+            self.has_unsafe = previous.has_internal_unsafe;
+        }
         self.functions.insert(full_path, info);
     }
     
+    /// Save the state of the current node before visiting a nested function, closure or initializer
+    fn enter_function(&mut self) {
+        self.function_stack.push(FunctionFrame {
+            function: self.current_function.take(),
+            has_unsafe: std::mem::take(&mut self.has_unsafe),
+            in_unsafe_block: self.in_unsafe_block,
+            unsafe_operations: std::mem::take(&mut self.current_unsafe_operations),
+        });
+    }
+    
+    /// Finish the current node and restore the enclosing one
+    fn leave_function(&mut self) {
+        self.update_unsafe_state();
+        if let Some(frame) = self.function_stack.pop() {
+            self.current_function = frame.function;
+            self.has_unsafe = frame.has_unsafe;
+            self.in_unsafe_block = frame.in_unsafe_block;
+            self.current_unsafe_operations = frame.unsafe_operations;
+        }
+    }
+    
     /// Add type definition to result set
     pub fn add_type_definition<T: ToTokens>(&mut self, name: String, vis: &Visibility, type_item: &T) {
         let module_path = self.get_current_module_path();
@@ -225,11 +626,30 @@ impl FunctionVisitor {
             source_code,
             file_path: self.file_path.clone(),
             constructors: Vec::new(), // Initialize as empty list
+            field_types: BTreeMap::new(),
+            is_union: false,
         };
         
         self.type_definitions.insert(full_path, definition);
     }
 
+    /// Record the field types of a struct or union added by `add_type_definition`, used to
+    /// infer the receiver of calls like `self.field.method()`
+    fn set_field_types<'a>(&mut self, name: &str, fields: impl Iterator<Item = &'a syn::Field>, is_union: bool) {
+        let field_types: BTreeMap<String, String> = fields
+            .enumerate()
+            .filter_map(|(index, field)| {
+                let field_name = field.ident.as_ref().map(|ident| ident.to_string()).unwrap_or_else(|| index.to_string());
+                scope::principal_type(&field.ty, Some(name)).map(|ty| (field_name, ty))
+            })
+            .collect();
+        let type_path = scope::qualify(&self.get_current_module_path(), name);
+        if let Some(definition) = self.type_definitions.get_mut(&type_path) {
+            definition.field_types = field_types;
+            definition.is_union = is_union;
+        }
+    }
+    
     /// Check if a method is a constructor for the given type
     pub fn is_constructor(&self, method: &syn::ImplItemFn, type_name: &str) -> bool {
         if let syn::ReturnType::Type(_, ty) = &method.sig.output {
@@ -242,11 +662,10 @@ impl FunctionVisitor {
                 },
                 // Handle reference types, like &mut Self
                 syn::Type::Reference(type_ref) => {
-                    if let syn::Type::Path(type_path) = &*type_ref.elem {
-                        if let Some(segment) = type_path.path.segments.last() {
-                            let return_type = segment.ident.to_string();
-                            return return_type == "Self" || return_type == type_name;
-                        }
+                    if let syn::Type::Path(type_path) = &*type_ref.elem
+                        && let Some(segment) = type_path.path.segments.last() {
+                        let return_type = segment.ident.to_string();
+                        return return_type == "Self" || return_type == type_name;
                     }
                 },
                 _ => {}
@@ -335,30 +754,30 @@ impl FunctionVisitor {
         )
     }
     
-    /// 检测是否是已知的unsafe函数
+    /// 检测是否是配置文件中指定的unsafe函数
+    /// 路径按段匹配，`raw::peek` 与 `crate::raw::peek` 互相匹配
     pub fn is_known_unsafe_function(&self, path: &str) -> bool {
-        self.known_unsafe_functions.contains(path)
+        self.known_unsafe_functions.iter().any(|known| {
+            known == path
+                || known.strip_suffix(path).is_some_and(|prefix| prefix.ends_with("::"))
+                || path.strip_suffix(known.as_str()).is_some_and(|prefix| prefix.ends_with("::"))
+        })
     }
     
-    /// 检查函数名是否包含unsafe关键词
+    /// 检查方法名是否包含配置文件中的unsafe关键词
     pub fn has_unsafe_keywords(&self, name: &str) -> bool {
-        name.contains("unsafe") || 
-        name.contains("unchecked") || 
-        name.contains("as_ptr") || 
-        name.contains("as_mut_ptr") || 
-        name == "assume_init" || 
-        name == "set_len" ||
-        name.contains("transmute") ||
-        name.contains("from_raw_parts")
+        self.extra_unsafe_keywords.iter().any(|keyword| name.contains(keyword.as_str()))
     }
     
     /// 记录unsafe操作
-    pub fn record_unsafe_operation(&mut self, op_type: UnsafeOperationType, description: String, code_snippet: String) {
+    pub fn record_unsafe_operation(&mut self, op_type: UnsafeOperationType, _description: String, code_snippet: String, span: proc_macro2::Span, confidence: Confidence, category: Option<UnsafeApiCategory>) {
         // 只有在有当前函数的情况下才记录
         if let Some(current_fn) = &self.current_function {
-            // 检查是否已存在相同的操作（相同代码片段）
+            let span = SourceSpan::from_span(span);
+            
+            // 检查是否已存在相同的操作（相同位置的相同代码片段）
             for op in &self.current_unsafe_operations {
-                if op.code_snippet == code_snippet {
+                if op.code_snippet == code_snippet && op.span == span {
                     return; // 跳过重复的操作
                 }
             }
@@ -371,6 +790,7 @@ impl FunctionVisitor {
                 UnsafeOperationType::InlineAssembly => "内联汇编".to_string(),
                 UnsafeOperationType::UnionFieldAccess => "访问联合体字段".to_string(),
                 UnsafeOperationType::MutStaticAccess => "访问可变静态变量".to_string(),
+                UnsafeOperationType::FfiCall { .. } => op_type.to_string(),
                 UnsafeOperationType::Other(desc) => desc.clone(),
             };
             
@@ -378,7 +798,12 @@ impl FunctionVisitor {
                 operation_type: op_type,
                 description: simplified_description,
                 code_snippet,
-                line_number: None,
+                line_number: span.map(|span| span.start_line),
+                span,
+                confidence,
+                category,
+                macro_origin: None,
+                cfg: None,
             };
             
             // 先将操作添加到当前函数中
@@ -393,22 +818,14 @@ impl FunctionVisitor {
     
     /// 检测是否是裸指针类型
     pub fn is_raw_pointer_type(&self, ty: &syn::Type) -> bool {
-        match ty {
-            syn::Type::Ptr(_) => true,
-            _ => false,
-        }
+        matches!(ty, syn::Type::Ptr(_))
     }
     
     /// 检测expr是否可能是裸指针
     pub fn might_be_raw_pointer(&self, expr: &Expr) -> bool {
         match expr {
             // 检查指针类型转换，如：expr as *const T 或 expr as *mut T
-            Expr::Cast(expr_cast) => {
-                match &*expr_cast.ty {
-                    syn::Type::Ptr(_) => true,
-                    _ => false,
-                }
-            },
+            Expr::Cast(expr_cast) => matches!(&*expr_cast.ty, syn::Type::Ptr(_)),
             // 检查路径表达式，如：ptr
             Expr::Path(expr_path) => {
                 let path_str = expr_path.to_token_stream().to_string();
@@ -464,167 +881,21 @@ impl FunctionVisitor {
     
     /// 更新unsafe状态
     pub fn update_unsafe_state(&mut self) {
-        if let Some(name) = &self.current_function {
-            if let Some(func) = self.functions.get_mut(name) {
-                func.has_internal_unsafe = self.has_unsafe;
-                
-                // 如果有unsafe块或调用，将函数添加到unsafe函数集合中
-                if self.has_unsafe || func.is_unsafe_fn {
-                    self.unsafe_functions.insert(name.clone());
-                }
-                
-                // 更新函数的unsafe操作列表
-                func.unsafe_operations = self.current_unsafe_operations.clone();
-            }
-        }
-        
-        // 重置当前unsafe操作列表
-        self.current_unsafe_operations.clear();
-    }
-
-    // 判断函数调用是否是常见的unsafe操作
-    pub fn is_common_unsafe_operation(&self, func_path: &str) -> Option<UnsafeOperationType> {
-        // 常见的unsafe函数名列表
-        let common_unsafe_funcs = [
-            // 裸指针相关操作
-            ("from_raw_parts", UnsafeOperationType::RawPointerDereference),
-            ("from_raw_parts_mut", UnsafeOperationType::RawPointerDereference),
-            ("copy_nonoverlapping", UnsafeOperationType::RawPointerDereference),
-            ("copy", UnsafeOperationType::RawPointerDereference),
-            ("write", UnsafeOperationType::RawPointerDereference),
-            ("read", UnsafeOperationType::RawPointerDereference),
-            ("offset", UnsafeOperationType::RawPointerDereference),
-            ("add", UnsafeOperationType::RawPointerDereference),
-            ("drop_in_place", UnsafeOperationType::RawPointerDereference),
-            ("slice_from_raw_parts", UnsafeOperationType::RawPointerDereference),
-            ("slice_from_raw_parts_mut", UnsafeOperationType::RawPointerDereference),
-            
-            // 内存相关
-            ("transmute", UnsafeOperationType::Other("内存转换".to_string())),
-            ("forget", UnsafeOperationType::Other("内存忽略".to_string())),
-            ("zeroed", UnsafeOperationType::Other("零初始化".to_string())),
-            ("uninitialized", UnsafeOperationType::Other("未初始化内存".to_string())),
-            
-            // 其他常见unsafe操作
-            ("set_len", UnsafeOperationType::UnsafeMethodCall),
-            ("as_ptr", UnsafeOperationType::UnsafeMethodCall),
-            ("as_mut_ptr", UnsafeOperationType::UnsafeMethodCall),
-            ("from_utf8_unchecked", UnsafeOperationType::UnsafeMethodCall),
-            ("from_utf8_unchecked_mut", UnsafeOperationType::UnsafeMethodCall),
-        ];
-        
-        // 1. 先检查完整的函数调用路径
-        for (keyword, op_type) in common_unsafe_funcs.iter() {
-            if func_path.contains(keyword) {
-                return Some(op_type.clone());
-            }
-        }
-        
-        // 2. 如果没有匹配到完整路径，检查路径的最后一部分
-        if let Some(last_part) = func_path.split("::").last() {
-            for (keyword, op_type) in common_unsafe_funcs.iter() {
-                if last_part == *keyword {
-                    return Some(op_type.clone());
-                }
-            }
-        }
-        
-        None
-    }
-
-    /// 检查完整或部分函数路径是否是已知的unsafe函数
-    pub fn is_known_unsafe_full_path(&self, segments: &[String]) -> bool {
-        if segments.is_empty() {
-            return false;
-        }
-        
-        // 单独函数名检测 - 无论在什么路径下，这些函数都被视为unsafe
-        let unsafe_function_names = [
-            "from_raw_parts",
-            "from_raw_parts_mut",
-            "copy_nonoverlapping",
-            "transmute",
-            "forget",
-            "offset",
-            "from_utf8_unchecked",
-            "from_utf8_unchecked_mut",
-            "drop_in_place",
-        ];
-        
-        // 检查最后的函数名是否是已知的unsafe函数
-        let last_segment = &segments[segments.len() - 1];
-        if unsafe_function_names.contains(&last_segment.as_str()) {
-            // 如果函数名是已知unsafe函数，再确认所在的上下文是否匹配
-            // 例如，如果函数名是"from_raw_parts"，我们需要检查它是否在slice模块中
-            
-            // from_raw_parts/from_raw_parts_mut: 检查是否来自slice模块
-            if (last_segment == "from_raw_parts" || last_segment == "from_raw_parts_mut") && segments.len() > 1 {
-                let prev_segment = &segments[segments.len() - 2];
-                // 如果前一个部分不是slice，可能是其他模块的同名函数，不一定是unsafe
-                if prev_segment != "slice" && prev_segment != "std" && prev_segment != "core" {
-                    // 但作为保守策略，我们仍然认为它是unsafe的
-                    return true;
-                }
-            }
+        if let Some(name) = &self.current_function
+            && let Some(func) = self.functions.get_mut(name) {
+            func.has_internal_unsafe = self.has_unsafe;
             
-            // copy/copy_nonoverlapping/read/write: 检查是否来自ptr模块
-            if (last_segment == "copy" || last_segment == "copy_nonoverlapping" || last_segment == "read" || last_segment == "write") && segments.len() > 1 {
-                let prev_segment = &segments[segments.len() - 2];
-                // 如果前一个部分不是ptr，可能是其他模块的同名函数，不一定是unsafe
-                if prev_segment != "ptr" && prev_segment != "std" && prev_segment != "core" {
-                    // 允许其他unsafe调用也能被检测到
-                    return true;
-                }
-            }
-            
-            // transmute/forget: 检查是否来自mem模块
-            if (last_segment == "transmute" || last_segment == "forget") && segments.len() > 1 {
-                let prev_segment = &segments[segments.len() - 2];
-                // 如果前一个部分不是mem，可能是其他模块的同名函数，不一定是unsafe
-                if prev_segment != "mem" && prev_segment != "std" && prev_segment != "core" {
-                    // 允许其他unsafe调用也能被检测到
-                    return true;
-                }
+            // 如果有unsafe块或调用，将函数添加到unsafe函数集合中
+            if self.has_unsafe || func.is_unsafe_fn {
+                self.unsafe_functions.insert(name.clone());
             }
             
-            // 保守策略：即使没有正确的上下文，仍然认为它可能是unsafe
-            return true;
+            // 更新函数的unsafe操作列表
+            func.unsafe_operations = self.current_unsafe_operations.clone();
         }
         
-        // 常见的unsafe函数完整路径
-        let known_unsafe_paths = [
-            vec!["core", "slice", "from_raw_parts"],
-            vec!["core", "slice", "from_raw_parts_mut"],
-            vec!["std", "slice", "from_raw_parts"],
-            vec!["std", "slice", "from_raw_parts_mut"],
-            vec!["slice", "from_raw_parts"],
-            vec!["slice", "from_raw_parts_mut"],
-            vec!["core", "ptr", "read"],
-            vec!["core", "ptr", "write"],
-            vec!["core", "ptr", "copy"],
-            vec!["core", "ptr", "copy_nonoverlapping"],
-            vec!["std", "ptr", "read"],
-            vec!["std", "ptr", "write"],
-            vec!["std", "ptr", "copy"],
-            vec!["std", "ptr", "copy_nonoverlapping"],
-            vec!["std", "mem", "transmute"],
-            vec!["core", "mem", "transmute"],
-            vec!["core", "mem", "forget"],
-            vec!["std", "mem", "forget"],
-        ];
-        
-        // 检查是否匹配任何已知的unsafe函数路径
-        for path in &known_unsafe_paths {
-            if path.len() <= segments.len() {
-                let start_idx = segments.len() - path.len();
-                let matching = segments[start_idx..].iter().zip(path.iter()).all(|(a, b)| a == b);
-                if matching {
-                    return true;
-                }
-            }
-        }
-        
-        false
+        // 重置当前unsafe操作列表
+        self.current_unsafe_operations.clear();
     }
 }
 
@@ -633,6 +904,10 @@ impl<'ast> Visit<'ast> for FunctionVisitor {
     fn visit_item_mod(&mut self, i: &'ast syn::ItemMod) {
         self.current_module_path.push(i.ident.to_string());
         
+        // 记录模块的可见性，用于判断其中的项能否从crate外部访问
+        let visibility = self.convert_visibility(&i.vis);
+        self.modules.insert(self.get_current_module_path(), visibility);
+        
         // Visit module contents
         if let Some((_, items)) = &i.content {
             for item in items {
@@ -646,33 +921,149 @@ impl<'ast> Visit<'ast> for FunctionVisitor {
     /// Visit function definition
     fn visit_item_fn(&mut self, i: &'ast ItemFn) {
         let name = i.sig.ident.to_string();
+        self.enter_function();
         self.add_function(name, &i.vis, i);
         
         // Visit function body
         visit::visit_block(self, &i.block);
         
-        // Update unsafe state
-        self.update_unsafe_state();
-        self.current_function = None;
+        // Update unsafe state, the enclosing function (if any) continues afterwards
+        self.leave_function();
     }
     
     /// Visit function in impl block
     fn visit_impl_item_fn(&mut self, i: &'ast ImplItemFn) {
         let name = i.sig.ident.to_string();
+        self.enter_function();
         self.add_impl_function(name, &i.vis, i);
         
         // Visit function body
         visit::visit_block(self, &i.block);
         
         // Update unsafe state
-        self.update_unsafe_state();
-        self.current_function = None;
+        self.leave_function();
+    }
+    
+    /// Visit trait definition, recording its methods and supertraits
+    fn visit_item_trait(&mut self, i: &'ast syn::ItemTrait) {
+        if i.unsafety.is_some() {
+            self.add_unsafe_trait(i);
+        }
+        
+        let name = i.ident.to_string();
+        let module_path = self.get_current_module_path();
+        let mut methods = Vec::new();
+        let mut default_methods = Vec::new();
+        for item in &i.items {
+            if let syn::TraitItem::Fn(method) = item {
+                methods.push(method.sig.ident.to_string());
+                if method.default.is_some() {
+                    default_methods.push(method.sig.ident.to_string());
+                }
+            }
+        }
+        let supertraits = i.supertraits.iter()
+            .filter_map(|bound| match bound {
+                syn::TypeParamBound::Trait(trait_bound) => Some(trait_bound.path.segments.iter()
+                    .map(|segment| segment.ident.to_string())
+                    .collect::<Vec<_>>()
+                    .join("::")),
+                _ => None,
+            })
+            .collect();
+        
+        let definition = TraitDefinition {
+            name: name.clone(),
+            module_path: module_path.clone(),
+            visibility: self.convert_visibility(&i.vis),
+            file_path: self.file_path.clone(),
+            methods,
+            default_methods,
+            supertraits,
+        };
+        self.traits.insert(scope::qualify(&module_path, &name), definition);
+        
+        let prev_trait = self.current_trait.replace(name);
+        visit::visit_item_trait(self, i);
+        self.current_trait = prev_trait;
+    }
+    
+    /// Visit trait method, only default implementations have a body to analyze
+    fn visit_trait_item_fn(&mut self, i: &'ast TraitItemFn) {
+        let (Some(trait_name), Some(block)) = (self.current_trait.clone(), &i.default) else {
+            return;
+        };
+        let vis = self.traits.get(&scope::qualify(&self.get_current_module_path(), &trait_name))
+            .map(|definition| definition.visibility.clone())
+            .unwrap_or(VisibilityKind::Module);
+        self.enter_function();
+        self.add_trait_function(&trait_name, vis, i, block);
+        
+        // Visit function body
+        visit::visit_block(self, block);
+        
+        // Update unsafe state
+        self.leave_function();
+    }
+    
+    /// Visit closure, a node of its own called by the enclosing node
+    fn visit_expr_closure(&mut self, i: &'ast syn::ExprClosure) {
+        let Some(parent) = self.current_function.clone() else {
+            visit::visit_expr_closure(self, i);
+            return;
+        };
+        
+        self.enter_function();
+        self.add_closure(parent, i);
+        visit::visit_expr_closure(self, i);
+        
+        // unsafe块中的闭包自身没有unsafe块，但其中的unsafe操作同样需要报告
+        if self.in_unsafe_block && !self.current_unsafe_operations.is_empty() {
+            self.has_unsafe = true;
+        }
+        self.leave_function();
+    }
+    
+    /// Visit const item, its initializer is a node of its own
+    fn visit_item_const(&mut self, i: &'ast syn::ItemConst) {
+        self.enter_function();
+        self.add_initializer(i.ident.to_string(), &i.vis, i.const_token.span, &i.ty, i, false);
+        visit::visit_item_const(self, i);
+        self.leave_function();
+    }
+    
+    /// Visit static item, its initializer is a node of its own
+    fn visit_item_static(&mut self, i: &'ast syn::ItemStatic) {
+        self.enter_function();
+        self.add_initializer(i.ident.to_string(), &i.vis, i.static_token.span, &i.ty, i, false);
+        // 对 static mut 的访问在构建调用图时按整个crate确认
+        if matches!(i.mutability, syn::StaticMutability::Mut(_))
+            && let Some(full_path) = &self.current_function {
+            self.mutable_statics.insert(full_path.clone());
+        }
+        visit::visit_item_static(self, i);
+        self.leave_function();
+    }
+    
+    /// Visit associated const in impl block
+    fn visit_impl_item_const(&mut self, i: &'ast syn::ImplItemConst) {
+        self.enter_function();
+        self.add_initializer(i.ident.to_string(), &i.vis, i.const_token.span, &i.ty, i, true);
+        visit::visit_impl_item_const(self, i);
+        self.leave_function();
     }
     
     /// Visit unsafe block
     fn visit_expr_unsafe(&mut self, i: &'ast ExprUnsafe) {
         self.has_unsafe = true;
         
+        // 记录unsafe块的位置
+        if let Some(current_fn) = &self.current_function
+            && let Some(func) = self.functions.get_mut(current_fn)
+            && let Some(span) = SourceSpan::from_span(i.span()) {
+            func.unsafe_blocks.push(span);
+        }
+        
         // 标记进入unsafe块
         let prev_in_unsafe = self.in_unsafe_block;
         self.in_unsafe_block = true;
@@ -687,10 +1078,25 @@ impl<'ast> Visit<'ast> for FunctionVisitor {
     /// Visit struct definition
     fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
         let name = i.ident.to_string();
-        self.add_type_definition(name, &i.vis, i);
+        self.add_type_definition(name.clone(), &i.vis, i);
+        self.set_field_types(&name, i.fields.iter(), false);
         visit::visit_item_struct(self, i);
     }
     
+    /// Visit extern block
+    fn visit_item_foreign_mod(&mut self, i: &'ast syn::ItemForeignMod) {
+        self.add_foreign_items(i);
+        visit::visit_item_foreign_mod(self, i);
+    }
+    
+    /// Visit union definition; reading its fields is an unsafe operation
+    fn visit_item_union(&mut self, i: &'ast syn::ItemUnion) {
+        let name = i.ident.to_string();
+        self.add_type_definition(name.clone(), &i.vis, i);
+        self.set_field_types(&name, i.fields.named.iter(), true);
+        visit::visit_item_union(self, i);
+    }
+    
     /// Visit enum definition
     fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
         let name = i.ident.to_string();
@@ -707,32 +1113,32 @@ impl<'ast> Visit<'ast> for FunctionVisitor {
 
     /// Visit impl block
     fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
-        // Extract type name for impl block
-        let type_name = match &*i.self_ty {
-            syn::Type::Path(type_path) => {
-                if let Some(segment) = type_path.path.segments.last() {
-                    segment.ident.to_string()
-                } else {
-                    // Skip if can't get type name
-                    return;
-                }
-            },
-            _ => return, // Not a path type, skip
+        // unsafe impl 由实现者保证trait要求的安全性，需要单独审查
+        if i.unsafety.is_some()
+            && let Some((_, trait_path, _)) = &i.trait_ {
+            self.add_unsafe_impl(i, trait_path);
+        }
+        
+        // Extract type name for impl block, skip if it has none (e.g. tuples)
+        let Some(impl_scope) = ImplScope::from_item_impl(i) else {
+            return;
         };
+        let type_name = impl_scope.self_type.clone();
         
-        // Set current impl type for use when processing methods
-        self.current_impl_type = Some(type_name.clone());
+        // Set current impl type for use when processing methods; impl blocks can be nested in function bodies
+        let prev_impl_type = self.current_impl_type.replace(type_name.clone());
+        let prev_impl = self.current_impl.replace(impl_scope);
         
         // Save complete impl block code
         let impl_code = i.to_token_stream().to_string();
         self.impl_blocks.entry(type_name.clone())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(impl_code.clone());
         
         // Check if it's a Default trait implementation
         let is_default_impl = if let Some((_, trait_path, _)) = &i.trait_ {
             trait_path.segments.last()
-                .map(|seg| seg.ident.to_string() == "Default")
+                .map(|seg| seg.ident == "Default")
                 .unwrap_or(false)
         } else {
             false
@@ -742,34 +1148,29 @@ impl<'ast> Visit<'ast> for FunctionVisitor {
         if is_default_impl {
             // Find matching type definition and add constructor
             for (path, def) in &mut self.type_definitions {
-                if let Some(def_name) = path.split("::").last() {
-                    if def_name == &type_name {
-                        def.constructors.push(impl_code.clone());
-                    }
+                if path.split("::").last() == Some(type_name.as_str()) {
+                    def.constructors.push(impl_code.clone());
                 }
             }
         } else {
             // For non-Default implementations, only extract constructor methods
             for item in &i.items {
-                if let syn::ImplItem::Fn(method) = item {
-                    if self.is_constructor(method, &type_name) {
-                        // Check if function is unsafe
-                        let is_unsafe = method.sig.unsafety.is_some();
+                if let syn::ImplItem::Fn(method) = item
+                    && self.is_constructor(method, &type_name) {
+                    // Check if function is unsafe
+                    let is_unsafe = method.sig.unsafety.is_some();
+                    
+                    // Only add safe constructors
+                    if !is_unsafe {
+                        // Only extract this constructor method
+                        let method_code = format!("impl {} {{\n    {}\n}}", 
+                            type_name, 
+                            method.to_token_stream());
                         
-                        // Only add safe constructors
-                        if !is_unsafe {
-                            // Only extract this constructor method
-                            let method_code = format!("impl {} {{\n    {}\n}}", 
-                                type_name, 
-                                method.to_token_stream().to_string());
-                            
-                            // Find matching type definition and add constructor
-                            for (path, def) in &mut self.type_definitions {
-                                if let Some(def_name) = path.split("::").last() {
-                                    if def_name == &type_name {
-                                        def.constructors.push(method_code.clone());
-                                    }
-                                }
+                        // Find matching type definition and add constructor
+                        for (path, def) in &mut self.type_definitions {
+                            if path.split("::").last() == Some(type_name.as_str()) {
+                                def.constructors.push(method_code.clone());
                             }
                         }
                     }
@@ -780,8 +1181,32 @@ impl<'ast> Visit<'ast> for FunctionVisitor {
         // Continue visiting impl block contents
         visit::visit_item_impl(self, i);
         
-        // Clear current impl type
-        self.current_impl_type = None;
+        // Restore the enclosing impl type
+        self.current_impl_type = prev_impl_type;
+        self.current_impl = prev_impl;
+    }
+    
+    /// 检测内联汇编：asm! 和 naked_asm! 记录在所在函数中，global_asm! 作为单独的声明
+    fn visit_macro(&mut self, i: &'ast syn::Macro) {
+        let name = i.path.segments.last().map(|segment| segment.ident.to_string());
+        match name.as_deref() {
+            Some("asm" | "naked_asm") if self.current_function.is_some() => {
+                // naked函数中的 naked_asm! 不在unsafe块内，但同样是unsafe代码
+                self.has_unsafe = true;
+                self.record_unsafe_operation(
+                    UnsafeOperationType::InlineAssembly,
+                    "内联汇编".to_string(),
+                    i.to_token_stream().to_string(),
+                    i.span(),
+                    Confidence::High,
+                    None
+                );
+            },
+            Some("global_asm") => self.add_global_asm(i),
+            _ => {},
+        }
+        
+        visit::visit_macro(self, i);
     }
     
     /// 检测裸指针解引用
@@ -798,7 +1223,10 @@ impl<'ast> Visit<'ast> for FunctionVisitor {
                         self.record_unsafe_operation(
                             UnsafeOperationType::RawPointerDereference,
                             "解引用裸指针".to_string(),
-                            expr_str
+                            expr_str,
+                            i.span(),
+                            Confidence::Low,
+                            None
                         );
                     }
                 }
@@ -811,42 +1239,34 @@ impl<'ast> Visit<'ast> for FunctionVisitor {
     
     /// 检测函数调用，可能是unsafe函数调用
     fn visit_expr_call(&mut self, i: &'ast ExprCall) {
-        if let Some(_current_function) = &self.current_function {
+        if self.current_function.is_some()
+            && let Expr::Path(path) = &*i.func {
             let code_snippet = i.to_token_stream().to_string();
+            let segments: Vec<String> = path.path.segments.iter()
+                .map(|seg| seg.ident.to_string())
+                .collect();
             
-            // 检查是否调用unsafe函数
-            if let Expr::Path(path) = &*i.func {
-                let path_str = path.to_token_stream().to_string();
-                
-                // 检查是否是完整路径的unsafe函数 (例如 core::slice::from_raw_parts)
-                let segments: Vec<String> = path.path.segments.iter()
-                    .map(|seg| seg.ident.to_string())
-                    .collect();
-                
-                if self.is_known_unsafe_full_path(&segments) {
-                    self.record_unsafe_operation(
-                        UnsafeOperationType::UnsafeFunctionCall,
-                        format!("调用unsafe函数: {}", path_str),
-                        code_snippet.clone()
-                    );
-                }
-                // 检查是否是已知的unsafe函数
-                else if self.is_known_unsafe_function(&path_str) {
-                    self.record_unsafe_operation(
-                        UnsafeOperationType::UnsafeFunctionCall,
-                        format!("调用unsafe函数: {}", path_str),
-                        code_snippet.clone()
-                    );
-                }
-                
-                // 检查是否是常见的unsafe操作
-                if let Some(op_type) = self.is_common_unsafe_operation(&path_str) {
-                    self.record_unsafe_operation(
-                        op_type,
-                        format!("调用unsafe操作: {}", path_str),
-                        code_snippet
-                    );
-                }
+            // 先按标准库unsafe API数据库匹配：完整路径或至少两段的后缀（例如 ptr::read）是确定的，
+            // 只写了函数名的调用（例如导入后的 transmute）是推测。unsafe函数只能在unsafe上下文中
+            // 调用，其他位置的同名函数（例如 crate 自己的 read）不是unsafe调用
+            let database = unsafe_apis::database();
+            let matched = self.in_unsafe_context().then(|| match database.function(&segments) {
+                Some(category) => Some((Some(category), Confidence::High)),
+                None if segments.len() == 1 => database.function_named(&segments[0]).map(|category| (Some(category), Confidence::Low)),
+                None => None,
+            }).flatten();
+            // 再检查配置文件中额外指定的unsafe函数，它们不限于unsafe上下文
+            let matched = matched.or_else(|| self.is_known_unsafe_function(&segments.join("::")).then_some((None, Confidence::High)));
+            
+            if let Some((category, confidence)) = matched {
+                self.record_unsafe_operation(
+                    UnsafeOperationType::UnsafeFunctionCall,
+                    format!("调用unsafe函数: {}", path.to_token_stream()),
+                    code_snippet,
+                    i.span(),
+                    confidence,
+                    category
+                );
             }
         }
         
@@ -856,25 +1276,20 @@ impl<'ast> Visit<'ast> for FunctionVisitor {
     
     /// 检测方法调用，可能是unsafe方法调用
     fn visit_expr_method_call(&mut self, i: &'ast ExprMethodCall) {
-        if let Some(current_function) = &self.current_function {
+        // unsafe方法只能在unsafe上下文中调用，其他位置的同名方法（例如 File::read、usize::add）不是unsafe调用
+        if self.current_function.is_some() && self.in_unsafe_context() {
             let method_name = i.method.to_string();
-            let code_snippet = i.to_token_stream().to_string();
             
-            // 检查是否是已知的unsafe方法
-            if self.has_unsafe_keywords(&method_name) {
+            // 接收者类型未知，只能按方法名匹配标准库中的unsafe方法或配置文件中的关键词
+            let category = unsafe_apis::database().method_named(&method_name);
+            if category.is_some() || self.has_unsafe_keywords(&method_name) {
                 self.record_unsafe_operation(
                     UnsafeOperationType::UnsafeMethodCall,
                     format!("调用unsafe方法: {}", method_name),
-                    code_snippet.clone()
-                );
-            }
-            
-            // 检查是否是常见的unsafe操作
-            if let Some(op_type) = self.is_common_unsafe_operation(&method_name) {
-                self.record_unsafe_operation(
-                    op_type,
-                    format!("调用unsafe操作: {}", method_name),
-                    code_snippet
+                    i.to_token_stream().to_string(),
+                    i.span(),
+                    Confidence::Low,
+                    category
                 );
             }
         }
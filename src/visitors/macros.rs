@@ -0,0 +1,603 @@
+use std::collections::HashMap;
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::ext::IdentExt;
+use syn::parse::discouraged::Speculative;
+use syn::parse::{ParseStream, Parser};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::visit_mut::{self, VisitMut};
+use syn::{Block, Expr, ImplItem, Item, Stmt};
+
+use crate::models::{MacroDefinition, MacroExpansion, SourceSpan};
+
+/// Expansions nested deeper than this are left unexpanded, e.g. recursive macros
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+/// Collect the `macro_rules!` definitions of a file, including those inside functions and modules
+pub fn collect_definitions(syntax: &syn::File) -> Vec<MacroDefinition> {
+    let mut collector = DefinitionCollector { definitions: Vec::new() };
+    collector.visit_file(syntax);
+    collector.definitions
+}
+
+struct DefinitionCollector {
+    definitions: Vec<MacroDefinition>,
+}
+
+impl<'ast> Visit<'ast> for DefinitionCollector {
+    fn visit_item_macro(&mut self, i: &'ast syn::ItemMacro) {
+        self.definitions.extend(definition(i));
+        syn::visit::visit_item_macro(self, i);
+    }
+}
+
+/// The definition of a `macro_rules!` item, None for other item macros
+pub fn definition(item: &syn::ItemMacro) -> Option<MacroDefinition> {
+    if !item.mac.path.is_ident("macro_rules") {
+        return None;
+    }
+    Some(MacroDefinition {
+        name: item.ident.as_ref()?.to_string(),
+        rules: item.mac.tokens.to_string(),
+    })
+}
+
+/// Best-effort expander of crate-local `macro_rules!` macros. Invocations in expression,
+/// statement, item and impl item position are replaced by their parsed expansion so that the
+/// visitors see the generated code; every generated token carries the span of the invocation.
+pub struct MacroExpander {
+    macros: HashMap<String, Vec<Rule>>,
+    pub expansions: Vec<MacroExpansion>, // 展开过的宏调用（只记录源码中直接出现的调用）
+    depth: usize,
+}
+
+impl MacroExpander {
+    /// Build an expander from macro definitions; later definitions of the same name win
+    pub fn new<'a>(definitions: impl IntoIterator<Item = &'a MacroDefinition>) -> Self {
+        let mut macros = HashMap::new();
+        for definition in definitions {
+            // 无法解析的规则（例如使用了不支持的语法）使整个宏保持不展开
+            if let Ok(tokens) = definition.rules.parse::<TokenStream>()
+                && let Some(rules) = parse_rules(tokens) {
+                macros.insert(definition.name.clone(), rules);
+            }
+        }
+        MacroExpander { macros, expansions: Vec::new(), depth: 0 }
+    }
+    
+    /// Expand the crate macros invoked in a file
+    pub fn expand_file(&mut self, syntax: &mut syn::File) {
+        if !self.macros.is_empty() {
+            self.visit_file_mut(syntax);
+        }
+    }
+    
+    /// Expand an invocation of a crate macro and parse the result, None when the macro is
+    /// unknown, no rule matches or the expansion does not parse as `T`
+    fn expand<T>(&mut self, mac: &syn::Macro, parser: impl Parser<Output = T>) -> Option<T> {
+        if self.depth >= MAX_EXPANSION_DEPTH {
+            return None;
+        }
+        let name = mac.path.segments.last()?.ident.to_string();
+        let rules = self.macros.get(&name)?;
+        let expanded = rules.iter().find_map(|rule| rule.apply(mac.tokens.clone()))?;
+        
+        let span = mac.span();
+        let parsed = parser.parse2(respan(expanded, span)).ok()?;
+        // 嵌套展开的代码已经带有最外层调用的位置
+        if self.depth == 0 {
+            self.expansions.push(MacroExpansion {
+                name: format!("{}!", name),
+                span: SourceSpan::from_span(span),
+            });
+        }
+        Some(parsed)
+    }
+    
+    /// Expand the item macros of an item list in place, then visit the items
+    fn expand_items(&mut self, items: &mut Vec<Item>) {
+        let mut expanded_items = Vec::with_capacity(items.len());
+        for mut item in std::mem::take(items) {
+            let expansion = match &item {
+                Item::Macro(item_macro) if item_macro.ident.is_none() => {
+                    self.expand(&item_macro.mac, |input: ParseStream| parse_all(input, |input| input.parse::<Item>()))
+                },
+                _ => None,
+            };
+            match expansion {
+                Some(mut generated) => {
+                    self.depth += 1;
+                    self.expand_items(&mut generated);
+                    self.depth -= 1;
+                    expanded_items.extend(generated);
+                },
+                None => {
+                    self.visit_item_mut(&mut item);
+                    expanded_items.push(item);
+                },
+            }
+        }
+        *items = expanded_items;
+    }
+}
+
+impl VisitMut for MacroExpander {
+    fn visit_file_mut(&mut self, i: &mut syn::File) {
+        self.expand_items(&mut i.items);
+    }
+    
+    fn visit_item_mod_mut(&mut self, i: &mut syn::ItemMod) {
+        if let Some((_, items)) = &mut i.content {
+            self.expand_items(items);
+        }
+    }
+    
+    fn visit_item_impl_mut(&mut self, i: &mut syn::ItemImpl) {
+        let mut items = Vec::with_capacity(i.items.len());
+        for mut item in std::mem::take(&mut i.items) {
+            let expansion = match &item {
+                ImplItem::Macro(impl_macro) => {
+                    self.expand(&impl_macro.mac, |input: ParseStream| parse_all(input, |input| input.parse::<ImplItem>()))
+                },
+                _ => None,
+            };
+            match expansion {
+                Some(generated) => {
+                    self.depth += 1;
+                    for mut generated_item in generated {
+                        self.visit_impl_item_mut(&mut generated_item);
+                        items.push(generated_item);
+                    }
+                    self.depth -= 1;
+                },
+                None => {
+                    self.visit_impl_item_mut(&mut item);
+                    items.push(item);
+                },
+            }
+        }
+        i.items = items;
+    }
+    
+    fn visit_block_mut(&mut self, i: &mut Block) {
+        let mut stmts = Vec::with_capacity(i.stmts.len());
+        for mut stmt in std::mem::take(&mut i.stmts) {
+            let expansion = match &stmt {
+                Stmt::Macro(stmt_macro) => self.expand(&stmt_macro.mac, Block::parse_within),
+                _ => None,
+            };
+            match expansion {
+                Some(generated) => {
+                    let mut block = Block { brace_token: i.brace_token, stmts: generated };
+                    self.depth += 1;
+                    self.visit_block_mut(&mut block);
+                    self.depth -= 1;
+                    stmts.extend(block.stmts);
+                },
+                None => {
+                    self.visit_stmt_mut(&mut stmt);
+                    stmts.push(stmt);
+                },
+            }
+        }
+        i.stmts = stmts;
+    }
+    
+    fn visit_expr_mut(&mut self, i: &mut Expr) {
+        // 代入的 expr 片段带有不可见分组，访问器不会穿过分组查看；运算的操作数加上括号以保持优先级
+        while let Expr::Group(group) = i {
+            *i = std::mem::replace(&mut *group.expr, Expr::Verbatim(TokenStream::new()));
+        }
+        parenthesize_operands(i);
+        
+        let expansion = match &*i {
+            Expr::Macro(expr_macro) => self.expand(&expr_macro.mac, |input: ParseStream| input.parse::<Expr>()),
+            _ => None,
+        };
+        match expansion {
+            Some(generated) => {
+                *i = generated;
+                self.depth += 1;
+                self.visit_expr_mut(i);
+                self.depth -= 1;
+            },
+            None => visit_mut::visit_expr_mut(self, i),
+        }
+    }
+}
+
+/// Turn the substituted fragments among the operands of an operator into parenthesized
+/// expressions unless they bind at least as tightly as any operator, e.g. `$a * 2` with
+/// `$a` = `x + 1`
+fn parenthesize_operands(expr: &mut Expr) {
+    let operands: Vec<&mut Expr> = match expr {
+        Expr::Unary(unary) => vec![&mut unary.expr],
+        Expr::Binary(binary) => vec![&mut binary.left, &mut binary.right],
+        Expr::Cast(cast) => vec![&mut cast.expr],
+        Expr::Reference(reference) => vec![&mut reference.expr],
+        Expr::MethodCall(call) => vec![&mut call.receiver],
+        Expr::Field(field) => vec![&mut field.base],
+        Expr::Index(index) => vec![&mut index.expr],
+        Expr::Call(call) => vec![&mut call.func],
+        Expr::Try(try_expr) => vec![&mut try_expr.expr],
+        Expr::Await(await_expr) => vec![&mut await_expr.base],
+        Expr::Range(range) => range.start.iter_mut().chain(range.end.iter_mut()).map(|operand| &mut **operand).collect(),
+        _ => Vec::new(),
+    };
+    for operand in operands {
+        let Expr::Group(group) = operand else {
+            continue;
+        };
+        let is_atomic = matches!(*group.expr, Expr::Path(_) | Expr::Lit(_) | Expr::Call(_) | Expr::MethodCall(_)
+            | Expr::Field(_) | Expr::Index(_) | Expr::Paren(_) | Expr::Tuple(_) | Expr::Array(_) | Expr::Macro(_)
+            | Expr::Struct(_) | Expr::Block(_) | Expr::Unsafe(_));
+        if !is_atomic {
+            *operand = Expr::Paren(syn::ExprParen {
+                attrs: Vec::new(),
+                paren_token: syn::token::Paren(group.group_token.span),
+                expr: std::mem::replace(&mut group.expr, Box::new(Expr::Verbatim(TokenStream::new()))),
+            });
+        }
+    }
+}
+
+/// Parse items until the input is exhausted
+fn parse_all<T>(input: ParseStream, parse: impl Fn(ParseStream) -> syn::Result<T>) -> syn::Result<Vec<T>> {
+    let mut items = Vec::new();
+    while !input.is_empty() {
+        items.push(parse(input)?);
+    }
+    Ok(items)
+}
+
+/// Give every token of a stream the same span
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens.into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => {
+                let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                TokenTree::Group(respanned)
+            },
+            mut other => {
+                other.set_span(span);
+                other
+            },
+        })
+        .collect()
+}
+
+/// One `(matcher) => { transcriber }` arm of a macro
+struct Rule {
+    matcher: Vec<Matcher>,
+    transcriber: Vec<Transcriber>,
+}
+
+enum Matcher {
+    Token(TokenTree),                                // 需要原样出现的标记
+    Group(Delimiter, Vec<Matcher>),
+    Fragment(String, String),                        // $name:kind
+    Repeat(Vec<Matcher>, Option<TokenTree>, char),   // $( ... ) 分隔符 重复方式
+}
+
+enum Transcriber {
+    Token(TokenTree),
+    Group(Delimiter, Vec<Transcriber>),
+    Variable(String),
+    Repeat(Vec<Transcriber>, Option<TokenTree>),
+}
+
+/// Value bound to a metavariable; repetitions bind one value per iteration
+#[derive(Clone)]
+enum Fragment {
+    Single(TokenStream),
+    Repeated(Vec<Fragment>),
+}
+
+type Bindings = HashMap<String, Fragment>;
+
+/// Parse the body of `macro_rules! name { ... }` into its rules
+fn parse_rules(tokens: TokenStream) -> Option<Vec<Rule>> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut rules = Vec::new();
+    let mut rest = tokens.as_slice();
+    while !rest.is_empty() {
+        let [TokenTree::Group(matcher), TokenTree::Punct(eq), TokenTree::Punct(gt), TokenTree::Group(transcriber), tail @ ..] = rest else {
+            return None;
+        };
+        if eq.as_char() != '=' || gt.as_char() != '>' {
+            return None;
+        }
+        rules.push(Rule {
+            matcher: parse_matcher(&matcher.stream().into_iter().collect::<Vec<_>>())?,
+            transcriber: parse_transcriber(&transcriber.stream().into_iter().collect::<Vec<_>>())?,
+        });
+        rest = match tail {
+            [TokenTree::Punct(semi), tail @ ..] if semi.as_char() == ';' => tail,
+            _ => tail,
+        };
+    }
+    Some(rules)
+}
+
+fn parse_matcher(tokens: &[TokenTree]) -> Option<Vec<Matcher>> {
+    let mut matchers = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            TokenTree::Punct(dollar) if dollar.as_char() == '$' => match tokens.get(i + 1) {
+                Some(TokenTree::Ident(name)) => {
+                    let (Some(TokenTree::Punct(colon)), Some(TokenTree::Ident(kind))) = (tokens.get(i + 2), tokens.get(i + 3)) else {
+                        return None;
+                    };
+                    if colon.as_char() != ':' {
+                        return None;
+                    }
+                    matchers.push(Matcher::Fragment(name.to_string(), kind.to_string()));
+                    i += 4;
+                },
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+                    let inner = parse_matcher(&group.stream().into_iter().collect::<Vec<_>>())?;
+                    let (separator, kind, consumed) = repetition_suffix(&tokens[i + 2..])?;
+                    matchers.push(Matcher::Repeat(inner, separator, kind));
+                    i += 2 + consumed;
+                },
+                _ => return None,
+            },
+            TokenTree::Group(group) => {
+                matchers.push(Matcher::Group(group.delimiter(), parse_matcher(&group.stream().into_iter().collect::<Vec<_>>())?));
+                i += 1;
+            },
+            token => {
+                matchers.push(Matcher::Token(token.clone()));
+                i += 1;
+            },
+        }
+    }
+    Some(matchers)
+}
+
+fn parse_transcriber(tokens: &[TokenTree]) -> Option<Vec<Transcriber>> {
+    let mut transcribers = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            TokenTree::Punct(dollar) if dollar.as_char() == '$' => match tokens.get(i + 1) {
+                // $crate 指向定义宏的crate，即被分析的crate
+                Some(TokenTree::Ident(name)) if name == "crate" => {
+                    transcribers.push(Transcriber::Token(TokenTree::Ident(Ident::new("crate", name.span()))));
+                    i += 2;
+                },
+                Some(TokenTree::Ident(name)) => {
+                    transcribers.push(Transcriber::Variable(name.to_string()));
+                    i += 2;
+                },
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+                    let inner = parse_transcriber(&group.stream().into_iter().collect::<Vec<_>>())?;
+                    let (separator, _, consumed) = repetition_suffix(&tokens[i + 2..])?;
+                    transcribers.push(Transcriber::Repeat(inner, separator));
+                    i += 2 + consumed;
+                },
+                _ => return None,
+            },
+            TokenTree::Group(group) => {
+                transcribers.push(Transcriber::Group(group.delimiter(), parse_transcriber(&group.stream().into_iter().collect::<Vec<_>>())?));
+                i += 1;
+            },
+            token => {
+                transcribers.push(Transcriber::Token(token.clone()));
+                i += 1;
+            },
+        }
+    }
+    Some(transcribers)
+}
+
+/// The optional separator and the `*`, `+` or `?` after `$( ... )`, with the number of tokens used
+fn repetition_suffix(tokens: &[TokenTree]) -> Option<(Option<TokenTree>, char, usize)> {
+    let kind_of = |token: &TokenTree| match token {
+        TokenTree::Punct(punct) if matches!(punct.as_char(), '*' | '+' | '?') => Some(punct.as_char()),
+        _ => None,
+    };
+    match tokens {
+        [first, ..] if kind_of(first).is_some() => Some((None, kind_of(first)?, 1)),
+        [separator, kind, ..] => Some((Some(separator.clone()), kind_of(kind)?, 2)),
+        _ => None,
+    }
+}
+
+impl Rule {
+    /// Expand the invocation arguments with this rule, None when they do not match
+    fn apply(&self, arguments: TokenStream) -> Option<TokenStream> {
+        let mut bindings = Bindings::new();
+        let parser = |input: ParseStream| {
+            match_sequence(input, &self.matcher, &mut bindings)?;
+            if !input.is_empty() {
+                return Err(input.error("unexpected tokens after macro arguments"));
+            }
+            Ok(())
+        };
+        parser.parse2(arguments).ok()?;
+        
+        let mut output = TokenStream::new();
+        transcribe(&self.transcriber, &bindings, &mut output)?;
+        Some(output)
+    }
+}
+
+fn match_sequence(input: ParseStream, matchers: &[Matcher], bindings: &mut Bindings) -> syn::Result<()> {
+    for matcher in matchers {
+        match matcher {
+            Matcher::Token(expected) => {
+                let token: TokenTree = input.parse()?;
+                if !same_token(&token, expected) {
+                    return Err(syn::Error::new(token.span(), "unexpected token"));
+                }
+            },
+            Matcher::Group(delimiter, inner) => {
+                let TokenTree::Group(group) = input.parse::<TokenTree>()? else {
+                    return Err(input.error("expected a delimited group"));
+                };
+                if group.delimiter() != *delimiter {
+                    return Err(syn::Error::new(group.span(), "unexpected delimiter"));
+                }
+                let parser = |input: ParseStream| {
+                    match_sequence(input, inner, bindings)?;
+                    if !input.is_empty() {
+                        return Err(input.error("unexpected tokens in group"));
+                    }
+                    Ok(())
+                };
+                parser.parse2(group.stream())?;
+            },
+            Matcher::Fragment(name, kind) => {
+                let fragment = parse_fragment(input, kind)?;
+                bindings.insert(name.clone(), Fragment::Single(fragment));
+            },
+            Matcher::Repeat(inner, separator, kind) => {
+                let mut iterations: Vec<Bindings> = Vec::new();
+                loop {
+                    let fork = input.fork();
+                    if !iterations.is_empty()
+                        && let Some(separator) = separator {
+                        match fork.parse::<TokenTree>() {
+                            Ok(token) if same_token(&token, separator) => {},
+                            _ => break,
+                        }
+                    }
+                    let mut iteration = Bindings::new();
+                    if match_sequence(&fork, inner, &mut iteration).is_err() || fork.cursor() == input.cursor() {
+                        break;
+                    }
+                    input.advance_to(&fork);
+                    iterations.push(iteration);
+                    if *kind == '?' {
+                        break;
+                    }
+                }
+                if *kind == '+' && iterations.is_empty() {
+                    return Err(input.error("expected at least one repetition"));
+                }
+                
+                let mut names = Vec::new();
+                variable_names(inner, &mut names);
+                for name in names {
+                    let values = iterations.iter_mut()
+                        .map(|iteration| iteration.remove(&name).unwrap_or(Fragment::Repeated(Vec::new())))
+                        .collect();
+                    bindings.insert(name, Fragment::Repeated(values));
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Parse one fragment of the given kind, e.g. `expr` or `ident`
+fn parse_fragment(input: ParseStream, kind: &str) -> syn::Result<TokenStream> {
+    let tokens = match kind {
+        // 表达式片段作为整体代入，不受展开处运算符优先级的影响
+        "expr" | "expr_2021" => {
+            let expr: Expr = input.parse()?;
+            TokenTree::Group(Group::new(Delimiter::None, expr.into_token_stream())).into()
+        },
+        "ident" => input.call(Ident::parse_any)?.into_token_stream(),
+        "ty" => input.parse::<syn::Type>()?.into_token_stream(),
+        "path" => input.parse::<syn::Path>()?.into_token_stream(),
+        "pat" => syn::Pat::parse_multi_with_leading_vert(input)?.into_token_stream(),
+        "pat_param" => syn::Pat::parse_single(input)?.into_token_stream(),
+        "block" => input.parse::<Block>()?.into_token_stream(),
+        "stmt" => input.parse::<Stmt>()?.into_token_stream(),
+        "item" => input.parse::<Item>()?.into_token_stream(),
+        "literal" => {
+            let mut tokens = TokenStream::new();
+            if input.peek(syn::Token![-]) {
+                input.parse::<syn::Token![-]>()?.to_tokens(&mut tokens);
+            }
+            input.parse::<syn::Lit>()?.to_tokens(&mut tokens);
+            tokens
+        },
+        "lifetime" => input.parse::<syn::Lifetime>()?.into_token_stream(),
+        "meta" => input.parse::<syn::Meta>()?.into_token_stream(),
+        "vis" => input.parse::<syn::Visibility>()?.into_token_stream(),
+        "tt" => input.parse::<TokenTree>()?.into(),
+        _ => return Err(input.error(format!("unsupported fragment kind `{}`", kind))),
+    };
+    Ok(tokens)
+}
+
+fn same_token(actual: &TokenTree, expected: &TokenTree) -> bool {
+    match (actual, expected) {
+        (TokenTree::Punct(a), TokenTree::Punct(b)) => a.as_char() == b.as_char(),
+        (TokenTree::Ident(a), TokenTree::Ident(b)) => a == b,
+        (TokenTree::Literal(a), TokenTree::Literal(b)) => a.to_string() == b.to_string(),
+        _ => false,
+    }
+}
+
+/// Names of the metavariables bound by a matcher, including nested repetitions
+fn variable_names(matchers: &[Matcher], names: &mut Vec<String>) {
+    for matcher in matchers {
+        match matcher {
+            Matcher::Fragment(name, _) => names.push(name.clone()),
+            Matcher::Group(_, inner) | Matcher::Repeat(inner, _, _) => variable_names(inner, names),
+            Matcher::Token(_) => {},
+        }
+    }
+}
+
+/// Names of the metavariables used by a transcriber
+fn used_variables(transcribers: &[Transcriber], names: &mut Vec<String>) {
+    for transcriber in transcribers {
+        match transcriber {
+            Transcriber::Variable(name) => names.push(name.clone()),
+            Transcriber::Group(_, inner) | Transcriber::Repeat(inner, _) => used_variables(inner, names),
+            Transcriber::Token(_) => {},
+        }
+    }
+}
+
+fn transcribe(transcribers: &[Transcriber], bindings: &Bindings, output: &mut TokenStream) -> Option<()> {
+    for transcriber in transcribers {
+        match transcriber {
+            Transcriber::Token(token) => output.extend(std::iter::once(token.clone())),
+            Transcriber::Group(delimiter, inner) => {
+                let mut stream = TokenStream::new();
+                transcribe(inner, bindings, &mut stream)?;
+                output.extend(std::iter::once(TokenTree::Group(Group::new(*delimiter, stream))));
+            },
+            Transcriber::Variable(name) => match bindings.get(name)? {
+                Fragment::Single(tokens) => output.extend(tokens.clone()),
+                Fragment::Repeated(_) => return None,
+            },
+            Transcriber::Repeat(inner, separator) => {
+                // 重复次数由其中用到的、在当前层重复的变量决定
+                let mut names = Vec::new();
+                used_variables(inner, &mut names);
+                let repeated: Vec<(&String, &Vec<Fragment>)> = names.iter()
+                    .filter_map(|name| match bindings.get(name) {
+                        Some(Fragment::Repeated(values)) => Some((name, values)),
+                        _ => None,
+                    })
+                    .collect();
+                let count = repeated.first()?.1.len();
+                if repeated.iter().any(|(_, values)| values.len() != count) {
+                    return None;
+                }
+                
+                for index in 0..count {
+                    if index > 0 && let Some(separator) = separator {
+                        output.extend(std::iter::once(separator.clone()));
+                    }
+                    let mut iteration = bindings.clone();
+                    for (name, values) in &repeated {
+                        iteration.insert((*name).clone(), values[index].clone());
+                    }
+                    transcribe(inner, &iteration, output)?;
+                }
+            },
+        }
+    }
+    Some(())
+}
@@ -1,5 +1,10 @@
 pub mod function;
 pub mod call;
+pub mod scope;
+pub mod macros;
+pub mod cfg;
 
 pub use function::FunctionVisitor;
-pub use call::CallVisitor;
\ No newline at end of file
+pub use call::CallVisitor;
+pub use scope::ImplScope;
+pub use macros::MacroExpander;
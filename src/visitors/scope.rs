@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+use quote::ToTokens;
+use syn::{ImplItem, ItemImpl, Type};
+
+/// The impl block a visitor is currently inside, shared by `FunctionVisitor` and
+/// `CallVisitor` so that both build the same keys for methods
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImplScope {
+    pub self_type: String,            // Self类型的名称（最后一段，不含泛型参数）
+    pub trait_name: Option<String>,   // trait impl的trait名称（最后一段）
+    pub methods: HashSet<String>,     // impl块中定义的方法名
+}
+
+impl ImplScope {
+    /// Describe an impl block; None when the Self type has no usable name (e.g. tuples)
+    pub fn from_item_impl(item: &ItemImpl) -> Option<Self> {
+        let self_type = impl_type_name(&item.self_ty)?;
+        let trait_name = item.trait_.as_ref()
+            .and_then(|(_, path, _)| path.segments.last())
+            .map(|segment| segment.ident.to_string());
+        let methods = item.items.iter()
+            .filter_map(|item| match item {
+                ImplItem::Fn(method) => Some(method.sig.ident.to_string()),
+                _ => None,
+            })
+            .collect();
+        
+        Some(ImplScope {
+            self_type,
+            trait_name,
+            methods,
+        })
+    }
+    
+    /// Owner segment of the methods of this impl: `Type` or `<Type as Trait>`
+    pub fn owner_segment(&self) -> String {
+        match &self.trait_name {
+            Some(trait_name) => format!("<{} as {}>", self.self_type, trait_name),
+            None => self.self_type.clone(),
+        }
+    }
+    
+    /// Key of a method defined in this impl block, e.g. `crate::foo::Bar::new`
+    pub fn method_key(&self, module_path: &str, method: &str) -> String {
+        qualify(module_path, &format!("{}::{}", self.owner_segment(), method))
+    }
+    
+    /// Key for `Self::method` / `self.method()` inside this impl block. Methods of the same
+    /// trait impl keep the trait qualification, anything else is an inherent method.
+    pub fn self_method_key(&self, module_path: &str, method: &str) -> String {
+        if self.methods.contains(method) {
+            self.method_key(module_path, method)
+        } else {
+            qualify(module_path, &format!("{}::{}", self.self_type, method))
+        }
+    }
+}
+
+/// Name of an impl's Self type: the last path segment, looking through references,
+/// e.g. `Foo` for `Foo<T>`, `&'a Foo` and `crate::foo::Foo`
+pub fn impl_type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last().map(|segment| segment.ident.to_string()),
+        Type::Reference(type_ref) => impl_type_name(&type_ref.elem),
+        Type::Paren(paren) => impl_type_name(&paren.elem),
+        Type::Group(group) => impl_type_name(&group.elem),
+        _ => None,
+    }
+}
+
+/// Join a module path and an item name, leaving the name alone at the root of a single file
+pub fn qualify(module_path: &str, name: &str) -> String {
+    if module_path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}::{}", module_path, name)
+    }
+}
+
+/// Key of a function, const or static item. Items inside a function body (or a closure or
+/// initializer) are qualified with that enclosing node, e.g. `crate::foo::outer::inner`.
+pub fn item_key(enclosing: Option<&str>, module_path: &str, name: &str) -> String {
+    match enclosing {
+        Some(parent) => format!("{}::{}", parent, name),
+        None => qualify(module_path, name),
+    }
+}
+
+/// Key of the `index`-th closure (in source order) directly inside a node, e.g. `crate::foo::run::{closure#0}`
+pub fn closure_key(parent: &str, index: usize) -> String {
+    format!("{}::{{closure#{}}}", parent, index)
+}
+
+/// Whether a node key was built by `closure_key`
+pub fn is_closure_key(path: &str) -> bool {
+    path.rsplit("::").next().is_some_and(|name| name.starts_with("{closure#"))
+}
+
+/// Source text of a type or bound without the spaces `quote` puts between tokens, e.g. `Wrapper<T>`
+pub fn compact_tokens<T: ToTokens>(tokens: &T) -> String {
+    let mut text = tokens.to_token_stream().to_string();
+    for (from, to) in [(" < ", "<"), ("< ", "<"), (" <", "<"), (" >", ">"), (" ,", ","), (" :: ", "::"), (":: ", "::"), ("& ", "&")] {
+        text = text.replace(from, to);
+    }
+    text
+}
+
+/// Written path of the type that methods are looked up on, e.g. `a::Foo` for `&mut a::Foo<T>`.
+/// Smart pointers and wrappers (`Box<Foo>`, `Rc<Foo>`, `Option<Foo>`, ...) stand for the wrapped
+/// type, since their methods are either auto-dereferenced or unwrapped before a call. Raw
+/// pointers, slices and arrays give the keys of the unsafe API database: `*const T`, `*mut T`
+/// and `[T]`.
+pub fn principal_type(ty: &Type, self_type: Option<&str>) -> Option<String> {
+    match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            let last = type_path.path.segments.last()?;
+            let name = last.ident.to_string();
+            
+            if name == "Self" && type_path.path.segments.len() == 1 {
+                return self_type.map(String::from);
+            }
+            
+            if is_transparent_wrapper(&name)
+                && let syn::PathArguments::AngleBracketed(args) = &last.arguments
+                && let Some(inner) = args.args.iter().find_map(|arg| match arg {
+                    syn::GenericArgument::Type(inner) => Some(inner),
+                    _ => None,
+                }) {
+                return principal_type(inner, self_type);
+            }
+            
+            Some(type_path.path.segments.iter()
+                .map(|segment| segment.ident.to_string())
+                .collect::<Vec<_>>()
+                .join("::"))
+        },
+        Type::Reference(type_ref) => principal_type(&type_ref.elem, self_type),
+        Type::Paren(paren) => principal_type(&paren.elem, self_type),
+        Type::Group(group) => principal_type(&group.elem, self_type),
+        Type::Ptr(pointer) if pointer.mutability.is_some() => Some("*mut T".to_string()),
+        Type::Ptr(_) => Some("*const T".to_string()),
+        // 数组调用方法时按切片处理
+        Type::Slice(_) | Type::Array(_) => Some("[T]".to_string()),
+        _ => None,
+    }
+}
+
+/// Wrapper types whose methods are called on the wrapped value after deref or unwrapping
+pub fn is_transparent_wrapper(name: &str) -> bool {
+    matches!(name, "Box" | "Rc" | "Arc" | "Option" | "Result" | "Cell" | "RefCell" | "Mutex" | "RwLock" | "Pin" | "ManuallyDrop")
+}
+
+/// Methods that give back (a reference to or guard of) the receiver's own type
+pub fn is_transparent_method(name: &str) -> bool {
+    matches!(name,
+        "unwrap" | "expect" | "unwrap_unchecked" | "clone" | "as_ref" | "as_mut" | "borrow" | "borrow_mut" |
+        "lock" | "read" | "write" | "get_mut" | "deref" | "deref_mut" | "as_deref" | "as_deref_mut" | "to_owned"
+    )
+}
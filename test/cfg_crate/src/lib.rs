@@ -0,0 +1,84 @@
+// 条件编译示例：默认按运行分析器的平台和启用的特性删除不编译的代码，
+// --target、--features、--cfg 选择其他配置；--all-cfgs 分析所有配置的代码，
+// 并标注每个发现存在的 #[cfg] 条件
+#[cfg(feature = "simd")]
+mod simd;
+
+unsafe extern "C" {
+    #[cfg(unix)]
+    #[link_name = "sysconf"]
+    fn sysconf(name: i32) -> i64;
+    
+    #[cfg(windows)]
+    #[link_name = "GetSystemInfo"]
+    fn get_system_info(info: *mut SystemInfo);
+}
+
+#[derive(Default)]
+#[repr(C)]
+pub struct SystemInfo {
+    pub page_size: u32,
+}
+
+// 同一函数在不同平台上的两个实现
+#[cfg(unix)]
+fn page_size() -> usize {
+    unsafe { sysconf(30) as usize }
+}
+
+#[cfg(windows)]
+fn page_size() -> usize {
+    let mut info = SystemInfo::default();
+    unsafe { get_system_info(&mut info) };
+    info.page_size as usize
+}
+
+pub fn aligned_len(len: usize) -> usize {
+    let page = page_size();
+    len.div_ceil(page) * page
+}
+
+#[cfg(target_os = "linux")]
+pub fn huge_page_size() -> usize {
+    unsafe { sysconf(84) as usize }
+}
+
+// 只有启用 simd 特性时才包含unsafe代码
+pub fn checksum(data: &[u8]) -> u32 {
+    #[cfg(feature = "simd")]
+    let sum = unsafe { simd::checksum_wide(data.as_ptr(), data.len()) };
+    #[cfg(not(feature = "simd"))]
+    let sum = data.iter().map(|&b| b as u32).sum();
+    sum
+}
+
+pub struct Buffer {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Buffer {
+    #[cfg_attr(not(debug_assertions), inline)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    
+    // release构建时省略边界检查
+    #[cfg(not(debug_assertions))]
+    pub fn get(&self, index: usize) -> u8 {
+        unsafe { *self.ptr.add(index) }
+    }
+    
+    #[cfg(debug_assertions)]
+    pub fn get(&self, index: usize) -> u8 {
+        assert!(index < self.len);
+        unsafe { self.ptr.add(index).read() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    pub fn zeroed_header() -> [u8; 4] {
+        unsafe { std::mem::zeroed() }
+    }
+}
@@ -0,0 +1,8 @@
+// 只有启用 simd 特性时才编译的模块
+pub unsafe fn checksum_wide(ptr: *const u8, len: usize) -> u32 {
+    let mut sum = 0u32;
+    for i in 0..len {
+        sum = sum.wrapping_add(unsafe { *ptr.add(i) } as u32);
+    }
+    sum
+}
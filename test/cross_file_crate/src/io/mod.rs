@@ -0,0 +1,8 @@
+// 私有模块中的辅助函数，通过crate路径调用其他文件中的unsafe代码
+
+pub(crate) fn copy_bytes(src: &[u8], dst: &mut [u8]) {
+    let len = src.len().min(dst.len());
+    unsafe {
+        std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), len);
+    }
+}
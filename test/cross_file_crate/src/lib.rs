@@ -0,0 +1,19 @@
+// 测试用例：跨文件调用，公共接口在lib.rs中，unsafe代码在其他文件的私有函数中
+
+mod raw;
+mod io;
+
+// 例子1：公共函数调用raw.rs中的私有unsafe函数（应该被识别）
+pub fn first_byte(data: &[u8]) -> u8 {
+    raw::read_first(data)
+}
+
+// 例子2：公共函数调用io/mod.rs中的函数（应该被识别）
+pub fn copy_all(src: &[u8], dst: &mut [u8]) {
+    io::copy_bytes(src, dst);
+}
+
+// 例子3：完全安全的公共函数（不应该被识别）
+pub fn length(data: &[u8]) -> usize {
+    data.len()
+}
@@ -0,0 +1,6 @@
+// 私有辅助函数，内部包含unsafe操作
+
+pub(crate) fn read_first(data: &[u8]) -> u8 {
+    let ptr = data.as_ptr();
+    unsafe { *ptr }
+}
@@ -0,0 +1,62 @@
+#![feature(prelude_import)]
+#[prelude_import]
+use std::prelude::rust_2021::*;
+#[macro_use]
+extern crate std;
+mod view {
+    use raw_view::RawView;
+    #[repr(C)]
+    pub struct Header {
+        pub magic: u32,
+        pub len: u32,
+    }
+    #[automatically_derived]
+    impl ::core::clone::Clone for Header {
+        #[inline]
+        fn clone(&self) -> Header {
+            let _: ::core::clone::AssertParamIsClone<u32>;
+            *self
+        }
+    }
+    #[automatically_derived]
+    impl ::core::marker::Copy for Header {}
+    impl Header {
+        pub fn from_bytes(bytes: &[u8]) -> Self {
+            if !(bytes.len() >= ::core::mem::size_of::<Self>()) {
+                ::core::panicking::panic(
+                    "assertion failed: bytes.len() >= ::core::mem::size_of::<Self>()",
+                )
+            }
+            unsafe { ::core::ptr::read_unaligned(bytes.as_ptr() as *const Self) }
+        }
+    }
+    unsafe impl ::raw_view::Pod for Header {}
+    pub struct Bytes {
+        data: Vec<u8>,
+    }
+    impl Bytes {
+        pub fn new(data: Vec<u8>) -> Self {
+            Bytes { data }
+        }
+        pub fn header(&self) -> Header {
+            Header::from_bytes(&self.data)
+        }
+        pub fn get(&self, index: usize) -> u8 {
+            if !(index < self.data.len()) {
+                ::core::panicking::panic("assertion failed: index < self.data.len()")
+            }
+            unsafe { *self.data.get_unchecked(index) }
+        }
+    }
+}
+pub use view::{Bytes, Header};
+pub fn parse(data: &[u8]) -> u32 {
+    Header::from_bytes(data).len
+}
+pub fn sum(data: &[u8]) -> u32 {
+    let mut total = 0;
+    for i in 0..data.len() {
+        total += unsafe { *data.get_unchecked(i) } as u32;
+    }
+    total
+}
@@ -0,0 +1,20 @@
+// 预展开输入示例：expanded.rs 是本crate经 cargo expand 展开后的源码，raw_view 是一个过程宏crate。
+// 用 --expanded 分析 expanded.rs 时，derive和属性宏生成的unsafe代码也会被报告，
+// 结果按项的路径映射回 src/ 中的原始文件；宏生成的函数归到其类型或模块所在的文件
+mod view;
+
+pub use view::{Bytes, Header};
+
+pub fn parse(data: &[u8]) -> u32 {
+    Header::from_bytes(data).len
+}
+
+// 属性宏去掉循环中的边界检查
+#[raw_view::no_bounds_check]
+pub fn sum(data: &[u8]) -> u32 {
+    let mut total = 0;
+    for i in 0..data.len() {
+        total += data[i] as u32;
+    }
+    total
+}
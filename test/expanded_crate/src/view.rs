@@ -0,0 +1,28 @@
+// RawView 派生宏生成 from_bytes（按字节读取结构体）和 unsafe impl Pod
+use raw_view::RawView;
+
+#[derive(Clone, Copy, RawView)]
+#[repr(C)]
+pub struct Header {
+    pub magic: u32,
+    pub len: u32,
+}
+
+pub struct Bytes {
+    data: Vec<u8>,
+}
+
+impl Bytes {
+    pub fn new(data: Vec<u8>) -> Self {
+        Bytes { data }
+    }
+
+    pub fn header(&self) -> Header {
+        Header::from_bytes(&self.data)
+    }
+
+    pub fn get(&self, index: usize) -> u8 {
+        assert!(index < self.data.len());
+        unsafe { *self.data.get_unchecked(index) }
+    }
+}
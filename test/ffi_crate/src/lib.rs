@@ -0,0 +1,43 @@
+// FFI示例：经由导入调用 sys.rs 中声明的外部函数，读取外部静态变量；
+// 私有模块中的 #[no_mangle] extern "C" 函数也是外部代码可以调用的入口点
+mod sys;
+
+use std::ffi::CStr;
+
+pub fn absolute(value: i32) -> i32 {
+    unsafe { sys::abs(value) }
+}
+
+pub fn length(text: &CStr) -> usize {
+    measure(text)
+}
+
+fn measure(text: &CStr) -> usize {
+    use sys::c_strlen;
+    unsafe { c_strlen(text.as_ptr()) }
+}
+
+pub fn last_error() -> i32 {
+    unsafe { sys::errno_value }
+}
+
+mod exports {
+    use crate::sys;
+
+    #[no_mangle]
+    pub extern "C" fn ffi_absolute(value: i32) -> i32 {
+        checked_abs(value)
+    }
+
+    fn checked_abs(value: i32) -> i32 {
+        if value == i32::MIN {
+            return i32::MAX;
+        }
+        unsafe { sys::abs(value) }
+    }
+
+    #[unsafe(export_name = "ffi_abs_raw")]
+    pub unsafe extern "C" fn raw_absolute(value: i32) -> i32 {
+        sys::abs(value)
+    }
+}
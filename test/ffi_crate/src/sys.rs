@@ -0,0 +1,9 @@
+// extern 块：strlen 通过 #[link_name] 链接到其他符号，errno_value 是外部静态变量
+use std::os::raw::c_char;
+
+extern "C" {
+    pub fn abs(value: i32) -> i32;
+    #[link_name = "strlen"]
+    pub fn c_strlen(text: *const c_char) -> usize;
+    pub static errno_value: i32;
+}
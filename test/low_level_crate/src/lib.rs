@@ -0,0 +1,50 @@
+// 内联汇编、联合体字段和 static mut 示例：联合体 Bits 和计数器 COUNTER 定义在 regs.rs 中，
+// 写联合体字段和只读取局部变量的unsafe块不是unsafe操作
+mod regs;
+
+use std::arch::global_asm;
+use std::ptr::addr_of_mut;
+
+use regs::{Bits, COUNTER};
+
+global_asm!(".globl low_level_marker", "low_level_marker:", "ret");
+
+pub fn float_bits(value: f32) -> u32 {
+    let bits = Bits { float: value };
+    unsafe { bits.int }
+}
+
+pub fn set_float(value: f32) -> Bits {
+    let mut bits = Bits { int: 0 };
+    bits.float = value;
+    bits
+}
+
+pub fn next_id() -> usize {
+    bump()
+}
+
+fn bump() -> usize {
+    unsafe {
+        COUNTER += 1;
+        COUNTER
+    }
+}
+
+pub fn counter_ptr() -> *mut usize {
+    unsafe { addr_of_mut!(COUNTER) }
+}
+
+pub fn read_tsc() -> u64 {
+    let low: u32;
+    let high: u32;
+    unsafe {
+        std::arch::asm!("rdtsc", out("eax") low, out("edx") high);
+    }
+    ((high as u64) << 32) | low as u64
+}
+
+pub fn local_only(values: &[u8]) -> u8 {
+    let first = values[0];
+    unsafe { first }
+}
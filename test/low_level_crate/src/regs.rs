@@ -0,0 +1,8 @@
+// 在另一个文件中定义的联合体和可变静态变量
+#[repr(C)]
+pub union Bits {
+    pub int: u32,
+    pub float: f32,
+}
+
+pub static mut COUNTER: usize = 0;
@@ -0,0 +1,57 @@
+// 声明宏示例：本crate的 macro_rules! 宏被展开后再分析，
+// 宏生成的unsafe块、函数和调用都会被检测，报告中标注操作来自哪个宏调用。
+// 宏按文本顺序可见：mod 声明之前定义的宏在子模块文件中同样会被展开，
+// #[macro_use] 模块中定义的宏在声明之后可用
+#[macro_use]
+mod unaligned;
+
+macro_rules! read_at {
+    ($ptr:expr, $index:expr) => {
+        unsafe { *$ptr.add($index) }
+    };
+}
+
+mod ring;
+
+pub use ring::Ring;
+
+// 为每种整数类型生成一个读取函数
+macro_rules! readers {
+    ($($name:ident: $ty:ty),* $(,)?) => {
+        $(
+            pub fn $name(values: &[$ty], index: usize) -> $ty {
+                read_at!(values.as_ptr(), index)
+            }
+        )*
+    };
+}
+
+readers! {
+    read_u8: u8,
+    read_u32: u32,
+}
+
+unsafe fn clear(ptr: *mut u8, len: usize) {
+    unsafe { std::ptr::write_bytes(ptr, 0, len) }
+}
+
+macro_rules! zero {
+    ($buffer:expr) => {
+        unsafe { clear($buffer.as_mut_ptr(), $buffer.len()) }
+    };
+}
+
+pub fn reset(buffer: &mut [u8]) {
+    zero!(buffer);
+}
+
+pub fn first(values: &[u64]) -> u64 {
+    if values.is_empty() {
+        return 0;
+    }
+    read_at!(values.as_ptr(), 0)
+}
+
+pub fn header(bytes: &[u8; 4]) -> u32 {
+    read_u32!(bytes.as_ptr())
+}
@@ -0,0 +1,28 @@
+// 子模块中使用 lib.rs 定义的宏，impl块中的宏调用生成方法
+pub struct Ring {
+    slots: Vec<u32>,
+    head: usize,
+}
+
+macro_rules! accessor {
+    ($name:ident, $offset:expr) => {
+        pub fn $name(&self) -> u32 {
+            read_at!(self.slots.as_ptr(), (self.head + $offset) % self.slots.len())
+        }
+    };
+}
+
+impl Ring {
+    pub fn new(capacity: usize) -> Self {
+        Ring { slots: vec![0; capacity.max(1)], head: 0 }
+    }
+
+    accessor!(front, 0);
+    accessor!(second, 1);
+
+    pub fn push(&mut self, value: u32) {
+        let index = self.head;
+        self.slots[index] = value;
+        self.head = (self.head + 1) % self.slots.len();
+    }
+}
@@ -0,0 +1,6 @@
+// 带有 #[macro_use] 声明的模块，其中的宏在 lib.rs 中声明之后的代码里展开
+macro_rules! read_u32 {
+    ($ptr:expr) => {
+        unsafe { std::ptr::read_unaligned($ptr as *const u32) }
+    };
+}
@@ -0,0 +1 @@
+pub(crate) fn helper(p: *const u8) -> u8 { unsafe { *p.add(2) } }
@@ -0,0 +1,2 @@
+pub(crate) mod deep;
+pub(crate) fn helper(p: *const u8) -> u8 { unsafe { *p.add(1) } }
@@ -0,0 +1,14 @@
+// 模块树示例：mod.rs、foo.rs、#[path] 和内联模块中的 mod 声明，
+// 五个同名的 helper 函数应分别解析为不同的完整路径
+mod a;
+mod b;
+#[path = "platform/unix_impl.rs"]
+mod sys;
+pub mod outer {
+    pub(crate) mod nested;
+}
+pub fn call_a(p: *const u8) -> u8 { a::helper(p) }
+pub fn call_b(p: *const u8) -> u8 { b::helper(p) }
+pub fn call_sys(p: *const u8) -> u8 { sys::helper(p) }
+pub fn call_nested(p: *const u8) -> u8 { outer::nested::helper(p) }
+pub fn call_deep(p: *const u8) -> u8 { b::deep::helper(p) }
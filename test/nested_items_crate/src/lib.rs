@@ -0,0 +1,33 @@
+// 函数体之外的unsafe代码示例：闭包、嵌套函数、trait默认方法以及const/static的初始化表达式
+// 都是调用图中单独的节点，闭包和嵌套函数通过所在的外层函数被调用
+pub static ZERO: u32 = unsafe { std::mem::transmute(0i32) };
+
+pub struct Limits;
+
+impl Limits {
+    pub const MAX: usize = unsafe { std::mem::transmute(64isize) };
+}
+
+pub fn sum(ptrs: &[*const u8]) -> u32 {
+    ptrs.iter().map(|p| unsafe { **p } as u32).sum()
+}
+
+pub fn first(ptr: *const u8) -> u8 {
+    fn read(ptr: *const u8) -> u8 {
+        unsafe { *ptr }
+    }
+    let value = read(ptr);
+    value.wrapping_add(tail())
+}
+
+fn tail() -> u8 {
+    0
+}
+
+pub trait Source {
+    fn as_ptr(&self) -> *const u8;
+    
+    fn peek(&self) -> u8 {
+        unsafe { *self.as_ptr() }
+    }
+}
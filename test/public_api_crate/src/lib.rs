@@ -0,0 +1,21 @@
+// 公共API示例：只有 visible、exported、Thing::method 和 via_glob 可以从crate外部访问，
+// 私有模块中的 pub fn、pub(crate) 函数和私有trait的默认方法不是入口点
+#![allow(deref_nullptr)]
+
+mod private {
+    pub fn hidden() -> u8 { unsafe { *std::ptr::null::<u8>() } }
+    pub fn exported() -> u8 { unsafe { *std::ptr::null::<u8>() } }
+    pub struct Thing;
+    impl Thing { pub fn method(&self) -> u8 { unsafe { *std::ptr::null::<u8>() } } }
+    pub trait Tr { fn dflt(&self) -> u8 { unsafe { *std::ptr::null::<u8>() } } }
+}
+mod globbed {
+    pub fn via_glob() -> u8 { unsafe { *std::ptr::null::<u8>() } }
+}
+pub mod open {
+    pub fn visible() -> u8 { unsafe { *std::ptr::null::<u8>() } }
+    pub(crate) fn crate_only() -> u8 { unsafe { *std::ptr::null::<u8>() } }
+    mod closed { pub fn inner() -> u8 { unsafe { *std::ptr::null::<u8>() } } }
+}
+pub use private::{exported, Thing};
+pub use globbed::*;
@@ -0,0 +1,2 @@
+pub mod raw;
+pub use self::raw::Buf;
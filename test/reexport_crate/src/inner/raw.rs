@@ -0,0 +1,6 @@
+pub(crate) fn peek(i: usize) -> u8 { unsafe { *std::ptr::null::<u8>().add(i) } }
+pub struct Buf(*const u8);
+impl Buf {
+    pub(crate) fn new() -> Self { Buf(std::ptr::null()) }
+    pub(crate) fn get(&self) -> u8 { unsafe { *self.0 } }
+}
@@ -0,0 +1,21 @@
+// 导入示例：glob导入、pub use重导出、别名、块内use和super::路径的调用
+// 都应解析到 inner::raw 中定义的函数和方法
+mod inner;
+pub mod prelude {
+    pub use crate::inner::raw::*;
+    pub use crate::inner::Buf as Buffer;
+}
+use prelude::*;
+
+pub fn via_glob() -> u8 { peek(0) }
+pub fn via_prelude_path() -> u8 { prelude::peek(1) }
+pub fn via_alias() -> u8 { let b = prelude::Buffer::new(); b.get() }
+pub fn via_block() -> u8 {
+    use inner::raw::peek as p;
+    p(2)
+}
+pub mod nested {
+    pub fn via_super() -> u8 { super::inner::raw::peek(3) }
+    pub fn shadow() -> u8 { peek(4) }
+    fn peek(_: usize) -> u8 { 0 }
+}
@@ -0,0 +1,66 @@
+// 标准库unsafe API示例：调用按嵌入的数据库分类并标注危险类别。
+// 导入后只写函数名的调用经导入解析为完整路径，接收者类型已知的方法调用按类型查找，
+// 裸指针和切片接收者按 "*const T"、"[T]" 查找；
+// as_ptr、wrapping_add、mem::forget 等安全的API不会被报告；接收者是数据库之外的已知类型
+// （File::read）或调用不在unsafe上下文中（usize::add）时，同名的unsafe方法也不会被推测出来；
+// 与常见安全方法同名的方法（as_ref）在接收者类型未知时不按名称推测；core::arch 下的函数都是unsafe的
+use std::ffi::{c_char, CStr};
+use std::fs::File;
+use std::io::Read;
+use std::mem::transmute;
+use std::ops::Add;
+
+pub fn bits(value: f32) -> u32 {
+    unsafe { transmute(value) }
+}
+
+pub fn pick(values: Vec<u8>, index: usize) -> u8 {
+    unsafe { *values.get_unchecked(index) }
+}
+
+pub fn pick_slice(values: &[u8], index: usize) -> u8 {
+    unsafe { *values.get_unchecked(index) }
+}
+
+pub fn next_byte(pointer: *const u8) -> u8 {
+    unsafe { *pointer.add(1) }
+}
+
+pub fn first(values: &[u8]) -> u8 {
+    unsafe { *values.get_unchecked(0) }
+}
+
+pub fn name_length(name: *const c_char) -> usize {
+    unsafe { CStr::from_ptr(name) }.to_bytes().len()
+}
+
+pub fn reclaim(raw: *mut u64) -> u64 {
+    let boxed = unsafe { Box::from_raw(raw) };
+    boxed.wrapping_add(1)
+}
+
+pub fn leak(values: Vec<u8>) -> *const u8 {
+    let pointer = values.as_ptr();
+    std::mem::forget(values);
+    pointer
+}
+
+pub fn read_header(file: &mut File, buffer: &mut Vec<u8>) -> usize {
+    let length = 3usize.add(1);
+    unsafe {
+        buffer.set_len(length);
+        file.read(buffer).unwrap_or(0)
+    }
+}
+
+pub fn first_char(names: &[String]) -> u8 {
+    unsafe {
+        let name: &str = names.first().map(|name| name.as_ref()).unwrap_or("?");
+        *name.as_bytes().get_unchecked(0)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub fn zero_lanes() -> std::arch::x86_64::__m256 {
+    unsafe { std::arch::x86_64::_mm256_setzero_ps() }
+}
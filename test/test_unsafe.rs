@@ -41,7 +41,7 @@ fn private_with_unsafe_inside() -> *const i32 {
     }
 }
 
-// 例子6：公共函数，调用私有不安全函数（原代码会识别，但修改后不会）
+// 例子6：公共函数，调用私有不安全函数（应该被识别为两跳路径）
 pub fn public_calling_private_unsafe() -> *const i32 {
     private_with_unsafe_inside()
 }
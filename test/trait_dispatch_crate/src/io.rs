@@ -0,0 +1,12 @@
+pub trait Base { fn base(&self) -> u8; }
+pub trait Reader: Base {
+    fn read(&self) -> u8;
+    fn twice(&self) -> u8 where Self: Sized { self.read() + Self::helper() }
+    fn helper() -> u8 where Self: Sized { let ptr: *const u8 = &1; unsafe { *ptr } }
+}
+pub struct Fast(*const u8);
+pub struct Safe;
+impl Base for Fast { fn base(&self) -> u8 { 0 } }
+impl Base for Safe { fn base(&self) -> u8 { 0 } }
+impl Reader for Fast { fn read(&self) -> u8 { unsafe { *self.0 } } }
+impl Reader for Safe { fn read(&self) -> u8 { 1 } }
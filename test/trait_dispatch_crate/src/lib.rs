@@ -0,0 +1,12 @@
+// trait分发示例：泛型约束、dyn Trait、Box<dyn Trait>、impl Trait、Trait::method 和
+// <T as Trait>::method 的调用都应连接到 impl Reader for Fast 中的unsafe实现
+mod io;
+use io::Reader;
+
+pub fn generic<R: Reader>(r: &R) -> u8 { r.read() }
+pub fn dynamic(r: &dyn Reader) -> u8 { r.read() }
+pub fn boxed(r: Box<dyn Reader>) -> u8 { r.read() }
+pub fn ufcs<T>(r: &T) -> u8 where T: Reader { Reader::read(r) + T::read(r) }
+pub fn qualified(r: &io::Fast) -> u8 { <io::Fast as Reader>::read(r) }
+pub fn implicit(r: impl Reader) -> u8 { r.twice() }
+pub fn concrete(r: &io::Safe) -> u8 { r.read() }
@@ -0,0 +1,23 @@
+// Buffer 的方法：read/add 是安全方法，peek 声明为 unsafe fn
+pub struct Buffer {
+    len: usize,
+}
+
+impl Buffer {
+    pub fn new(len: usize) -> Self {
+        Buffer { len }
+    }
+
+    pub fn read(&self) -> usize {
+        self.len
+    }
+
+    pub fn add(&self, extra: usize) -> usize {
+        self.len + extra
+    }
+
+    /// 调用者需要保证 ptr 指向有效的 u8
+    pub unsafe fn peek(&self, ptr: *const u8) -> u8 {
+        unsafe { ptr.read() }
+    }
+}
@@ -0,0 +1,27 @@
+// unsafe fn 声明示例：调用按本crate的声明判断是否unsafe，而不是按名称猜测。
+// frobnicate 的名称看不出是unsafe，Buffer::read 和 Buffer::add 的名称像裸指针操作但其实是安全方法
+mod buffer;
+
+pub use buffer::Buffer;
+
+/// 调用者需要保证 ptr 指向有效的 u8
+pub unsafe fn frobnicate(ptr: *const u8) -> u8 {
+    unsafe { *ptr }
+}
+
+pub fn first(bytes: &[u8]) -> u8 {
+    unsafe { frobnicate(bytes.as_ptr()) }
+}
+
+pub fn total(buffer: &Buffer) -> usize {
+    unsafe { buffer.read() + buffer.add(1) }
+}
+
+pub fn skip(bytes: &[u8]) -> u8 {
+    let buffer = Buffer::new(bytes.len());
+    unsafe { buffer.peek(bytes.as_ptr()) }
+}
+
+pub fn copy_first(src: &[u8], dst: &mut [u8]) {
+    unsafe { std::ptr::copy(src.as_ptr(), dst.as_mut_ptr(), 1) }
+}
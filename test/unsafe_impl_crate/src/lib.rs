@@ -0,0 +1,31 @@
+// unsafe impl 和 unsafe trait 示例：Shared<T> 的Send实现缺少 T: Send 约束，
+// Sync实现以 T: Send 为约束，Token 的约束写在where子句中
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+
+pub struct Shared<T> {
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+pub struct Token<'a, T>(PhantomData<&'a T>);
+
+unsafe impl<'a, T> Sync for Token<'a, T> where T: Sync {}
+
+pub unsafe trait RawBuffer {
+    fn as_ptr(&self) -> *const u8;
+}
+
+unsafe impl RawBuffer for Vec<u8> {
+    fn as_ptr(&self) -> *const u8 {
+        self.as_slice().as_ptr()
+    }
+}
+
+mod handle {
+    pub struct Handle(*mut u8);
+    
+    unsafe impl Send for Handle {}
+}
@@ -0,0 +1,360 @@
+//! Runs the analyzer on the fixture crates under `test/` and checks the paths, unsafe
+//! operations and confidence levels it reports, and that the fixtures build with rustc.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use analysis::config::CfgConfig;
+use analysis::models::{Confidence, FileAnalysisResult, UnsafeApiCategory, UnsafeDeclarationKind, UnsafeOperation, UnsafeOperationType};
+use analysis::{AnalysisConfig, StaticAnalyzer, Verbosity, DEFAULT_FILE_SIZE_LIMIT, DEFAULT_MAX_SEARCH_DEPTH, DEFAULT_TIMEOUT_SECONDS};
+
+/// Fixtures that cannot be built on their own: the sources of `expanded_crate` derive with a
+/// procedural macro crate that is not part of the fixture, which is why it is analyzed expanded
+const UNBUILDABLE_FIXTURES: &[&str] = &["expanded_crate"];
+
+fn fixture_dir(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("test").join(name)
+}
+
+fn analyzer(config: AnalysisConfig) -> StaticAnalyzer {
+    StaticAnalyzer::new(DEFAULT_MAX_SEARCH_DEPTH, DEFAULT_FILE_SIZE_LIMIT, DEFAULT_TIMEOUT_SECONDS)
+        .with_verbosity(Verbosity::Quiet)
+        .with_config(config)
+        .expect("valid configuration")
+}
+
+/// Analyze a fixture for a fixed target, so that `#[cfg]` does not depend on the host
+fn analyze(name: &str) -> Vec<FileAnalysisResult> {
+    analyze_for(name, CfgConfig {
+        target: Some("x86_64-unknown-linux-gnu".to_string()),
+        ..CfgConfig::default()
+    })
+}
+
+fn analyze_for(name: &str, cfg: CfgConfig) -> Vec<FileAnalysisResult> {
+    let analyzer = analyzer(AnalysisConfig { cfg, ..AnalysisConfig::default() });
+    analyzer.analyze_directory_parallel(&fixture_dir(name)).expect("fixture is analyzed");
+    assert_eq!(analyzer.failed_files(), 0, "files of {} failed to parse", name);
+    analyzer.get_results()
+}
+
+/// Every reported path, written as `entry -> ... -> unsafe function`
+fn paths(results: &[FileAnalysisResult]) -> BTreeSet<String> {
+    results.iter()
+        .flat_map(|result| &result.paths)
+        .map(|path| path.iter().map(|node| node.full_path.as_str()).collect::<Vec<_>>().join(" -> "))
+        .collect()
+}
+
+fn assert_paths(results: &[FileAnalysisResult], expected: &[&str]) {
+    let expected: BTreeSet<String> = expected.iter().map(|path| path.to_string()).collect();
+    assert_eq!(paths(results), expected);
+}
+
+/// Operations reported in a function on any path
+fn operations<'a>(results: &'a [FileAnalysisResult], function: &str) -> Vec<&'a UnsafeOperation> {
+    let mut operations: Vec<&UnsafeOperation> = Vec::new();
+    for node in results.iter().flat_map(|result| &result.paths).flatten() {
+        if node.full_path == function {
+            for op in &node.unsafe_operations {
+                if !operations.contains(&op) {
+                    operations.push(op);
+                }
+            }
+        }
+    }
+    operations
+}
+
+/// Snippets are printed with spaces between all tokens; compare them without whitespace
+fn compact(code: &str) -> String {
+    code.split_whitespace().collect()
+}
+
+/// The operation on `code` in `function`, checked to have the given confidence
+fn operation<'a>(results: &'a [FileAnalysisResult], function: &str, code: &str, confidence: Confidence) -> &'a UnsafeOperation {
+    let found = operations(results, function);
+    let op = found.iter()
+        .find(|op| compact(&op.code_snippet) == compact(code))
+        .unwrap_or_else(|| panic!("no operation `{}` in {}, found {:?}", code, function,
+            found.iter().map(|op| &op.code_snippet).collect::<Vec<_>>()));
+    assert_eq!(op.confidence, confidence, "confidence of `{}` in {}", code, function);
+    op
+}
+
+fn assert_no_operation(results: &[FileAnalysisResult], function: &str, code: &str) {
+    assert!(operations(results, function).iter().all(|op| compact(&op.code_snippet) != compact(code)),
+        "unexpected operation `{}` in {}", code, function);
+}
+
+#[test]
+fn cross_file_crate() {
+    let results = analyze("cross_file_crate");
+    assert_paths(&results, &[
+        "crate::copy_all -> crate::io::copy_bytes",
+        "crate::first_byte -> crate::raw::read_first",
+    ]);
+    let copy = operation(&results, "crate::io::copy_bytes",
+        "std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), len)", Confidence::High);
+    assert_eq!(copy.category, Some(UnsafeApiCategory::RawPointer));
+    operation(&results, "crate::raw::read_first", "*ptr", Confidence::Low);
+}
+
+#[test]
+fn module_tree_crate() {
+    let results = analyze("module_tree_crate");
+    assert_paths(&results, &[
+        "crate::call_a -> crate::a::helper",
+        "crate::call_b -> crate::b::helper",
+        "crate::call_deep -> crate::b::deep::helper",
+        "crate::call_nested -> crate::outer::nested::helper",
+        "crate::call_sys -> crate::sys::helper",
+    ]);
+    operation(&results, "crate::b::deep::helper", "p.add(2)", Confidence::High);
+    operation(&results, "crate::sys::helper", "p.add(3)", Confidence::High);
+}
+
+#[test]
+fn unsafe_fn_crate() {
+    let results = analyze("unsafe_fn_crate");
+    assert_paths(&results, &["crate::copy_first", "crate::first", "crate::skip", "crate::total"]);
+    // 名称看不出unsafe的 unsafe fn 按声明判断
+    let call = operation(&results, "crate::first", "frobnicate(bytes.as_ptr())", Confidence::High);
+    assert_eq!(call.operation_type, UnsafeOperationType::UnsafeFunctionCall);
+    let method = operation(&results, "crate::skip", "buffer.peek(bytes.as_ptr())", Confidence::High);
+    assert_eq!(method.operation_type, UnsafeOperationType::UnsafeMethodCall);
+}
+
+#[test]
+fn public_api_crate() {
+    let results = analyze("public_api_crate");
+    assert_paths(&results, &[
+        "crate::globbed::via_glob",
+        "crate::open::visible",
+        "crate::private::Thing::method",
+        "crate::private::exported",
+    ]);
+}
+
+#[test]
+fn reexport_crate() {
+    let results = analyze("reexport_crate");
+    assert_paths(&results, &[
+        "crate::nested::via_super -> crate::inner::raw::peek",
+        "crate::via_alias -> crate::inner::raw::Buf::get",
+        "crate::via_block -> crate::inner::raw::peek",
+        "crate::via_glob -> crate::inner::raw::peek",
+        "crate::via_prelude_path -> crate::inner::raw::peek",
+    ]);
+}
+
+#[test]
+fn trait_dispatch_crate() {
+    let results = analyze("trait_dispatch_crate");
+    assert_paths(&results, &[
+        "crate::boxed -> crate::io::<Fast as Reader>::read",
+        "crate::dynamic -> crate::io::<Fast as Reader>::read",
+        "crate::generic -> crate::io::<Fast as Reader>::read",
+        "crate::implicit -> crate::io::Reader::twice -> crate::io::<Fast as Reader>::read",
+        "crate::implicit -> crate::io::Reader::twice -> crate::io::Reader::helper",
+        "crate::qualified -> crate::io::<Fast as Reader>::read",
+        "crate::ufcs -> crate::io::<Fast as Reader>::read",
+    ]);
+}
+
+#[test]
+fn nested_items_crate() {
+    let results = analyze("nested_items_crate");
+    assert_paths(&results, &[
+        "crate::Limits::MAX",
+        "crate::Source::peek",
+        "crate::ZERO",
+        "crate::first -> crate::first::read",
+        "crate::sum -> crate::sum::{closure#0}",
+    ]);
+    let transmute = operation(&results, "crate::ZERO", "std::mem::transmute(0i32)", Confidence::High);
+    assert_eq!(transmute.category, Some(UnsafeApiCategory::Transmute));
+}
+
+#[test]
+fn ffi_crate() {
+    let results = analyze("ffi_crate");
+    assert_paths(&results, &[
+        "crate::absolute",
+        "crate::exports::ffi_absolute -> crate::exports::checked_abs",
+        "crate::last_error",
+        "crate::length -> crate::measure",
+    ]);
+    let call = operation(&results, "crate::absolute", "sys::abs(value)", Confidence::High);
+    assert!(matches!(call.operation_type, UnsafeOperationType::FfiCall { .. }));
+    operation(&results, "crate::last_error", "sys::errno_value", Confidence::High);
+}
+
+#[test]
+fn low_level_crate() {
+    let results = analyze("low_level_crate");
+    assert_paths(&results, &[
+        "crate::counter_ptr",
+        "crate::float_bits",
+        "crate::local_only",
+        "crate::next_id -> crate::bump",
+        "crate::read_tsc",
+    ]);
+    let access = operation(&results, "crate::bump", "COUNTER", Confidence::High);
+    assert_eq!(access.operation_type, UnsafeOperationType::MutStaticAccess);
+    let read = operation(&results, "crate::float_bits", "bits.int", Confidence::High);
+    assert_eq!(read.operation_type, UnsafeOperationType::UnionFieldAccess);
+    
+    let declarations: Vec<_> = results.iter().flat_map(|result| &result.unsafe_declarations).collect();
+    assert!(declarations.iter().any(|declaration| declaration.kind == UnsafeDeclarationKind::GlobalAsm));
+}
+
+#[test]
+fn std_api_crate() {
+    let results = analyze("std_api_crate");
+    assert_paths(&results, &[
+        "crate::bits",
+        "crate::first",
+        "crate::first_char",
+        "crate::name_length",
+        "crate::next_byte",
+        "crate::pick",
+        "crate::pick_slice",
+        "crate::read_header",
+        "crate::reclaim",
+        "crate::zero_lanes",
+    ]);
+    // 接收者类型已知的方法按类型在数据库中确认：Vec经解引用到切片，裸指针和切片直接查找
+    for (function, code, category) in [
+        ("crate::bits", "transmute(value)", UnsafeApiCategory::Transmute),
+        ("crate::pick", "values.get_unchecked(index)", UnsafeApiCategory::UncheckedBounds),
+        ("crate::pick_slice", "values.get_unchecked(index)", UnsafeApiCategory::UncheckedBounds),
+        ("crate::first", "values.get_unchecked(0)", UnsafeApiCategory::UncheckedBounds),
+        ("crate::next_byte", "pointer.add(1)", UnsafeApiCategory::RawPointer),
+        ("crate::reclaim", "Box::from_raw(raw)", UnsafeApiCategory::Ownership),
+        ("crate::read_header", "buffer.set_len(length)", UnsafeApiCategory::Uninitialized),
+        ("crate::zero_lanes", "std::arch::x86_64::_mm256_setzero_ps()", UnsafeApiCategory::TargetFeature),
+    ] {
+        assert_eq!(operation(&results, function, code, Confidence::High).category, Some(category));
+    }
+    // File::read 和 usize::add 不是unsafe方法
+    assert_no_operation(&results, "crate::read_header", "file.read(buffer)");
+    assert_no_operation(&results, "crate::read_header", "3usize.add(1)");
+    // 接收者类型未知时 as_ref 不按名称推测，get_unchecked 推测为 low
+    assert_no_operation(&results, "crate::first_char", "name.as_ref()");
+    operation(&results, "crate::first_char", "name.as_bytes().get_unchecked(0)", Confidence::Low);
+}
+
+#[test]
+fn macro_crate() {
+    let results = analyze("macro_crate");
+    assert_paths(&results, &[
+        "crate::first",
+        "crate::header",
+        "crate::read_u32",
+        "crate::read_u8",
+        "crate::reset",
+        "crate::ring::Ring::front",
+        "crate::ring::Ring::second",
+    ]);
+    let clear = operation(&results, "crate::reset", "clear(buffer.as_mut_ptr(), buffer.len())", Confidence::High);
+    assert_eq!(clear.macro_origin.as_deref(), Some("zero!"));
+    // 子模块文件中展开 lib.rs 中在 mod 声明之前定义的宏
+    let front = operation(&results, "crate::ring::Ring::front",
+        "*self.slots.as_ptr().add((self.head + 0) % self.slots.len())", Confidence::Low);
+    assert_eq!(front.macro_origin.as_deref(), Some("accessor!"));
+    // #[macro_use] 模块中的宏在声明之后可用
+    let header = operation(&results, "crate::header",
+        "std::ptr::read_unaligned(bytes.as_ptr() as *const u32)", Confidence::High);
+    assert_eq!(header.macro_origin.as_deref(), Some("read_u32!"));
+}
+
+#[test]
+fn cfg_crate() {
+    let linux = analyze("cfg_crate");
+    assert_paths(&linux, &["crate::Buffer::get", "crate::aligned_len -> crate::page_size", "crate::huge_page_size"]);
+    let call = operation(&linux, "crate::page_size", "sysconf(30)", Confidence::High);
+    assert!(matches!(call.operation_type, UnsafeOperationType::FfiCall { .. }));
+    
+    let windows = analyze_for("cfg_crate", CfgConfig {
+        target: Some("x86_64-pc-windows-msvc".to_string()),
+        ..CfgConfig::default()
+    });
+    assert!(paths(&windows).contains("crate::aligned_len -> crate::page_size"));
+    assert!(!paths(&windows).contains("crate::huge_page_size"));
+    operation(&windows, "crate::page_size", "get_system_info(&mut info)", Confidence::High);
+    
+    // 分析所有配置时标注操作存在的条件
+    let all = analyze_for("cfg_crate", CfgConfig { all_configurations: true, ..CfgConfig::default() });
+    let checksum = operation(&all, "crate::checksum", "simd::checksum_wide(data.as_ptr(), data.len())", Confidence::High);
+    assert_eq!(checksum.cfg.as_deref(), Some("feature = \"simd\""));
+}
+
+#[test]
+fn unsafe_impl_crate() {
+    let results = analyze("unsafe_impl_crate");
+    let declarations: Vec<String> = results.iter()
+        .flat_map(|result| &result.unsafe_declarations)
+        .map(|declaration| format!("{} [{}]", declaration, declaration.missing_bounds.join(", ")))
+        .collect();
+    assert!(declarations.contains(&"unsafe impl Send for Shared<T> [T: Send]".to_string()), "{:?}", declarations);
+    assert!(declarations.contains(&"unsafe impl Sync for Shared<T> []".to_string()), "{:?}", declarations);
+    assert!(declarations.contains(&"unsafe trait RawBuffer []".to_string()), "{:?}", declarations);
+    // 实现类型的定义按类型名查找，Vec 不是本crate的类型
+    for declaration in results.iter().flat_map(|result| &result.unsafe_declarations) {
+        let in_crate = declaration.self_type_name.as_deref().is_some_and(|name| name != "Vec");
+        assert_eq!(declaration.type_definition.is_some(), in_crate, "type definition of {}", declaration);
+    }
+}
+
+#[test]
+fn expanded_crate() {
+    let dir = fixture_dir("expanded_crate");
+    let analyzer = analyzer(AnalysisConfig::default());
+    analyzer.analyze_expanded(&dir.join("expanded.rs"), Some(&dir.join("src"))).expect("expanded file is analyzed");
+    let results = analyzer.get_results();
+    assert_paths(&results, &["crate::sum", "crate::view::Bytes::get", "crate::view::Header::from_bytes"]);
+    
+    // derive宏生成的代码中的unsafe操作映射回原始源码
+    operation(&results, "crate::view::Header::from_bytes",
+        "::core::ptr::read_unaligned(bytes.as_ptr() as *const Self)", Confidence::High);
+    let declaration = results.iter()
+        .flat_map(|result| &result.unsafe_declarations)
+        .find(|declaration| declaration.trait_name.ends_with("Pod"))
+        .expect("unsafe impl generated by the derive");
+    assert!(declaration.file_path.ends_with("view.rs"), "{}", declaration.file_path);
+}
+
+/// The fixtures are meant to be valid Rust, so that what the analyzer reports is what rustc
+/// compiles; check them as libraries, with all their optional modules
+#[test]
+fn fixtures_build_with_rustc() {
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fixtures");
+    fs::create_dir_all(&out_dir).unwrap();
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(fixture_dir(""))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.join("src/lib.rs").is_file())
+        .collect();
+    fixtures.sort();
+    assert!(!fixtures.is_empty());
+    
+    for fixture in fixtures {
+        let name = fixture.file_name().unwrap().to_string_lossy().to_string();
+        if UNBUILDABLE_FIXTURES.contains(&name.as_str()) {
+            continue;
+        }
+        let output = Command::new(&rustc)
+            .args(["--edition", "2021", "--crate-type", "lib", "--emit", "metadata", "-A", "warnings"])
+            .args(["--crate-name", &name, "--cfg", "feature=\"simd\""])
+            .arg("--out-dir").arg(&out_dir)
+            .arg(fixture.join("src/lib.rs"))
+            .output()
+            .expect("rustc runs");
+        assert!(output.status.success(), "{} does not build:\n{}", name, String::from_utf8_lossy(&output.stderr));
+    }
+}